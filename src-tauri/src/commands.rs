@@ -1,7 +1,18 @@
+use std::path::Path;
 use serde::{Deserialize, Serialize};
 use tauri::State;
 use crate::AppState;
-use crate::stream::types::{Stream, StreamInput};
+use crate::db::backup::BackupInfo;
+use crate::i18n::Locale;
+use crate::stream::bulk::BulkReport;
+use crate::stream::manager::ManagerError;
+use crate::stream::query::{StreamPage, StreamQuery};
+use crate::stream::report::{ReportFormat, ReportQuery, UptimeReport};
+use crate::stream::transfer::{ExportReport, ImportOptions, ImportReport, TransferFormat};
+use crate::stream::types::{
+    Channel, ChannelInput, ChannelPatch, ScheduleConfig, Stream, StreamInput, StreamPatch, StreamSession, StreamTemplate,
+    Tag, TemplateInput,
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct InitializeParams {
@@ -12,43 +23,256 @@ pub struct InitializeParams {
 pub async fn initialize(
     state: State<'_, AppState>,
     instance_id: String,
-) -> Result<(), String> {
+) -> Result<(), ManagerError> {
     let mut id = state.instance_id.write().await;
     *id = Some(instance_id.clone());
     
-    let mut manager = state.stream_manager.write().await;
-    manager.initialize(&instance_id).await.map_err(|e| e.to_string())
+    state.stream_manager.initialize(&instance_id).await
 }
 
 #[tauri::command]
-pub async fn get_streams(state: State<'_, AppState>) -> Result<Vec<Stream>, String> {
-    let manager = state.stream_manager.read().await;
-    manager.get_streams().await.map_err(|e| e.to_string())
+pub async fn reveal_stream_key(state: State<'_, AppState>, id: String) -> Result<String, ManagerError> {
+    state.stream_manager.reveal_stream_key(&id).await
+}
+
+#[tauri::command]
+pub async fn rotate_master_key(state: State<'_, AppState>) -> Result<(), ManagerError> {
+    state.stream_manager.rotate_master_key().await
+}
+
+#[tauri::command]
+pub async fn list_backups(state: State<'_, AppState>) -> Result<Vec<BackupInfo>, ManagerError> {
+    state.stream_manager.list_backups().await
+}
+
+#[tauri::command]
+pub async fn create_backup(state: State<'_, AppState>) -> Result<BackupInfo, ManagerError> {
+    state.stream_manager.create_backup().await
+}
+
+#[tauri::command]
+pub async fn restore_backup(state: State<'_, AppState>, file_name: String) -> Result<(), ManagerError> {
+    state.stream_manager.restore_backup(&file_name).await
+}
+
+#[tauri::command]
+pub async fn get_locale(state: State<'_, AppState>) -> Result<Locale, ManagerError> {
+    Ok(state.stream_manager.get_locale())
+}
+
+#[tauri::command]
+pub async fn set_locale(state: State<'_, AppState>, locale: Locale) -> Result<(), ManagerError> {
+    state.stream_manager.set_locale(locale).await
+}
+
+#[tauri::command]
+pub async fn get_streams(state: State<'_, AppState>, query: Option<StreamQuery>) -> Result<StreamPage, ManagerError> {
+    state.stream_manager.get_streams(query.unwrap_or_default()).await
+}
+
+#[tauri::command]
+pub async fn get_stream_sessions(state: State<'_, AppState>, id: String) -> Result<Vec<StreamSession>, ManagerError> {
+    state.stream_manager.get_stream_sessions(&id).await
+}
+
+#[tauri::command]
+pub async fn get_uptime_report(state: State<'_, AppState>, query: ReportQuery) -> Result<UptimeReport, ManagerError> {
+    state.stream_manager.get_uptime_report(query).await
+}
+
+#[tauri::command]
+pub async fn export_uptime_report(
+    state: State<'_, AppState>,
+    query: ReportQuery,
+    format: ReportFormat,
+    path: String,
+) -> Result<(), ManagerError> {
+    state.stream_manager.export_uptime_report(query, format, Path::new(&path)).await
+}
+
+#[tauri::command]
+pub async fn export_streams(
+    state: State<'_, AppState>,
+    path: String,
+    format: TransferFormat,
+    redact_keys: Option<bool>,
+) -> Result<ExportReport, ManagerError> {
+    state.stream_manager.export_streams(Path::new(&path), format, redact_keys.unwrap_or(false)).await
+}
+
+#[tauri::command]
+pub async fn import_streams(
+    state: State<'_, AppState>,
+    path: String,
+    format: TransferFormat,
+    options: Option<ImportOptions>,
+) -> Result<ImportReport, ManagerError> {
+    state.stream_manager.import_streams(Path::new(&path), format, options.unwrap_or_default()).await
 }
 
 #[tauri::command]
 pub async fn add_stream(
     state: State<'_, AppState>,
     stream: StreamInput,
-) -> Result<Stream, String> {
-    let mut manager = state.stream_manager.write().await;
-    manager.add_stream(stream).await.map_err(|e| e.to_string())
+) -> Result<Stream, ManagerError> {
+    state.stream_manager.add_stream(stream).await
+}
+
+#[tauri::command]
+pub async fn update_stream(
+    state: State<'_, AppState>,
+    id: String,
+    patch: StreamPatch,
+    restart: Option<bool>,
+) -> Result<Stream, ManagerError> {
+    state.stream_manager.update_stream(&id, patch, restart.unwrap_or(false)).await
+}
+
+#[tauri::command]
+pub async fn get_channels(state: State<'_, AppState>) -> Result<Vec<Channel>, ManagerError> {
+    state.stream_manager.get_channels().await
+}
+
+#[tauri::command]
+pub async fn add_channel(state: State<'_, AppState>, input: ChannelInput) -> Result<Channel, ManagerError> {
+    state.stream_manager.add_channel(input).await
+}
+
+#[tauri::command]
+pub async fn update_channel(
+    state: State<'_, AppState>,
+    id: String,
+    patch: ChannelPatch,
+    restart: Option<bool>,
+) -> Result<Channel, ManagerError> {
+    state.stream_manager.update_channel(&id, patch, restart.unwrap_or(false)).await
+}
+
+#[tauri::command]
+pub async fn delete_channel(state: State<'_, AppState>, id: String) -> Result<(), ManagerError> {
+    state.stream_manager.delete_channel(&id).await
+}
+
+#[tauri::command]
+pub async fn start_stream(state: State<'_, AppState>, id: String) -> Result<(), ManagerError> {
+    state.stream_manager.start_stream(&id).await
+}
+
+#[tauri::command]
+pub async fn stop_stream(state: State<'_, AppState>, id: String) -> Result<(), ManagerError> {
+    state.stream_manager.stop_stream(&id).await
+}
+
+#[tauri::command]
+pub async fn delete_stream(state: State<'_, AppState>, id: String) -> Result<(), ManagerError> {
+    state.stream_manager.delete_stream(&id).await
+}
+
+#[tauri::command]
+pub async fn restore_stream(state: State<'_, AppState>, id: String) -> Result<Stream, ManagerError> {
+    state.stream_manager.restore_stream(&id).await
+}
+
+#[tauri::command]
+pub async fn purge_stream(state: State<'_, AppState>, id: String) -> Result<(), ManagerError> {
+    state.stream_manager.purge_stream(&id).await
+}
+
+#[tauri::command]
+pub async fn empty_archive(state: State<'_, AppState>) -> Result<usize, ManagerError> {
+    state.stream_manager.empty_archive().await
+}
+
+#[tauri::command]
+pub async fn get_archive_retention(state: State<'_, AppState>) -> Result<Option<u32>, ManagerError> {
+    state.stream_manager.get_archive_retention().await
+}
+
+#[tauri::command]
+pub async fn set_archive_retention(state: State<'_, AppState>, days: Option<u32>) -> Result<(), ManagerError> {
+    state.stream_manager.set_archive_retention(days).await
+}
+
+#[tauri::command]
+pub async fn restart_stream_from_beginning(state: State<'_, AppState>, id: String) -> Result<(), ManagerError> {
+    state.stream_manager.restart_from_beginning(&id).await
+}
+
+#[tauri::command]
+pub async fn clone_stream(state: State<'_, AppState>, id: String, overrides: Option<StreamPatch>) -> Result<Stream, ManagerError> {
+    state.stream_manager.clone_stream(&id, overrides.unwrap_or_default()).await
+}
+
+#[tauri::command]
+pub async fn get_templates(state: State<'_, AppState>) -> Result<Vec<StreamTemplate>, ManagerError> {
+    state.stream_manager.get_templates().await
+}
+
+#[tauri::command]
+pub async fn add_template(state: State<'_, AppState>, input: TemplateInput) -> Result<StreamTemplate, ManagerError> {
+    state.stream_manager.add_template(input).await
 }
 
 #[tauri::command]
-pub async fn start_stream(state: State<'_, AppState>, id: String) -> Result<(), String> {
-    let mut manager = state.stream_manager.write().await;
-    manager.start_stream(&id).await.map_err(|e| e.to_string())
+pub async fn update_template(
+    state: State<'_, AppState>,
+    id: String,
+    input: TemplateInput,
+) -> Result<StreamTemplate, ManagerError> {
+    state.stream_manager.update_template(&id, input).await
 }
 
 #[tauri::command]
-pub async fn stop_stream(state: State<'_, AppState>, id: String) -> Result<(), String> {
-    let mut manager = state.stream_manager.write().await;
-    manager.stop_stream(&id).await.map_err(|e| e.to_string())
+pub async fn delete_template(state: State<'_, AppState>, id: String) -> Result<(), ManagerError> {
+    state.stream_manager.delete_template(&id).await
+}
+
+#[tauri::command]
+pub async fn create_from_template(
+    state: State<'_, AppState>,
+    template_id: String,
+    overrides: StreamPatch,
+    start_immediately: Option<bool>,
+) -> Result<Stream, ManagerError> {
+    state.stream_manager.create_from_template(&template_id, overrides, start_immediately.unwrap_or(false)).await
+}
+
+#[tauri::command]
+pub async fn get_tags(state: State<'_, AppState>) -> Result<Vec<Tag>, ManagerError> {
+    state.stream_manager.get_tags().await
+}
+
+#[tauri::command]
+pub async fn tag_streams(state: State<'_, AppState>, ids: Vec<String>, tag: String) -> Result<u64, ManagerError> {
+    state.stream_manager.tag_streams(&ids, &tag).await
+}
+
+#[tauri::command]
+pub async fn untag_streams(state: State<'_, AppState>, ids: Vec<String>, tag: String) -> Result<u64, ManagerError> {
+    state.stream_manager.untag_streams(&ids, &tag).await
+}
+
+#[tauri::command]
+pub async fn bulk_start(state: State<'_, AppState>, tag: String) -> Result<BulkReport, ManagerError> {
+    state.stream_manager.bulk_start(&tag).await
+}
+
+#[tauri::command]
+pub async fn bulk_stop(state: State<'_, AppState>, tag: String) -> Result<BulkReport, ManagerError> {
+    state.stream_manager.bulk_stop(&tag).await
+}
+
+#[tauri::command]
+pub async fn bulk_reschedule(
+    state: State<'_, AppState>,
+    tag: String,
+    schedule: ScheduleConfig,
+    restart: Option<bool>,
+) -> Result<BulkReport, ManagerError> {
+    state.stream_manager.bulk_reschedule(&tag, schedule, restart.unwrap_or(false)).await
 }
 
 #[tauri::command]
-pub async fn delete_stream(state: State<'_, AppState>, id: String) -> Result<(), String> {
-    let mut manager = state.stream_manager.write().await;
-    manager.delete_stream(&id).await.map_err(|e| e.to_string())
+pub async fn bulk_delete(state: State<'_, AppState>, tag: String) -> Result<BulkReport, ManagerError> {
+    state.stream_manager.bulk_delete(&tag).await
 }
@@ -0,0 +1,195 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use chrono::SubsecRound;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::db::Database;
+
+/// Time between scheduled snapshots. One is also taken at startup.
+pub const BACKUP_INTERVAL: Duration = Duration::from_secs(6 * 3600);
+
+/// Snapshots kept per database - five days at the scheduled rate
+pub const BACKUPS_KEPT: usize = 20;
+
+const STAMP_FORMAT: &str = "%Y%m%dT%H%M%S%3f";
+
+#[derive(Error, Debug)]
+pub enum BackupError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Backup not found: {0}")]
+    NotFound(String),
+    #[error("Backup {file} is damaged: {error}")]
+    Damaged { file: String, error: String },
+}
+
+/// One snapshot of the database
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub created_at: String, // RFC 3339
+    pub size_bytes: u64,
+}
+
+/// Snapshots of `streams_abcd1234.db` are `backups/streams_abcd1234-<stamp>.db` beside it
+fn backup_dir(db_path: &Path) -> PathBuf {
+    db_path.with_file_name("backups")
+}
+
+fn prefix(db_path: &Path) -> String {
+    format!("{}-", db_path.file_stem().and_then(|s| s.to_str()).unwrap_or("streams"))
+}
+
+/// Path for a snapshot of the database taken now, creating the folder if need be
+pub fn next_path(db_path: &Path) -> std::io::Result<(PathBuf, chrono::DateTime<chrono::Utc>)> {
+    let dir = backup_dir(db_path);
+    std::fs::create_dir_all(&dir)?;
+
+    // To the millisecond, like the stamp `list` reads it back from
+    let now = chrono::Utc::now().trunc_subsecs(3);
+    Ok((dir.join(format!("{}{}.db", prefix(db_path), now.format(STAMP_FORMAT))), now))
+}
+
+/// Write a consistent copy of the live database, safe to take while streams are writing to it
+pub async fn snapshot(db: &Database) -> Result<BackupInfo, BackupError> {
+    let (path, now) = next_path(db.path())?;
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    db.snapshot_to(&path).await?;
+
+    Ok(BackupInfo {
+        file_name,
+        created_at: now.to_rfc3339(),
+        size_bytes: std::fs::metadata(&path)?.len(),
+    })
+}
+
+/// The database's snapshots, newest first. Other instances' snapshots share the folder and are left out.
+pub fn list(db_path: &Path) -> Result<Vec<BackupInfo>, BackupError> {
+    let entries = match std::fs::read_dir(backup_dir(db_path)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let prefix = prefix(db_path);
+    let mut backups = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let Some(stamp) = file_name.strip_prefix(&prefix).and_then(|rest| rest.strip_suffix(".db")) else {
+            continue;
+        };
+        let Ok(created_at) = chrono::NaiveDateTime::parse_from_str(stamp, STAMP_FORMAT) else {
+            continue;
+        };
+        backups.push(BackupInfo {
+            file_name,
+            created_at: created_at.and_utc().to_rfc3339(),
+            size_bytes: entry.metadata()?.len(),
+        });
+    }
+
+    // Stamps sort like the times they stand for
+    backups.sort_by(|a, b| b.file_name.cmp(&a.file_name));
+    Ok(backups)
+}
+
+/// Delete all but the newest `keep` snapshots
+pub fn prune(db_path: &Path, keep: usize) -> Result<usize, BackupError> {
+    let mut backups = list(db_path)?;
+    let expired = backups.split_off(keep.min(backups.len()));
+    for backup in &expired {
+        std::fs::remove_file(backup_dir(db_path).join(&backup.file_name))?;
+    }
+    Ok(expired.len())
+}
+
+/// Path of one of the database's snapshots. Only names `list` returns are accepted.
+pub fn find(db_path: &Path, file_name: &str) -> Result<PathBuf, BackupError> {
+    if !list(db_path)?.iter().any(|b| b.file_name == file_name) {
+        return Err(BackupError::NotFound(file_name.to_string()));
+    }
+    Ok(backup_dir(db_path).join(file_name))
+}
+
+/// Check a snapshot reads back whole, returning its schema version
+pub async fn verify(path: &Path) -> Result<u32, BackupError> {
+    let file = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let db = Database::new(path).await?;
+    let result = async {
+        let check = db.quick_check().await?;
+        if check != "ok" {
+            return Err(BackupError::Damaged { file: file.clone(), error: check });
+        }
+        Ok(db.schema_version().await?)
+    }
+    .await;
+    db.close().await;
+    result
+}
+
+/// Copy a snapshot beside the database, to be brought up to date there before `swap_in`
+pub fn stage(backup: &Path, db_path: &Path) -> Result<PathBuf, BackupError> {
+    let staged = db_path.with_extension("restoring");
+    std::fs::copy(backup, &staged)?;
+    Ok(staged)
+}
+
+/// Replace the database file with a staged one. The database must be closed.
+pub fn swap_in(staged: &Path, db_path: &Path) -> Result<(), BackupError> {
+    // Beside the target, so the rename that replaces it is atomic
+    std::fs::rename(staged, db_path)?;
+
+    // A write-ahead log left by the old file would be replayed into the new one
+    for suffix in ["-wal", "-shm"] {
+        let mut name = db_path.as_os_str().to_os_string();
+        name.push(suffix);
+        match std::fs::remove_file(PathBuf::from(name)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations;
+
+    fn scratch_db_path() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ylm-backup-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("streams_test.db")
+    }
+
+    #[tokio::test]
+    async fn test_snapshots_are_listed_newest_first_and_pruned() {
+        let path = scratch_db_path();
+        let db = Database::new(&path).await.unwrap();
+        db.migrate().await.unwrap();
+
+        let mut taken = Vec::new();
+        for _ in 0..3 {
+            taken.push(snapshot(&db).await.unwrap());
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        // Another instance's snapshot in the same folder
+        std::fs::write(backup_dir(&path).join("streams_other-20240507T100000000.db"), b"").unwrap();
+
+        let listed = list(&path).unwrap();
+        assert_eq!(listed.iter().map(|b| &b.file_name).collect::<Vec<_>>(), taken.iter().rev().map(|b| &b.file_name).collect::<Vec<_>>());
+        assert_eq!(verify(&find(&path, &listed[0].file_name).unwrap()).await.unwrap(), migrations::latest_version());
+        assert!(matches!(find(&path, "../streams_test.db"), Err(BackupError::NotFound(_))));
+
+        assert_eq!(prune(&path, 1).unwrap(), 2);
+        assert_eq!(list(&path).unwrap(), vec![listed[0].clone()]);
+
+        db.close().await;
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+}
@@ -1,10 +1,35 @@
-use std::path::Path;
-use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite, Row};
-use crate::stream::types::{Stream, StreamStatus, ScheduleConfig};
+use std::path::{Path, PathBuf};
+use sqlx::{sqlite::{SqlitePoolOptions, SqliteRow}, Pool, QueryBuilder, Sqlite, Row};
+use crate::db::migrations::{self, MigrationError};
+use crate::i18n::{Message, Params};
+use crate::stream::query::{Cursor, SortDirection, StreamQuery};
+use crate::stream::state::{Transition, TransitionOutcome};
+use crate::stream::edit;
+use crate::stream::types::{
+    Channel, ClipConfig, EncoderStats, FailureKind, MediaTrim, ResumeConfig, ScheduleConfig, SessionEndReason,
+    Stream, StreamSession, StreamStatus, StreamTemplate, Tag, TemplateSettings,
+};
+
+/// Streams with the key and name of their channel, and their tags as a JSON array
+const STREAM_SELECT: &str = "SELECT s.id, s.name, s.channel_id, c.name AS channel_name, c.youtube_key, c.masked_key, s.video_path, s.video_trim, s.ingest_endpoints, s.clips, s.status, s.status_reason, s.schedule, s.started_at, s.stopped_at, s.created_at, s.last_elapsed_seconds, s.live_seconds, s.last_seen_at, s.resume, s.playback_position, s.archived_at, (SELECT json_group_array(t.tag) FROM stream_tags t WHERE t.stream_id = s.id) AS tags FROM streams s LEFT JOIN channels c ON c.id = s.channel_id";
+
+const CHANNEL_SELECT: &str = "SELECT id, name, destination, youtube_key, masked_key, notes, created_at FROM channels";
+
+/// Templates with the name of their channel
+const TEMPLATE_SELECT: &str = "SELECT t.id, t.name, t.channel_id, c.name AS channel_name, t.settings, t.created_at FROM templates t LEFT JOIN channels c ON c.id = t.channel_id";
+
+/// What a heartbeat records about a live stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heartbeat {
+    pub elapsed_seconds: u64, // Uptime of the current FFmpeg process
+    pub live_seconds: u64,    // Live time across automatic restarts
+    pub playback_position: Option<f64>,
+}
 
 #[derive(Clone)]
 pub struct Database {
     pool: Pool<Sqlite>,
+    path: PathBuf,
 }
 
 impl Database {
@@ -21,219 +46,1201 @@ impl Database {
             .execute(&pool)
             .await?;
         
-        Ok(Self { pool })
-    }
-
-    pub async fn migrate(&self) -> Result<(), sqlx::Error> {
-        // Create table if not exists
-        sqlx::query(r#"
-            CREATE TABLE IF NOT EXISTS streams (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                youtube_key TEXT NOT NULL,
-                video_path TEXT NOT NULL,
-                status TEXT NOT NULL DEFAULT 'idle',
-                schedule TEXT NOT NULL,
-                started_at TEXT,
-                stopped_at TEXT,
-                created_at TEXT NOT NULL,
-                last_elapsed_seconds INTEGER
-            )
-        "#)
-        .execute(&self.pool)
-        .await?;
-        
-        // Add last_elapsed_seconds column if not exists (migration)
-        sqlx::query(r#"
-            ALTER TABLE streams ADD COLUMN last_elapsed_seconds INTEGER
-        "#)
-        .execute(&self.pool)
-        .await
-        .ok(); // Ignore error if column already exists
-        
+        Ok(Self { pool, path: path.to_path_buf() })
+    }
+
+    /// Apply pending schema migrations, refusing a database written by a newer version
+    pub async fn migrate(&self) -> Result<(), MigrationError> {
+        migrations::run(&self.pool, &self.path).await
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub async fn schema_version(&self) -> Result<u32, sqlx::Error> {
+        migrations::current_version(&self.pool).await
+    }
+
+    /// Write a consistent copy of the database to a new file at `path`
+    pub async fn snapshot_to(&self, path: &Path) -> Result<(), sqlx::Error> {
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.to_string_lossy().as_ref())
+            .execute(&self.pool)
+            .await?;
         Ok(())
     }
 
+    /// `ok`, or the first problems SQLite found in the file
+    pub async fn quick_check(&self) -> Result<String, sqlx::Error> {
+        let problems: Vec<String> = sqlx::query_scalar("PRAGMA quick_check")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(problems.join("; "))
+    }
+
+    /// Close every connection, checkpointing the write-ahead log into the file
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    #[cfg(test)]
+    pub(crate) fn pool(&self) -> &Pool<Sqlite> {
+        &self.pool
+    }
+
     pub async fn get_all_streams(&self) -> Result<Vec<Stream>, sqlx::Error> {
-        let rows = sqlx::query(
-            "SELECT id, name, youtube_key, video_path, status, schedule, started_at, stopped_at, created_at, last_elapsed_seconds FROM streams ORDER BY created_at DESC"
-        )
+        let rows = sqlx::query(&format!("{} ORDER BY s.created_at DESC", STREAM_SELECT))
         .fetch_all(&self.pool)
         .await?;
 
-        let streams = rows.iter().map(|row| {
-            let schedule_json: String = row.get("schedule");
-            let schedule: ScheduleConfig = serde_json::from_str(&schedule_json)
-                .unwrap_or_else(|_| ScheduleConfig {
-                    schedule_type: crate::stream::types::ScheduleType::Manual,
-                    duration: None,
-                    absolute: None,
-                });
-            
-            let status_str: String = row.get("status");
-            let status = match status_str.as_str() {
-                "live" => StreamStatus::Live,
-                "scheduled" => StreamStatus::Scheduled,
-                "completed" => StreamStatus::Completed,
-                "error" => StreamStatus::Error,
-                "stopping" => StreamStatus::Stopping,
-                _ => StreamStatus::Idle,
-            };
-
-            let last_elapsed: Option<i64> = row.get("last_elapsed_seconds");
-
-            Stream {
-                id: row.get("id"),
-                name: row.get("name"),
-                youtube_key: row.get("youtube_key"),
-                video_path: row.get("video_path"),
-                status,
-                schedule,
-                started_at: row.get("started_at"),
-                stopped_at: row.get("stopped_at"),
-                created_at: row.get("created_at"),
-                elapsed_seconds: None,
-                last_elapsed_seconds: last_elapsed.map(|v| v as u64),
-            }
-        }).collect();
-
-        Ok(streams)
+        Ok(rows.iter().map(Self::row_to_stream).collect())
     }
 
     pub async fn get_stream(&self, id: &str) -> Result<Option<Stream>, sqlx::Error> {
-        let row = sqlx::query(
-            "SELECT id, name, youtube_key, video_path, status, schedule, started_at, stopped_at, created_at, last_elapsed_seconds FROM streams WHERE id = ?"
-        )
+        let row = sqlx::query(&format!("{} WHERE s.id = ?", STREAM_SELECT))
         .bind(id)
         .fetch_optional(&self.pool)
         .await?;
 
-        Ok(row.map(|row| {
-            let schedule_json: String = row.get("schedule");
-            let schedule: ScheduleConfig = serde_json::from_str(&schedule_json)
-                .unwrap_or_else(|_| ScheduleConfig {
-                    schedule_type: crate::stream::types::ScheduleType::Manual,
-                    duration: None,
-                    absolute: None,
-                });
-            
-            let status_str: String = row.get("status");
-            let status = match status_str.as_str() {
-                "live" => StreamStatus::Live,
-                "scheduled" => StreamStatus::Scheduled,
-                "completed" => StreamStatus::Completed,
-                "error" => StreamStatus::Error,
-                "stopping" => StreamStatus::Stopping,
-                _ => StreamStatus::Idle,
-            };
-
-            let last_elapsed: Option<i64> = row.get("last_elapsed_seconds");
-
-            Stream {
-                id: row.get("id"),
-                name: row.get("name"),
-                youtube_key: row.get("youtube_key"),
-                video_path: row.get("video_path"),
-                status,
-                schedule,
-                started_at: row.get("started_at"),
-                stopped_at: row.get("stopped_at"),
-                created_at: row.get("created_at"),
-                elapsed_seconds: None,
-                last_elapsed_seconds: last_elapsed.map(|v| v as u64),
+        Ok(row.as_ref().map(Self::row_to_stream))
+    }
+
+    /// One page of the streams a query matches, and how many match in all.
+    /// `sealed_search` is the search text sealed by the vault, so a whole key finds its streams.
+    pub async fn query_streams(
+        &self,
+        query: &StreamQuery,
+        sealed_search: Option<&str>,
+        cursor: Option<&Cursor>,
+    ) -> Result<(Vec<Stream>, u64), sqlx::Error> {
+        let mut count = QueryBuilder::new("SELECT COUNT(*) FROM streams s LEFT JOIN channels c ON c.id = s.channel_id");
+        Self::push_stream_filters(&mut count, query, sealed_search);
+        let total: i64 = count.build_query_scalar().fetch_one(&self.pool).await?;
+
+        let mut select = QueryBuilder::new(STREAM_SELECT);
+        Self::push_stream_filters(&mut select, query, sealed_search);
+        let sort = query.sort.expression();
+        let (after, order) = match query.direction {
+            SortDirection::Asc => (">", "ASC"),
+            SortDirection::Desc => ("<", "DESC"),
+        };
+        if let Some(cursor) = cursor {
+            // Keyset paging: rows past the cursor's sort value, ties broken by id
+            select.push(" AND (");
+            select.push(format!("{sort} {after} ")).push_bind(cursor.value.clone());
+            select.push(format!(" OR ({sort} = ")).push_bind(cursor.value.clone());
+            select.push(format!(" AND s.id {after} ")).push_bind(cursor.id.clone());
+            select.push("))");
+        }
+        select.push(format!(" ORDER BY {sort} {order}, s.id {order}"));
+        if let Some(limit) = query.limit {
+            select.push(" LIMIT ").push_bind(limit as i64);
+        }
+
+        let rows = select.build().fetch_all(&self.pool).await?;
+        Ok((rows.iter().map(Self::row_to_stream).collect(), total as u64))
+    }
+
+    /// Add the query's WHERE clause
+    fn push_stream_filters(builder: &mut QueryBuilder<'_, Sqlite>, query: &StreamQuery, sealed_search: Option<&str>) {
+        // Either the archive or everything else, never both
+        builder.push(if query.archived { " WHERE s.archived_at IS NOT NULL" } else { " WHERE s.archived_at IS NULL" });
+
+        if !query.statuses.is_empty() {
+            builder.push(" AND s.status IN (");
+            let mut values = builder.separated(", ");
+            for status in &query.statuses {
+                values.push_bind(status.as_str());
             }
-        }))
+            builder.push(")");
+        }
+        if !query.schedule_types.is_empty() {
+            builder.push(" AND json_extract(s.schedule, '$.type') IN (");
+            let mut values = builder.separated(", ");
+            for schedule_type in &query.schedule_types {
+                values.push_bind(schedule_type.as_str());
+            }
+            builder.push(")");
+        }
+        if let Some(search) = query.search_text() {
+            let pattern = format!("%{}%", search.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+            builder.push(" AND (s.name LIKE ").push_bind(pattern.clone()).push(" ESCAPE '\\'");
+            builder.push(" OR c.name LIKE ").push_bind(pattern.clone()).push(" ESCAPE '\\'");
+            builder.push(" OR c.masked_key LIKE ").push_bind(pattern).push(" ESCAPE '\\'");
+            if let Some(sealed) = sealed_search {
+                builder.push(" OR c.youtube_key = ").push_bind(sealed.to_string());
+            }
+            builder.push(")");
+        }
+        if !query.tags.is_empty() {
+            builder.push(" AND EXISTS (SELECT 1 FROM stream_tags t WHERE t.stream_id = s.id AND t.tag IN (");
+            let mut values = builder.separated(", ");
+            for tag in &query.tags {
+                values.push_bind(tag.clone());
+            }
+            builder.push("))");
+        }
+
+        let bounds = [
+            ("s.created_at >= ", &query.created_from),
+            ("s.created_at < ", &query.created_to),
+            ("s.started_at >= ", &query.started_from),
+            ("s.started_at < ", &query.started_to),
+        ];
+        for (condition, bound) in bounds {
+            if let Some(bound) = bound {
+                builder.push(" AND ").push(condition).push_bind(bound.clone());
+            }
+        }
+    }
+
+    fn row_to_stream(row: &SqliteRow) -> Stream {
+        let schedule_json: String = row.get("schedule");
+        let schedule: ScheduleConfig = serde_json::from_str(&schedule_json)
+            .unwrap_or(ScheduleConfig {
+                schedule_type: crate::stream::types::ScheduleType::Manual,
+                duration: None,
+                absolute: None,
+                plays: None,
+            });
+
+        let status_str: String = row.get("status");
+        let status = StreamStatus::from_db(&status_str);
+
+        let trim_json: Option<String> = row.get("video_trim");
+        let trim: MediaTrim = trim_json
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        // Rows created before failover support have no endpoint list
+        let endpoints_json: Option<String> = row.get("ingest_endpoints");
+        let ingest_endpoints: Vec<String> = endpoints_json
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        let clips_json: Option<String> = row.get("clips");
+        let clips: ClipConfig = clips_json
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        let resume_json: Option<String> = row.get("resume");
+        let resume: ResumeConfig = resume_json
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        let tags_json: Option<String> = row.get("tags");
+        let tags: Vec<String> = tags_json
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        let last_elapsed: Option<i64> = row.get("last_elapsed_seconds");
+        let live_seconds: Option<i64> = row.get("live_seconds");
+
+        Stream {
+            id: row.get("id"),
+            name: row.get("name"),
+            channel_id: row.get::<Option<String>, _>("channel_id").unwrap_or_default(),
+            channel_name: row.get::<Option<String>, _>("channel_name").unwrap_or_default(),
+            youtube_key: row.get::<Option<String>, _>("youtube_key").unwrap_or_default(),
+            masked_key: row.get::<Option<String>, _>("masked_key").unwrap_or_default(),
+            video_path: row.get("video_path"),
+            trim,
+            ingest_endpoints,
+            clips,
+            status,
+            status_reason: Self::reason(row, "status_reason"),
+            schedule,
+            started_at: row.get("started_at"),
+            stopped_at: row.get("stopped_at"),
+            created_at: row.get("created_at"),
+            elapsed_seconds: None,
+            last_elapsed_seconds: last_elapsed.map(|v| v as u64),
+            live_seconds: live_seconds.map(|v| v as u64),
+            last_seen_at: row.get("last_seen_at"),
+            active_endpoint: None,
+            resume,
+            playback_position: row.get("playback_position"),
+            tags: edit::normalize_tags(&tags),
+            archived_at: row.get("archived_at"),
+        }
     }
 
     pub async fn insert_stream(&self, stream: &Stream) -> Result<(), sqlx::Error> {
         let schedule_json = serde_json::to_string(&stream.schedule)
             .unwrap_or_else(|_| "{}".to_string());
         
-        let status_str = match stream.status {
-            StreamStatus::Idle => "idle",
-            StreamStatus::Live => "live",
-            StreamStatus::Scheduled => "scheduled",
-            StreamStatus::Completed => "completed",
-            StreamStatus::Error => "error",
-            StreamStatus::Stopping => "stopping",
-        };
+        let trim_json = serde_json::to_string(&stream.trim)
+            .unwrap_or_else(|_| "{}".to_string());
+        let endpoints_json = serde_json::to_string(&stream.ingest_endpoints)
+            .unwrap_or_else(|_| "[]".to_string());
+        let clips_json = serde_json::to_string(&stream.clips)
+            .unwrap_or_else(|_| "{}".to_string());
+        let resume_json = serde_json::to_string(&stream.resume)
+            .unwrap_or_else(|_| "{}".to_string());
 
+        let mut tx = self.pool.begin().await?;
         sqlx::query(
-            "INSERT INTO streams (id, name, youtube_key, video_path, status, schedule, started_at, stopped_at, created_at, last_elapsed_seconds) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            "INSERT INTO streams (id, name, channel_id, video_path, video_trim, ingest_endpoints, clips, status, status_reason, schedule, started_at, stopped_at, created_at, last_elapsed_seconds, live_seconds, last_seen_at, resume, playback_position) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
         )
         .bind(&stream.id)
         .bind(&stream.name)
-        .bind(&stream.youtube_key)
+        .bind(&stream.channel_id)
         .bind(&stream.video_path)
-        .bind(status_str)
+        .bind(&trim_json)
+        .bind(&endpoints_json)
+        .bind(&clips_json)
+        .bind(stream.status.as_str())
+        .bind(&stream.status_reason)
         .bind(&schedule_json)
         .bind(&stream.started_at)
         .bind(&stream.stopped_at)
         .bind(&stream.created_at)
         .bind(stream.last_elapsed_seconds.map(|v| v as i64))
-        .execute(&self.pool)
+        .bind(stream.live_seconds.map(|v| v as i64))
+        .bind(&stream.last_seen_at)
+        .bind(&resume_json)
+        .bind(stream.playback_position)
+        .execute(&mut *tx)
+        .await?;
+        Self::replace_tags(&mut tx, &stream.id, &stream.tags).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Save a stream's settings. Status and run history are left alone.
+    pub async fn update_stream(&self, stream: &Stream) -> Result<(), sqlx::Error> {
+        let schedule_json = serde_json::to_string(&stream.schedule)
+            .unwrap_or_else(|_| "{}".to_string());
+        let trim_json = serde_json::to_string(&stream.trim)
+            .unwrap_or_else(|_| "{}".to_string());
+        let endpoints_json = serde_json::to_string(&stream.ingest_endpoints)
+            .unwrap_or_else(|_| "[]".to_string());
+        let clips_json = serde_json::to_string(&stream.clips)
+            .unwrap_or_else(|_| "{}".to_string());
+        let resume_json = serde_json::to_string(&stream.resume)
+            .unwrap_or_else(|_| "{}".to_string());
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "UPDATE streams SET name = ?, channel_id = ?, video_path = ?, video_trim = ?, ingest_endpoints = ?, clips = ?, schedule = ?, resume = ?, playback_position = ? WHERE id = ?"
+        )
+        .bind(&stream.name)
+        .bind(&stream.channel_id)
+        .bind(&stream.video_path)
+        .bind(&trim_json)
+        .bind(&endpoints_json)
+        .bind(&clips_json)
+        .bind(&schedule_json)
+        .bind(&resume_json)
+        .bind(stream.playback_position)
+        .bind(&stream.id)
+        .execute(&mut *tx)
         .await?;
+        Self::replace_tags(&mut tx, &stream.id, &stream.tags).await?;
+        tx.commit().await?;
 
         Ok(())
     }
 
-    pub async fn update_stream_status(&self, id: &str, status: StreamStatus) -> Result<(), sqlx::Error> {
-        let status_str = match status {
-            StreamStatus::Idle => "idle",
-            StreamStatus::Live => "live",
-            StreamStatus::Scheduled => "scheduled",
-            StreamStatus::Completed => "completed",
-            StreamStatus::Error => "error",
-            StreamStatus::Stopping => "stopping",
+    async fn replace_tags(tx: &mut sqlx::Transaction<'_, Sqlite>, stream_id: &str, tags: &[String]) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM stream_tags WHERE stream_id = ?")
+            .bind(stream_id)
+            .execute(&mut **tx)
+            .await?;
+        for tag in tags {
+            sqlx::query("INSERT OR IGNORE INTO stream_tags (stream_id, tag) VALUES (?, ?)")
+                .bind(stream_id)
+                .bind(tag)
+                .execute(&mut **tx)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Every tag in use, with how many streams carry it. Archived streams don't count.
+    pub async fn get_tags(&self) -> Result<Vec<Tag>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT MIN(t.tag) AS name, COUNT(*) AS stream_count FROM stream_tags t JOIN streams s ON s.id = t.stream_id WHERE s.archived_at IS NULL GROUP BY t.tag ORDER BY t.tag"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| Tag {
+            name: row.get("name"),
+            stream_count: row.get::<i64, _>("stream_count") as u64,
+        }).collect())
+    }
+
+    /// Tag streams, returning how many didn't already carry the tag
+    pub async fn add_tag(&self, stream_ids: &[String], tag: &str) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut added = 0;
+        for stream_id in stream_ids {
+            added += sqlx::query("INSERT OR IGNORE INTO stream_tags (stream_id, tag) SELECT id, ? FROM streams WHERE id = ?")
+                .bind(tag)
+                .bind(stream_id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        }
+        tx.commit().await?;
+        Ok(added)
+    }
+
+    /// Untag streams, returning how many carried the tag
+    pub async fn remove_tag(&self, stream_ids: &[String], tag: &str) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut removed = 0;
+        for stream_id in stream_ids {
+            removed += sqlx::query("DELETE FROM stream_tags WHERE stream_id = ? AND tag = ?")
+                .bind(stream_id)
+                .bind(tag)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        }
+        tx.commit().await?;
+        Ok(removed)
+    }
+
+    /// Apply a status transition and everything recorded with it in one transaction.
+    /// The transition is checked against the table and the status the row has right now.
+    pub async fn transition_stream(&self, id: &str, transition: &Transition) -> Result<TransitionOutcome, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query("SELECT status FROM streams WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        let Some(row) = row else {
+            return Ok(TransitionOutcome::NotFound);
         };
 
-        sqlx::query("UPDATE streams SET status = ? WHERE id = ?")
-            .bind(status_str)
+        let status_str: String = row.get("status");
+        let from = StreamStatus::from_db(&status_str);
+        if !from.can_transition_to(&transition.to) {
+            return Ok(TransitionOutcome::Rejected { from });
+        }
+
+        let now = chrono::Utc::now();
+        let scheduled_end = transition.scheduled_seconds
+            .map(|seconds| (now + chrono::Duration::seconds(seconds as i64)).to_rfc3339());
+        let now = now.to_rfc3339();
+        let result = sqlx::query(
+            "UPDATE streams SET status = ?, status_reason = ?, \
+             started_at = CASE WHEN ? THEN ? ELSE started_at END, \
+             stopped_at = CASE WHEN ? THEN ? WHEN ? THEN NULL ELSE stopped_at END, \
+             last_elapsed_seconds = COALESCE(?, last_elapsed_seconds), \
+             live_seconds = COALESCE(?, CASE WHEN ? THEN NULL ELSE live_seconds END), \
+             last_seen_at = CASE WHEN ? THEN ? ELSE last_seen_at END, \
+             playback_position = CASE WHEN ? THEN ? ELSE playback_position END \
+             WHERE id = ? AND status = ?"
+        )
+        .bind(transition.to.as_str())
+        .bind(transition.reason.as_ref().map(Message::to_db))
+        .bind(transition.started)
+        .bind(&now)
+        .bind(transition.stopped)
+        .bind(&now)
+        .bind(transition.started)
+        .bind(transition.last_elapsed_seconds.map(|v| v as i64))
+        .bind(transition.live_seconds.map(|v| v as i64))
+        .bind(transition.started)
+        .bind(transition.started)
+        .bind(&now)
+        .bind(transition.playback_position.is_some())
+        .bind(transition.playback_position.flatten())
+        .bind(id)
+        .bind(from.as_str())
+        .execute(&mut *tx)
+        .await?;
+
+        // Someone else moved the stream on between the read and the write
+        if result.rows_affected() == 0 {
+            return Ok(TransitionOutcome::Rejected { from });
+        }
+
+        if transition.started {
+            sqlx::query("INSERT INTO sessions (id, stream_id, started_at, last_seen_at, scheduled_end) VALUES (?, ?, ?, ?, ?)")
+                .bind(uuid::Uuid::new_v4().to_string())
+                .bind(id)
+                .bind(&now)
+                .bind(&now)
+                .bind(&scheduled_end)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        if let Some(end) = &transition.session_end {
+            let stats_json = end.encoder_stats.as_ref()
+                .and_then(|stats| serde_json::to_string(stats).ok());
+            let log_json = serde_json::to_string(&end.log_tail)
+                .unwrap_or_else(|_| "[]".to_string());
+
+            sqlx::query(
+                "UPDATE sessions SET ended_at = ?, end_reason = ?, failure_kind = ?, failure_reason = ?, \
+                 elapsed_seconds = ?, live_seconds = COALESCE(?, live_seconds), encoder_stats = ?, log_tail = ? \
+                 WHERE stream_id = ? AND ended_at IS NULL"
+            )
+            .bind(&now)
+            .bind(end.reason.as_str())
+            .bind(end.failure_kind.map(|kind| kind.as_str()))
+            .bind(transition.reason.as_ref().map(Message::to_db))
+            .bind(transition.last_elapsed_seconds.map(|v| v as i64))
+            .bind(transition.live_seconds.map(|v| v as i64))
+            .bind(stats_json)
+            .bind(log_json)
             .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(TransitionOutcome::Applied { from })
+    }
+
+    /// Record that a live stream is still up, how long for, and where its media is.
+    /// What an unclean shutdown leaves behind is then at most one heartbeat out of date.
+    pub async fn heartbeat(&self, id: &str, heartbeat: &Heartbeat) -> Result<(), sqlx::Error> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "UPDATE streams SET last_elapsed_seconds = ?, live_seconds = ?, last_seen_at = ?, \
+             playback_position = COALESCE(?, playback_position) \
+             WHERE id = ? AND status = 'live'"
+        )
+        .bind(heartbeat.elapsed_seconds as i64)
+        .bind(heartbeat.live_seconds as i64)
+        .bind(&now)
+        .bind(heartbeat.playback_position)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "UPDATE sessions SET elapsed_seconds = ?, live_seconds = ?, last_seen_at = ? \
+             WHERE stream_id = ? AND ended_at IS NULL"
+        )
+        .bind(heartbeat.elapsed_seconds as i64)
+        .bind(heartbeat.live_seconds as i64)
+        .bind(&now)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Move the deadline of the stream's open session after its schedule was edited
+    pub async fn update_session_schedule(&self, stream_id: &str, scheduled_seconds: Option<u64>) -> Result<(), sqlx::Error> {
+        let scheduled_end = scheduled_seconds
+            .map(|seconds| (chrono::Utc::now() + chrono::Duration::seconds(seconds as i64)).to_rfc3339());
+
+        sqlx::query("UPDATE sessions SET scheduled_end = ? WHERE stream_id = ? AND ended_at IS NULL")
+            .bind(scheduled_end)
+            .bind(stream_id)
             .execute(&self.pool)
             .await?;
 
         Ok(())
     }
 
-    pub async fn update_stream_started_at(&self, id: &str) -> Result<(), sqlx::Error> {
-        let now = chrono::Utc::now().to_rfc3339();
-        sqlx::query("UPDATE streams SET started_at = ? WHERE id = ?")
-            .bind(&now)
-            .bind(id)
+    /// Count a crash recovery against the stream's open session, and when it happened
+    pub async fn record_session_restart(&self, stream_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE sessions SET restarts = restarts + 1, \
+             restart_times = json_insert(COALESCE(restart_times, '[]'), '$[#]', ?) \
+             WHERE stream_id = ? AND ended_at IS NULL"
+        )
+            .bind(chrono::Utc::now().to_rfc3339())
+            .bind(stream_id)
             .execute(&self.pool)
             .await?;
 
         Ok(())
     }
 
-    pub async fn update_stream_stopped_at(&self, id: &str) -> Result<(), sqlx::Error> {
+    /// Every session of a stream, newest first
+    pub async fn get_stream_sessions(&self, stream_id: &str) -> Result<Vec<StreamSession>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT id, stream_id, started_at, ended_at, scheduled_end, end_reason, failure_kind, failure_reason, elapsed_seconds, live_seconds, last_seen_at, restarts, restart_times, encoder_stats, log_tail FROM sessions WHERE stream_id = ? ORDER BY started_at DESC"
+        )
+        .bind(stream_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::row_to_session).collect())
+    }
+
+    /// Sessions of every stream that overlap `[from, to)`, oldest first.
+    /// Both bounds are RFC 3339 UTC timestamps, which sort as text.
+    pub async fn get_sessions_between(&self, from: &str, to: &str) -> Result<Vec<StreamSession>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT id, stream_id, started_at, ended_at, scheduled_end, end_reason, failure_kind, failure_reason, elapsed_seconds, live_seconds, last_seen_at, restarts, restart_times, encoder_stats, log_tail FROM sessions WHERE started_at < ? AND (ended_at IS NULL OR ended_at > ?) ORDER BY started_at"
+        )
+        .bind(to)
+        .bind(from)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::row_to_session).collect())
+    }
+
+    /// A stored reason in the operator's current language
+    fn reason(row: &SqliteRow, column: &str) -> Option<String> {
+        let stored: Option<String> = row.get(column);
+        stored.map(|value| Message::from_db(&value).localized())
+    }
+
+    fn row_to_session(row: &SqliteRow) -> StreamSession {
+        let end_reason: Option<String> = row.get("end_reason");
+        let failure_kind: Option<String> = row.get("failure_kind");
+        let elapsed: Option<i64> = row.get("elapsed_seconds");
+        let live_seconds: Option<i64> = row.get("live_seconds");
+        let restarts: i64 = row.get("restarts");
+        let restart_json: Option<String> = row.get("restart_times");
+        let restart_times: Vec<String> = restart_json
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        let stats_json: Option<String> = row.get("encoder_stats");
+        let encoder_stats: Option<EncoderStats> = stats_json
+            .and_then(|json| serde_json::from_str(&json).ok());
+
+        let log_json: Option<String> = row.get("log_tail");
+        let log_tail: Vec<String> = log_json
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        StreamSession {
+            id: row.get("id"),
+            stream_id: row.get("stream_id"),
+            started_at: row.get("started_at"),
+            ended_at: row.get("ended_at"),
+            scheduled_end: row.get("scheduled_end"),
+            end_reason: end_reason.as_deref().and_then(SessionEndReason::from_db),
+            failure_kind: failure_kind.as_deref().and_then(FailureKind::from_db),
+            failure_reason: Self::reason(row, "failure_reason"),
+            elapsed_seconds: elapsed.map(|v| v as u64),
+            live_seconds: live_seconds.map(|v| v as u64),
+            last_seen_at: row.get("last_seen_at"),
+            restarts: restarts as u32,
+            restart_times,
+            encoder_stats,
+            log_tail,
+        }
+    }
+
+    /// Mark streams still recorded as live or stopping as errored and close their sessions
+    /// as of their last heartbeat. Used at startup, when no FFmpeg process from a previous run can still exist.
+    pub async fn mark_interrupted_streams(&self) -> Result<u64, sqlx::Error> {
         let now = chrono::Utc::now().to_rfc3339();
-        sqlx::query("UPDATE streams SET stopped_at = ? WHERE id = ?")
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query(
+            "UPDATE streams SET status = 'error', status_reason = ?, stopped_at = COALESCE(stopped_at, last_seen_at, ?) WHERE status IN ('live', 'stopping')"
+        )
+        .bind(Message::new("reason.interrupted", &Params::new()).to_db())
+        .bind(&now)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE sessions SET ended_at = COALESCE(last_seen_at, ?), end_reason = ? WHERE ended_at IS NULL")
             .bind(&now)
+            .bind(SessionEndReason::Interrupted.as_str())
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Every channel's stored key, as `(id, youtube_key, masked_key)`
+    pub async fn get_channel_keys(&self) -> Result<Vec<(String, String, Option<String>)>, sqlx::Error> {
+        sqlx::query_as("SELECT id, youtube_key, masked_key FROM channels")
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Store new values of the channel keys all at once, e.g. resealed under a new master key
+    pub async fn replace_channel_keys(&self, keys: &[(String, String, String)]) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        for (id, key, masked) in keys {
+            sqlx::query("UPDATE channels SET youtube_key = ?, masked_key = ? WHERE id = ?")
+                .bind(key)
+                .bind(masked)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Every channel with the ids of the streams publishing to it
+    pub async fn get_channels(&self) -> Result<Vec<Channel>, sqlx::Error> {
+        let rows = sqlx::query(&format!("{} ORDER BY name", CHANNEL_SELECT))
+            .fetch_all(&self.pool)
+            .await?;
+        let usage: Vec<(String, String)> = sqlx::query_as(
+            "SELECT channel_id, id FROM streams WHERE channel_id IS NOT NULL ORDER BY created_at"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(|row| {
+            let mut channel = Self::row_to_channel(row);
+            channel.stream_ids = usage.iter()
+                .filter(|(channel_id, _)| *channel_id == channel.id)
+                .map(|(_, stream_id)| stream_id.clone())
+                .collect();
+            channel
+        }).collect())
+    }
+
+    pub async fn get_channel(&self, id: &str) -> Result<Option<Channel>, sqlx::Error> {
+        let row = sqlx::query(&format!("{} WHERE id = ?", CHANNEL_SELECT))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        let Some(mut channel) = row.as_ref().map(Self::row_to_channel) else {
+            return Ok(None);
+        };
+
+        channel.stream_ids = sqlx::query_scalar("SELECT id FROM streams WHERE channel_id = ? ORDER BY created_at")
             .bind(id)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(Some(channel))
+    }
+
+    /// The channel a sealed key belongs to, if any - keys are unique across channels
+    pub async fn get_channel_by_key(&self, sealed: &str) -> Result<Option<Channel>, sqlx::Error> {
+        let row = sqlx::query(&format!("{} WHERE youtube_key = ?", CHANNEL_SELECT))
+            .bind(sealed)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().map(Self::row_to_channel))
+    }
+
+    fn row_to_channel(row: &SqliteRow) -> Channel {
+        Channel {
+            id: row.get("id"),
+            name: row.get("name"),
+            destination: row.get("destination"),
+            youtube_key: row.get("youtube_key"),
+            masked_key: row.get::<Option<String>, _>("masked_key").unwrap_or_default(),
+            notes: row.get("notes"),
+            created_at: row.get("created_at"),
+            stream_ids: Vec::new(),
+        }
+    }
+
+    pub async fn insert_channel(&self, channel: &Channel) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO channels (id, name, destination, youtube_key, masked_key, notes, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&channel.id)
+        .bind(&channel.name)
+        .bind(&channel.destination)
+        .bind(&channel.youtube_key)
+        .bind(&channel.masked_key)
+        .bind(&channel.notes)
+        .bind(&channel.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_channel(&self, channel: &Channel) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE channels SET name = ?, destination = ?, youtube_key = ?, masked_key = ?, notes = ? WHERE id = ?"
+        )
+        .bind(&channel.name)
+        .bind(&channel.destination)
+        .bind(&channel.youtube_key)
+        .bind(&channel.masked_key)
+        .bind(&channel.notes)
+        .bind(&channel.id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delete a channel. Templates that used it leave the channel to each stream.
+    pub async fn delete_channel(&self, id: &str) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("UPDATE templates SET channel_id = NULL WHERE channel_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM channels WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn get_templates(&self) -> Result<Vec<StreamTemplate>, sqlx::Error> {
+        let rows = sqlx::query(&format!("{} ORDER BY t.name COLLATE NOCASE", TEMPLATE_SELECT))
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().filter_map(Self::row_to_template).collect())
+    }
+
+    pub async fn get_template(&self, id: &str) -> Result<Option<StreamTemplate>, sqlx::Error> {
+        let row = sqlx::query(&format!("{} WHERE t.id = ?", TEMPLATE_SELECT))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().and_then(Self::row_to_template))
+    }
+
+    /// None for a row whose settings no longer read back, which is logged and left out
+    fn row_to_template(row: &SqliteRow) -> Option<StreamTemplate> {
+        let id: String = row.get("id");
+        let settings_json: String = row.get("settings");
+        let settings: TemplateSettings = match serde_json::from_str(&settings_json) {
+            Ok(settings) => settings,
+            Err(e) => {
+                tracing::warn!("Skipping template {} with unreadable settings: {}", id, e);
+                return None;
+            }
+        };
+
+        Some(StreamTemplate {
+            id,
+            name: row.get("name"),
+            channel_id: row.get("channel_id"),
+            channel_name: row.get("channel_name"),
+            settings,
+            created_at: row.get("created_at"),
+        })
+    }
+
+    pub async fn insert_template(&self, template: &StreamTemplate) -> Result<(), sqlx::Error> {
+        let settings_json = serde_json::to_string(&template.settings)
+            .unwrap_or_else(|_| "{}".to_string());
+
+        sqlx::query("INSERT INTO templates (id, name, channel_id, settings, created_at) VALUES (?, ?, ?, ?, ?)")
+            .bind(&template.id)
+            .bind(&template.name)
+            .bind(&template.channel_id)
+            .bind(&settings_json)
+            .bind(&template.created_at)
             .execute(&self.pool)
             .await?;
 
         Ok(())
     }
 
-    pub async fn update_stream_last_elapsed(&self, id: &str, seconds: u64) -> Result<(), sqlx::Error> {
-        sqlx::query("UPDATE streams SET last_elapsed_seconds = ? WHERE id = ?")
-            .bind(seconds as i64)
+    pub async fn update_template(&self, template: &StreamTemplate) -> Result<(), sqlx::Error> {
+        let settings_json = serde_json::to_string(&template.settings)
+            .unwrap_or_else(|_| "{}".to_string());
+
+        sqlx::query("UPDATE templates SET name = ?, channel_id = ?, settings = ? WHERE id = ?")
+            .bind(&template.name)
+            .bind(&template.channel_id)
+            .bind(&settings_json)
+            .bind(&template.id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Returns false if there was no such template
+    pub async fn delete_template(&self, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM templates WHERE id = ?")
             .bind(id)
             .execute(&self.pool)
             .await?;
 
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_setting(&self, key: &str) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT value FROM settings WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    pub async fn set_setting(&self, key: &str, value: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO settings (key, value) VALUES (?, ?) ON CONFLICT(key) DO UPDATE SET value = excluded.value")
+            .bind(key)
+            .bind(value)
+            .execute(&self.pool)
+            .await?;
+
         Ok(())
     }
 
+    /// Move a stream to the archive, returning false if it was already there or doesn't exist
+    pub async fn archive_stream(&self, id: &str, archived_at: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("UPDATE streams SET archived_at = ? WHERE id = ? AND archived_at IS NULL")
+            .bind(archived_at)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Bring a stream back from the archive, returning false if it wasn't archived or doesn't exist
+    pub async fn unarchive_stream(&self, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("UPDATE streams SET archived_at = NULL WHERE id = ? AND archived_at IS NOT NULL")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Ids of streams archived before `cutoff`, or of every archived stream without one
+    pub async fn get_archived_ids(&self, cutoff: Option<&str>) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT id FROM streams WHERE archived_at IS NOT NULL AND (? IS NULL OR archived_at < ?)")
+            .bind(cutoff)
+            .bind(cutoff)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Erase a stream for good. Its sessions stay for the reports.
     pub async fn delete_stream(&self, id: &str) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM stream_tags WHERE stream_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
         sqlx::query("DELETE FROM streams WHERE id = ?")
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await?;
+        tx.commit().await?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::query::StreamSort;
+    use crate::stream::state::SessionEnd;
+
+    /// A migrated database in a fresh directory
+    async fn database() -> Database {
+        let dir = std::env::temp_dir().join(format!("ylm-database-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = Database::new(&dir.join("streams.db")).await.unwrap();
+        db.migrate().await.unwrap();
+        db
+    }
+
+    async fn insert_channel(db: &Database, id: &str) {
+        let channel = Channel {
+            id: id.into(),
+            name: format!("Channel {}", id),
+            destination: None,
+            youtube_key: format!("enc:v1:k:{}", id),
+            masked_key: "••••".into(),
+            notes: String::new(),
+            created_at: "2024-05-07T10:00:00+00:00".into(),
+            stream_ids: Vec::new(),
+        };
+        db.insert_channel(&channel).await.unwrap();
+    }
+
+    async fn insert_stream(db: &Database, id: &str, channel_id: &str) -> Stream {
+        let stream = stream(id, channel_id);
+        db.insert_stream(&stream).await.unwrap();
+        stream
+    }
+
+    fn stream(id: &str, channel_id: &str) -> Stream {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": format!("Stream {}", id),
+            "channelId": channel_id,
+            "youtubeKey": "",
+            "videoPath": "/videos/lofi.mp4",
+            "status": "idle",
+            "schedule": { "type": "manual", "duration": null, "absolute": null },
+            "startedAt": null,
+            "stoppedAt": null,
+            "createdAt": "2024-05-07T10:00:00+00:00",
+        }))
+        .unwrap()
+    }
+
+    /// Ids of every stream the query matches, following the cursor page by page
+    async fn page_through(db: &Database, query: &StreamQuery) -> Vec<String> {
+        let limit = query.limit.unwrap() as usize;
+        let mut ids = Vec::new();
+        let mut cursor = None;
+        loop {
+            let (page, _) = db.query_streams(query, None, cursor.as_ref()).await.unwrap();
+            ids.extend(page.iter().map(|s| s.id.clone()));
+            match page.last() {
+                Some(last) if page.len() == limit => cursor = Some(Cursor::after(last, query.sort, query.direction)),
+                _ => return ids,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_keyset_pages_return_every_stream_once() {
+        let db = database().await;
+        insert_channel(&db, "ch").await;
+        // Names differing only in case tie under NOCASE, like the three equal creation times
+        for (id, name, created_at) in [
+            ("a", "lofi", "2024-05-07T10:00:00+00:00"),
+            ("b", "Lofi", "2024-05-07T10:00:00+00:00"),
+            ("c", "Ambient", "2024-05-07T10:00:00+00:00"),
+            ("d", "rain", "2024-05-08T10:00:00+00:00"),
+            ("e", "Beats", "2024-05-06T10:00:00+00:00"),
+        ] {
+            let mut stream = stream(id, "ch");
+            stream.name = name.into();
+            stream.created_at = created_at.into();
+            db.insert_stream(&stream).await.unwrap();
+        }
+
+        let query = |sort, direction| StreamQuery { sort, direction, limit: Some(2), ..Default::default() };
+        assert_eq!(page_through(&db, &query(StreamSort::CreatedAt, SortDirection::Desc)).await, ["d", "c", "b", "a", "e"]);
+        assert_eq!(page_through(&db, &query(StreamSort::CreatedAt, SortDirection::Asc)).await, ["e", "a", "b", "c", "d"]);
+        assert_eq!(page_through(&db, &query(StreamSort::Name, SortDirection::Asc)).await, ["c", "e", "a", "b", "d"]);
+
+        let (page, total) = db.query_streams(&query(StreamSort::Name, SortDirection::Asc), None, None).await.unwrap();
+        assert_eq!((page.len(), total), (2, 5));
+    }
+
+    #[tokio::test]
+    async fn test_query_filters_combine() {
+        let db = database().await;
+        insert_channel(&db, "ch").await;
+        for (id, status, tags) in [("a", "live", vec!["client a"]), ("b", "idle", vec!["client a", "lofi"]), ("c", "idle", vec![])] {
+            let mut stream = stream(id, "ch");
+            stream.status = serde_json::from_value(serde_json::json!(status)).unwrap();
+            stream.tags = tags.into_iter().map(str::to_string).collect();
+            db.insert_stream(&stream).await.unwrap();
+        }
+        db.archive_stream("c", "2024-05-09T10:00:00+00:00").await.unwrap();
+
+        let ids = |query: StreamQuery| {
+            let db = db.clone();
+            async move {
+                let (streams, total) = db.query_streams(&query, None, None).await.unwrap();
+                assert_eq!(total as usize, streams.len());
+                let mut ids: Vec<String> = streams.into_iter().map(|s| s.id).collect();
+                ids.sort();
+                ids
+            }
+        };
+
+        assert_eq!(ids(StreamQuery::default()).await, ["a", "b"]);
+        assert_eq!(ids(StreamQuery { archived: true, ..Default::default() }).await, ["c"]);
+        assert_eq!(ids(StreamQuery { statuses: vec![StreamStatus::Idle], ..Default::default() }).await, ["b"]);
+        assert_eq!(ids(StreamQuery { tags: vec!["lofi".into()], ..Default::default() }).await, ["b"]);
+        let both = StreamQuery { tags: vec!["client a".into()], statuses: vec![StreamStatus::Live], ..Default::default() };
+        assert_eq!(ids(both).await, ["a"]);
+        assert_eq!(ids(StreamQuery { search: Some("stream B".into()), ..Default::default() }).await, ["b"]);
+        assert_eq!(ids(StreamQuery { search: Some("channel ch".into()), ..Default::default() }).await, ["a", "b"]);
+        // LIKE wildcards in the search are taken literally
+        assert_eq!(ids(StreamQuery { search: Some("%".into()), ..Default::default() }).await, Vec::<String>::new());
+        let bounded = StreamQuery { created_to: Some("2024-05-07T10:00:00+00:00".into()), ..Default::default() };
+        assert_eq!(ids(bounded).await, Vec::<String>::new());
+    }
+
+    fn heartbeat(elapsed_seconds: u64, live_seconds: u64, playback_position: Option<f64>) -> Heartbeat {
+        Heartbeat { elapsed_seconds, live_seconds, playback_position }
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_updates_the_live_stream_and_its_session() {
+        let db = database().await;
+        insert_channel(&db, "ch").await;
+        insert_stream(&db, "a", "ch").await;
+
+        // Nothing to record while the stream is not live
+        db.heartbeat("a", &heartbeat(5, 5, Some(5.0))).await.unwrap();
+        assert_eq!(db.get_stream("a").await.unwrap().unwrap().last_seen_at, None);
+
+        db.transition_stream("a", &Transition::live()).await.unwrap();
+        db.heartbeat("a", &heartbeat(30, 90, Some(42.5))).await.unwrap();
+        // A heartbeat without a known position keeps the last one
+        db.heartbeat("a", &heartbeat(40, 100, None)).await.unwrap();
+
+        let stream = db.get_stream("a").await.unwrap().unwrap();
+        assert_eq!(stream.last_elapsed_seconds, Some(40));
+        assert_eq!(stream.live_seconds, Some(100));
+        assert_eq!(stream.playback_position, Some(42.5));
+        assert!(stream.last_seen_at.is_some());
+
+        let session = &db.get_stream_sessions("a").await.unwrap()[0];
+        assert_eq!(session.elapsed_seconds, Some(40));
+        assert_eq!(session.live_seconds, Some(100));
+        assert_eq!(session.last_seen_at, stream.last_seen_at);
+    }
+
+    #[tokio::test]
+    async fn test_live_time_carries_over_restarts_and_resets_on_a_new_run() {
+        let db = database().await;
+        insert_channel(&db, "ch").await;
+        insert_stream(&db, "a", "ch").await;
+
+        // The second process of the run reports the first one's live time too
+        db.transition_stream("a", &Transition::live()).await.unwrap();
+        db.heartbeat("a", &heartbeat(60, 60, None)).await.unwrap();
+        db.record_session_restart("a").await.unwrap();
+        db.heartbeat("a", &heartbeat(20, 80, None)).await.unwrap();
+        let end = SessionEnd::new(SessionEndReason::Manual);
+        db.transition_stream("a", &Transition::completed(Some(30), end).live_for(Some(90))).await.unwrap();
+
+        let stream = db.get_stream("a").await.unwrap().unwrap();
+        assert_eq!(stream.live_seconds, Some(90));
+        assert_eq!(stream.last_elapsed_seconds, Some(30));
+        assert_eq!(db.get_stream_sessions("a").await.unwrap()[0].live_seconds, Some(90));
+
+        // A new run starts counting from zero
+        db.transition_stream("a", &Transition::live()).await.unwrap();
+        assert_eq!(db.get_stream("a").await.unwrap().unwrap().live_seconds, None);
+        assert_eq!(db.get_stream_sessions("a").await.unwrap()[0].live_seconds, None);
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_streams_end_at_their_last_heartbeat() {
+        let db = database().await;
+        insert_channel(&db, "ch").await;
+        insert_stream(&db, "a", "ch").await;
+        insert_stream(&db, "b", "ch").await;
+
+        db.transition_stream("a", &Transition::live()).await.unwrap();
+        db.heartbeat("a", &heartbeat(30, 30, Some(30.0))).await.unwrap();
+        let last_seen = db.get_stream("a").await.unwrap().unwrap().last_seen_at;
+
+        assert_eq!(db.mark_interrupted_streams().await.unwrap(), 1);
+
+        let stream = db.get_stream("a").await.unwrap().unwrap();
+        assert_eq!(stream.status, StreamStatus::Error);
+        // Kept by key, so it reads back in whatever language is current
+        let stored: Option<String> = sqlx::query_scalar("SELECT status_reason FROM streams WHERE id = 'a'")
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(stored.as_deref().map(Message::from_db), Some(Message::new("reason.interrupted", &Params::new())));
+        assert_eq!(stream.status_reason, Some(Message::new("reason.interrupted", &Params::new()).localized()));
+        assert_eq!(stream.stopped_at, last_seen);
+        assert_eq!(stream.playback_position, Some(30.0));
+        let session = &db.get_stream_sessions("a").await.unwrap()[0];
+        assert_eq!(session.ended_at, last_seen);
+        assert_eq!(session.end_reason, Some(SessionEndReason::Interrupted));
+
+        // Streams that were not live are left alone
+        assert_eq!(db.get_stream("b").await.unwrap().unwrap().status, StreamStatus::Idle);
+    }
+
+    #[tokio::test]
+    async fn test_tags_are_added_and_removed_and_counted_on_streams_in_use() {
+        let db = database().await;
+        insert_channel(&db, "ch").await;
+        for id in ["a", "b", "c"] {
+            insert_stream(&db, id, "ch").await;
+        }
+        let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+
+        // Unknown streams and streams already tagged are not counted
+        assert_eq!(db.add_tag(&ids(&["a", "b", "missing"]), "lofi").await.unwrap(), 2);
+        assert_eq!(db.add_tag(&ids(&["a", "c"]), "lofi").await.unwrap(), 1);
+        assert_eq!(db.add_tag(&ids(&["b"]), "client a").await.unwrap(), 1);
+        assert_eq!(db.remove_tag(&ids(&["c", "missing"]), "lofi").await.unwrap(), 1);
+        assert_eq!(db.get_stream("a").await.unwrap().unwrap().tags, ["lofi"]);
+
+        let counts = |tags: Vec<Tag>| tags.into_iter().map(|t| (t.name, t.stream_count)).collect::<Vec<_>>();
+        assert_eq!(counts(db.get_tags().await.unwrap()), [("client a".to_string(), 1), ("lofi".to_string(), 2)]);
+
+        // The archive keeps its tags but they are not offered
+        db.archive_stream("b", "2024-05-09T10:00:00+00:00").await.unwrap();
+        assert_eq!(counts(db.get_tags().await.unwrap()), [("lofi".to_string(), 1)]);
+        assert_eq!(db.get_stream("b").await.unwrap().unwrap().tags, ["client a", "lofi"]);
+    }
+
+    #[tokio::test]
+    async fn test_archived_streams_are_restored_and_purged_by_their_cutoff() {
+        let db = database().await;
+        insert_channel(&db, "ch").await;
+        for id in ["a", "b", "c"] {
+            insert_stream(&db, id, "ch").await;
+        }
+        let archived = |cutoff: Option<&'static str>| {
+            let db = &db;
+            async move {
+                let mut ids = db.get_archived_ids(cutoff).await.unwrap();
+                ids.sort();
+                ids
+            }
+        };
+
+        assert!(db.archive_stream("a", "2024-05-01T10:00:00+00:00").await.unwrap());
+        assert!(db.archive_stream("b", "2024-05-09T10:00:00+00:00").await.unwrap());
+        // Archiving twice keeps the first time, and unknown streams are not archived
+        assert!(!db.archive_stream("a", "2024-05-20T10:00:00+00:00").await.unwrap());
+        assert!(!db.archive_stream("missing", "2024-05-20T10:00:00+00:00").await.unwrap());
+        assert_eq!(db.get_stream("a").await.unwrap().unwrap().archived_at.as_deref(), Some("2024-05-01T10:00:00+00:00"));
+
+        // Only streams archived strictly before the cutoff have expired
+        assert_eq!(archived(None).await, ["a", "b"]);
+        assert_eq!(archived(Some("2024-05-09T10:00:00+00:00")).await, ["a"]);
+        assert_eq!(archived(Some("2024-05-10T10:00:00+00:00")).await, ["a", "b"]);
+        assert!(archived(Some("2024-04-30T10:00:00+00:00")).await.is_empty());
+
+        // A restored stream is back in use and no longer up for purging
+        assert!(db.unarchive_stream("b").await.unwrap());
+        assert!(!db.unarchive_stream("b").await.unwrap());
+        assert!(!db.unarchive_stream("c").await.unwrap());
+        assert_eq!(db.get_stream("b").await.unwrap().unwrap().archived_at, None);
+        assert_eq!(archived(None).await, ["a"]);
+
+        // Purging erases the stream and its tags
+        db.add_tag(&["a".to_string()], "lofi").await.unwrap();
+        db.delete_stream("a").await.unwrap();
+        assert!(db.get_stream("a").await.unwrap().is_none());
+        assert!(archived(None).await.is_empty());
+        let tagged: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM stream_tags WHERE stream_id = 'a'")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(tagged, 0);
+    }
+
+    #[tokio::test]
+    async fn test_restarts_are_recorded_with_their_time() {
+        let db = database().await;
+        insert_channel(&db, "ch").await;
+        insert_stream(&db, "a", "ch").await;
+
+        db.transition_stream("a", &Transition::live()).await.unwrap();
+        db.record_session_restart("a").await.unwrap();
+        db.record_session_restart("a").await.unwrap();
+        db.transition_stream("a", &Transition::completed(Some(60), SessionEnd::new(SessionEndReason::Manual)))
+            .await
+            .unwrap();
+        // A closed session takes no more restarts
+        db.record_session_restart("a").await.unwrap();
+
+        let sessions = db.get_stream_sessions("a").await.unwrap();
+        assert_eq!(sessions[0].restarts, 2);
+        assert_eq!(sessions[0].restart_times.len(), 2);
+        assert!(sessions[0].restart_times.iter().all(|time| chrono::DateTime::parse_from_rfc3339(time).is_ok()));
+    }
+}
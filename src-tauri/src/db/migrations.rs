@@ -0,0 +1,430 @@
+use std::path::Path;
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use thiserror::Error;
+
+use crate::db::backup;
+
+#[derive(Error, Debug)]
+pub enum MigrationError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Database schema version {found} is newer than this app supports ({supported})")]
+    NewerSchema { found: u32, supported: u32 },
+}
+
+/// One change to the schema, applied as a whole or not at all
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub steps: &'static [Step],
+}
+
+pub enum Step {
+    Sql(&'static str),
+    /// Add a column unless it is already there. Databases created before versioning
+    /// picked up columns as features landed, so their tables differ.
+    AddColumn { table: &'static str, column: &'static str, definition: &'static str },
+}
+
+/// Every migration, in the order they are applied. Never edit one that has shipped - add another.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "streams",
+        steps: &[
+            Step::Sql(r#"
+                CREATE TABLE IF NOT EXISTS streams (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    youtube_key TEXT NOT NULL,
+                    video_path TEXT NOT NULL,
+                    status TEXT NOT NULL DEFAULT 'idle',
+                    schedule TEXT NOT NULL,
+                    started_at TEXT,
+                    stopped_at TEXT,
+                    created_at TEXT NOT NULL
+                )
+            "#),
+            Step::AddColumn { table: "streams", column: "last_elapsed_seconds", definition: "INTEGER" },
+            Step::AddColumn { table: "streams", column: "ingest_endpoints", definition: "TEXT" },
+            Step::AddColumn { table: "streams", column: "clips", definition: "TEXT" },
+            Step::AddColumn { table: "streams", column: "resume", definition: "TEXT" },
+            Step::AddColumn { table: "streams", column: "playback_position", definition: "REAL" },
+            Step::AddColumn { table: "streams", column: "video_trim", definition: "TEXT" },
+            Step::AddColumn { table: "streams", column: "status_reason", definition: "TEXT" },
+            Step::AddColumn { table: "streams", column: "live_seconds", definition: "INTEGER" },
+            Step::AddColumn { table: "streams", column: "last_seen_at", definition: "TEXT" },
+        ],
+    },
+    Migration {
+        version: 2,
+        description: "sessions",
+        steps: &[
+            // One row per go-live. No foreign key - the history outlives deleted streams.
+            Step::Sql(r#"
+                CREATE TABLE IF NOT EXISTS sessions (
+                    id TEXT PRIMARY KEY,
+                    stream_id TEXT NOT NULL,
+                    started_at TEXT NOT NULL,
+                    ended_at TEXT,
+                    end_reason TEXT,
+                    failure_kind TEXT,
+                    failure_reason TEXT,
+                    elapsed_seconds INTEGER,
+                    restarts INTEGER NOT NULL DEFAULT 0,
+                    encoder_stats TEXT,
+                    log_tail TEXT
+                )
+            "#),
+            Step::AddColumn { table: "sessions", column: "scheduled_end", definition: "TEXT" },
+            Step::AddColumn { table: "sessions", column: "live_seconds", definition: "INTEGER" },
+            Step::AddColumn { table: "sessions", column: "last_seen_at", definition: "TEXT" },
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_sessions_stream ON sessions (stream_id, started_at)"),
+        ],
+    },
+    Migration {
+        version: 3,
+        description: "settings",
+        steps: &[
+            // Operator preferences, e.g. the locale
+            Step::Sql(r#"
+                CREATE TABLE IF NOT EXISTS settings (
+                    key TEXT PRIMARY KEY,
+                    value TEXT NOT NULL
+                )
+            "#),
+        ],
+    },
+    Migration {
+        version: 4,
+        description: "masked stream keys",
+        steps: &[
+            // Filled in when the vault next reseals keys at startup
+            Step::AddColumn { table: "streams", column: "masked_key", definition: "TEXT" },
+        ],
+    },
+    Migration {
+        version: 5,
+        description: "channels",
+        steps: &[
+            // Keys live on a channel streams share. Sealing is deterministic, so one key is one row.
+            Step::Sql(r#"
+                CREATE TABLE IF NOT EXISTS channels (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    destination TEXT,
+                    youtube_key TEXT NOT NULL UNIQUE,
+                    masked_key TEXT,
+                    notes TEXT NOT NULL DEFAULT '',
+                    created_at TEXT NOT NULL
+                )
+            "#),
+            Step::AddColumn { table: "streams", column: "channel_id", definition: "TEXT" },
+            // One channel per key already in use, named after the first stream that used it
+            Step::Sql(r#"
+                INSERT INTO channels (id, name, youtube_key, masked_key, created_at)
+                SELECT lower(hex(randomblob(16))), MIN(name), youtube_key, MAX(masked_key), MIN(created_at)
+                FROM streams
+                GROUP BY youtube_key
+            "#),
+            Step::Sql(r#"
+                UPDATE streams
+                SET channel_id = (SELECT id FROM channels WHERE channels.youtube_key = streams.youtube_key)
+            "#),
+            Step::Sql("ALTER TABLE streams DROP COLUMN youtube_key"),
+            Step::Sql("ALTER TABLE streams DROP COLUMN masked_key"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_streams_channel ON streams (channel_id)"),
+        ],
+    },
+    Migration {
+        version: 6,
+        description: "stream query indexes",
+        steps: &[
+            // One per `StreamSort`, on the same expression the query orders by, with the id that breaks ties
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_streams_status ON streams (status, id)"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_streams_created ON streams (created_at, id)"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_streams_name ON streams (name COLLATE NOCASE, id)"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_streams_started ON streams (COALESCE(started_at, ''), id)"),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_streams_schedule_type ON streams (json_extract(schedule, '$.type'))"),
+        ],
+    },
+    Migration {
+        version: 7,
+        description: "tags",
+        steps: &[
+            // Tags match whatever their case, like `normalize_tags` treats them
+            Step::Sql(r#"
+                CREATE TABLE IF NOT EXISTS stream_tags (
+                    stream_id TEXT NOT NULL,
+                    tag TEXT NOT NULL COLLATE NOCASE,
+                    PRIMARY KEY (stream_id, tag)
+                )
+            "#),
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_stream_tags_tag ON stream_tags (tag)"),
+        ],
+    },
+    Migration {
+        version: 8,
+        description: "archive",
+        steps: &[
+            // Deleted streams are archived, and purged once they have been archived for the retention period
+            Step::AddColumn { table: "streams", column: "archived_at", definition: "TEXT" },
+            Step::Sql("CREATE INDEX IF NOT EXISTS idx_streams_archived ON streams (archived_at)"),
+        ],
+    },
+    Migration {
+        version: 9,
+        description: "templates",
+        steps: &[
+            // Settings beyond the channel are one JSON document, like a stream's clips or schedule
+            Step::Sql(r#"
+                CREATE TABLE IF NOT EXISTS templates (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    channel_id TEXT,
+                    settings TEXT NOT NULL,
+                    created_at TEXT NOT NULL
+                )
+            "#),
+        ],
+    },
+    Migration {
+        version: 10,
+        description: "session restart times",
+        steps: &[
+            // A JSON array of RFC 3339 times, so reports count only the restarts inside their range
+            Step::AddColumn { table: "sessions", column: "restart_times", definition: "TEXT" },
+        ],
+    },
+    Migration {
+        version: 11,
+        description: "uuid channel ids",
+        steps: &[
+            // Channels made from existing keys in version 5 got bare hex ids. Give them version 4 UUIDs
+            // like the app makes, and move the streams and templates on them along.
+            Step::Sql(r#"
+                CREATE TEMP TABLE channel_ids AS
+                SELECT id AS old_id,
+                    lower(hex(randomblob(4))) || '-' || lower(hex(randomblob(2))) || '-4' ||
+                    substr(lower(hex(randomblob(2))), 2) || '-' || substr('89ab', 1 + (random() & 3), 1) ||
+                    substr(lower(hex(randomblob(2))), 2) || '-' || lower(hex(randomblob(6))) AS new_id
+                FROM channels
+                WHERE length(id) = 32 AND instr(id, '-') = 0
+            "#),
+            Step::Sql(r#"
+                UPDATE streams SET channel_id = (SELECT new_id FROM channel_ids WHERE old_id = streams.channel_id)
+                WHERE channel_id IN (SELECT old_id FROM channel_ids)
+            "#),
+            Step::Sql(r#"
+                UPDATE templates SET channel_id = (SELECT new_id FROM channel_ids WHERE old_id = templates.channel_id)
+                WHERE channel_id IN (SELECT old_id FROM channel_ids)
+            "#),
+            Step::Sql(r#"
+                UPDATE channels SET id = (SELECT new_id FROM channel_ids WHERE old_id = channels.id)
+                WHERE id IN (SELECT old_id FROM channel_ids)
+            "#),
+            Step::Sql("DROP TABLE channel_ids"),
+        ],
+    },
+    Migration {
+        version: 12,
+        description: "utc creation times",
+        steps: &[
+            // Streams took their creation time from the frontend, which writes UTC as "Z". Spell it
+            // "+00:00" like every other stored time, so date filters compare them as text.
+            Step::Sql(r#"
+                UPDATE streams SET created_at = substr(created_at, 1, length(created_at) - 1) || '+00:00'
+                WHERE created_at LIKE '%Z'
+            "#),
+        ],
+    },
+];
+
+/// Version of the schema this build writes
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+/// Bring the database at `path` up to the latest version, backing it up first if it holds data
+pub async fn run(pool: &Pool<Sqlite>, path: &Path) -> Result<(), MigrationError> {
+    sqlx::query(r#"
+        CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )
+    "#)
+    .execute(pool)
+    .await?;
+
+    let current = current_version(pool).await?;
+    let latest = latest_version();
+    if current > latest {
+        return Err(MigrationError::NewerSchema { found: current, supported: latest });
+    }
+    if current == latest {
+        return Ok(());
+    }
+
+    // Unversioned databases from before this runner hold streams too
+    let has_data: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'streams'"
+    )
+    .fetch_one(pool)
+    .await?;
+    if has_data {
+        let (backup, _) = backup::next_path(path).map_err(sqlx::Error::Io)?;
+        sqlx::query("VACUUM INTO ?")
+            .bind(backup.to_string_lossy().as_ref())
+            .execute(pool)
+            .await?;
+        tracing::info!("Backed up database at schema version {} to {:?}", current, backup);
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let mut tx = pool.begin().await?;
+        for step in migration.steps {
+            apply(&mut tx, step).await?;
+        }
+        sqlx::query("INSERT INTO schema_version (version, description, applied_at) VALUES (?, ?, ?)")
+            .bind(migration.version)
+            .bind(migration.description)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        tracing::info!("Migrated database to schema version {} ({})", migration.version, migration.description);
+    }
+
+    Ok(())
+}
+
+pub async fn current_version(pool: &Pool<Sqlite>) -> Result<u32, sqlx::Error> {
+    let version: Option<u32> = sqlx::query_scalar("SELECT MAX(version) FROM schema_version")
+        .fetch_one(pool)
+        .await?;
+    Ok(version.unwrap_or(0))
+}
+
+async fn apply(conn: &mut SqliteConnection, step: &Step) -> Result<(), sqlx::Error> {
+    match step {
+        Step::Sql(sql) => {
+            sqlx::query(sql).execute(&mut *conn).await?;
+        }
+        Step::AddColumn { table, column, definition } => {
+            let exists: bool = sqlx::query_scalar("SELECT COUNT(*) > 0 FROM pragma_table_info(?) WHERE name = ?")
+                .bind(table)
+                .bind(column)
+                .fetch_one(&mut *conn)
+                .await?;
+            if !exists {
+                sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+                    .execute(&mut *conn)
+                    .await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use super::*;
+    use crate::db::Database;
+    use crate::stream::query::StreamQuery;
+
+    /// A fresh directory for one test's database files
+    fn scratch_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ylm-migrations-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn backups(dir: &Path) -> usize {
+        backup::list(&dir.join("streams_test.db")).unwrap().len()
+    }
+
+    #[test]
+    fn test_versions_are_consecutive() {
+        let versions: Vec<u32> = MIGRATIONS.iter().map(|m| m.version).collect();
+        let expected: Vec<u32> = (1..=MIGRATIONS.len() as u32).collect();
+        assert_eq!(versions, expected);
+    }
+
+    #[tokio::test]
+    async fn test_unversioned_database_is_backed_up_and_upgraded() {
+        let dir = scratch_dir();
+        let path = dir.join("streams_test.db");
+
+        // The table as the first release created it
+        let db = Database::new(&path).await.unwrap();
+        sqlx::query(r#"
+            CREATE TABLE streams (
+                id TEXT PRIMARY KEY, name TEXT NOT NULL, youtube_key TEXT NOT NULL, video_path TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'idle', schedule TEXT NOT NULL, started_at TEXT, stopped_at TEXT,
+                created_at TEXT NOT NULL, last_elapsed_seconds INTEGER
+            )
+        "#)
+        .execute(db.pool())
+        .await
+        .unwrap();
+        sqlx::query(r#"
+            INSERT INTO streams (id, name, youtube_key, video_path, schedule, created_at)
+            VALUES ('abc', 'Lofi radio', 'aaaa-bbbb', '/videos/lofi.mp4', '{"type":"manual"}', '2024-05-07T10:00:00+00:00'),
+                   ('def', 'Lofi radio 2', 'aaaa-bbbb', '/videos/lofi.mp4', '{"type":"manual"}', '2024-05-08T10:00:00.000Z')
+        "#)
+        .execute(db.pool())
+        .await
+        .unwrap();
+
+        db.migrate().await.unwrap();
+        assert_eq!(current_version(db.pool()).await.unwrap(), latest_version());
+        assert_eq!(backups(&dir), 1);
+        let streams = db.get_all_streams().await.unwrap();
+        assert_eq!(streams.len(), 2);
+        assert!(streams.iter().any(|s| s.name == "Lofi radio"));
+
+        // Streams sharing a key share the channel created for it
+        let channels = db.get_channels().await.unwrap();
+        assert_eq!(channels.len(), 1);
+        assert_eq!(channels[0].name, "Lofi radio");
+        assert_eq!(channels[0].youtube_key, "aaaa-bbbb");
+        assert!(streams.iter().all(|s| s.channel_id == channels[0].id && s.youtube_key == "aaaa-bbbb"));
+        let id = uuid::Uuid::parse_str(&channels[0].id).unwrap();
+        assert_eq!((id.get_version_num(), id.get_variant()), (4, uuid::Variant::RFC4122));
+        assert_eq!(id.hyphenated().to_string(), channels[0].id);
+
+        // Creation times the frontend wrote in "Z" fall within date filters
+        let query = StreamQuery { created_from: Some("2024-05-08T17:00:00+07:00".into()), ..Default::default() };
+        let (query, _) = query.normalize().unwrap();
+        let (found, _) = db.query_streams(&query, None, None).await.unwrap();
+        assert_eq!(found.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(), ["def"]);
+
+        // Up to date - nothing to back up or apply
+        db.migrate().await.unwrap();
+        assert_eq!(backups(&dir), 1);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_newer_schema_is_refused() {
+        let dir = scratch_dir();
+        let db = Database::new(&dir.join("streams_test.db")).await.unwrap();
+        db.migrate().await.unwrap();
+        assert_eq!(backups(&dir), 0);
+
+        sqlx::query("INSERT INTO schema_version (version, description, applied_at) VALUES (?, 'future', '')")
+            .bind(latest_version() + 1)
+            .execute(db.pool())
+            .await
+            .unwrap();
+        assert!(matches!(
+            db.migrate().await,
+            Err(MigrationError::NewerSchema { found, supported }) if found == supported + 1
+        ));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
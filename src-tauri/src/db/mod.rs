@@ -1,3 +1,6 @@
+pub mod backup;
 pub mod database;
+pub mod migrations;
 
-pub use database::Database;
+pub use database::{Database, Heartbeat};
+pub use migrations::MigrationError;
@@ -0,0 +1,314 @@
+use std::collections::BTreeMap;
+use std::sync::{PoisonError, RwLock};
+use serde::{Deserialize, Serialize};
+
+/// Language of text the backend generates for the operator
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    En,
+    #[default]
+    Vi, // The UI's own language
+}
+
+impl Locale {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Vi => "vi",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "en" => Some(Self::En),
+            "vi" => Some(Self::Vi),
+            _ => None,
+        }
+    }
+}
+
+/// Values substituted for `{name}` placeholders in a message
+pub type Params = BTreeMap<&'static str, String>;
+
+pub fn params<const N: usize>(pairs: [(&'static str, String); N]) -> Params {
+    pairs.into_iter().collect()
+}
+
+static LOCALE: RwLock<Locale> = RwLock::new(Locale::Vi);
+
+pub fn locale() -> Locale {
+    *LOCALE.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+pub fn set_locale(locale: Locale) {
+    *LOCALE.write().unwrap_or_else(PoisonError::into_inner) = locale;
+}
+
+/// Render a message in a given language, falling back to English, then to the key itself
+pub fn translate(locale: Locale, key: &str, params: &Params) -> String {
+    fill(template(locale, key), params.iter().map(|(name, value)| (*name, value.clone())))
+}
+
+fn template(locale: Locale, key: &str) -> &str {
+    lookup(locale, key)
+        .or_else(|| lookup(Locale::En, key))
+        .unwrap_or(key)
+}
+
+fn fill<'a>(template: &str, params: impl IntoIterator<Item = (&'a str, String)>) -> String {
+    params.into_iter().fold(template.to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{}}}", name), &value)
+    })
+}
+
+/// A message kept as its key and values rather than as text, so it can be stored
+/// and rendered later in whichever language is current by then
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    pub key: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, String>,
+    /// Messages filling placeholders, rendered in the same language and joined with `; `
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub nested: BTreeMap<String, Vec<Message>>,
+}
+
+impl Message {
+    pub fn new(key: impl Into<String>, params: &Params) -> Self {
+        Self {
+            key: key.into(),
+            params: params.iter().map(|(name, value)| (name.to_string(), value.clone())).collect(),
+            nested: BTreeMap::new(),
+        }
+    }
+
+    /// Fill the `name` placeholder with other messages
+    pub fn with(mut self, name: &str, messages: Vec<Message>) -> Self {
+        self.nested.insert(name.to_string(), messages);
+        self
+    }
+
+    pub fn render(&self, locale: Locale) -> String {
+        let params = self.params.iter().map(|(name, value)| (name.as_str(), value.clone()));
+        let nested = self.nested.iter().map(|(name, messages)| {
+            let rendered = messages.iter().map(|message| message.render(locale)).collect::<Vec<_>>();
+            (name.as_str(), rendered.join("; "))
+        });
+        fill(template(locale, &self.key), params.chain(nested))
+    }
+
+    /// The message in the operator's language
+    pub fn localized(&self) -> String {
+        self.render(locale())
+    }
+
+    pub fn to_db(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|_| self.key.clone())
+    }
+
+    /// A stored message. Text stored before messages were kept by key reads back as it was.
+    pub fn from_db(value: &str) -> Self {
+        serde_json::from_str(value).unwrap_or_else(|_| Self::new(value, &Params::new()))
+    }
+}
+
+fn lookup(locale: Locale, key: &str) -> Option<&'static str> {
+    let catalogue = match locale {
+        Locale::En => EN,
+        Locale::Vi => VI,
+    };
+    catalogue.iter().find(|(k, _)| *k == key).map(|(_, template)| *template)
+}
+
+const EN: &[(&str, &str)] = &[
+    // Command errors, keyed by `ManagerError::kind`
+    ("error.database", "Database error: {error}"),
+    ("error.schemaTooNew", "The database was written by a newer version of the app (schema {found}, this version supports {supported}) - update the app"),
+    ("error.notFound", "Stream not found: {id}"),
+    ("error.archived", "Stream {id} is archived - restore it first"),
+    ("error.notArchived", "Stream {id} is not archived"),
+    ("error.channelNotFound", "Channel not found: {id}"),
+    ("error.templateNotFound", "Template not found: {id}"),
+    ("error.channelInUse", "Channel {name} is used by {count} streams - move them to another channel or purge them from the archive first"),
+    ("error.alreadyRunning", "Stream already running: {id}"),
+    ("error.duplicateKey", "Stream key {key} is already used by a live stream"),
+    ("error.ffmpeg", "FFmpeg error: {error}"),
+    ("error.noIngestEndpoint", "No ingest endpoint accepts connections"),
+    ("error.exitedImmediately", "FFmpeg process exited immediately - check video file or stream key"),
+    ("error.io", "IO error: {error}"),
+    ("error.invalidKeyFile", "The master key file {path} is damaged - stream keys cannot be read"),
+    ("error.undecryptableKey", "The stream key cannot be decrypted with this app's master key - enter it again"),
+    ("error.keysNotResealed", "{count} channel keys cannot be decrypted with this app's master key ({channels}) - the master key was left as it was"),
+    ("error.invalidTrim", "Invalid trim of {path}: {error}"),
+    ("error.workerStopped", "Stream worker stopped: {id}"),
+    ("error.invalidTransition", "Invalid status transition: {from} -> {to}"),
+    ("error.invalidRange", "Invalid report range: {from} to {to} - expected RFC 3339 times, the first before the second"),
+    ("error.validation", "Invalid stream: {errors}"),
+    ("error.restartRequired", "Changing {fields} needs a restart while the stream is live"),
+    ("error.invalidImport", "Cannot read the import file: {error}"),
+    ("error.backupNotFound", "Backup not found: {file}"),
+    ("error.backupDamaged", "Backup {file} is damaged: {error}"),
+    ("error.streamsNotStopped", "{count} live streams could not be stopped ({streams}) - the backup was not restored"),
+    ("error.databaseLost", "The database could not be reopened after a failed restore: {error} - restart the app"),
+    // Rejected fields
+    ("field.nameRequired", "Name is required"),
+    ("field.keyRequired", "Stream key is required"),
+    ("field.channelNameRequired", "Channel name is required"),
+    ("field.channelNotFound", "Channel not found: {id}"),
+    ("field.keyInUse", "This key already belongs to channel {channel}"),
+    ("field.videoRequired", "Video file is required"),
+    ("field.fileNotFound", "File not found: {path}"),
+    ("field.unreadableMedia", "Could not read {path}: {error}"),
+    ("field.invalidUrl", "{url} is not an rtmp:// or rtmps:// URL"),
+    ("field.durationRequired", "A duration schedule needs a duration"),
+    ("field.durationZero", "Duration must be longer than zero"),
+    ("field.stopTimeRequired", "An absolute schedule needs a stop time"),
+    ("field.stopTimeInPast", "{datetime} is in the past"),
+    ("field.invalidTimezone", "Unknown timezone: {timezone}"),
+    ("field.invalidDateTime", "{datetime} is not a date and time like 2024-01-15T14:30"),
+    ("field.ambiguousTime", "{datetime} is skipped or repeated by a daylight saving change in {timezone}"),
+    ("field.playsZero", "Play count must be at least 1"),
+    ("field.invalidValue", "Invalid value: {value}"),
+    ("field.invalidRecord", "Invalid stream: {error}"),
+    ("field.limitZero", "Page size must be at least 1"),
+    ("field.cursorMismatch", "The cursor is from a list in another order - start again from the first page"),
+    ("field.tagRequired", "Tag cannot be blank"),
+    ("field.tagTooLong", "Tag {tag} is longer than {max} characters"),
+    ("field.retentionZero", "Archived streams must be kept at least 1 day"),
+    ("field.retentionTooLong", "Archived streams can be kept at most {max} days"),
+    ("field.described", "{field}: {message}"),
+    // Trim points, keyed by `TrimError::key`
+    ("trim.notFinite", "Trim points must be numbers of seconds"),
+    ("trim.negative", "Trim points cannot be negative"),
+    ("trim.endBeforeStart", "End {end}s must be after start {start}s"),
+    ("trim.startPastEnd", "Start {start}s is past the end of the media ({duration}s)"),
+    ("trim.endPastEnd", "End {end}s is past the end of the media ({duration}s)"),
+    // Why a stream failed, shown in its status and error events
+    ("reason.endOfInput", "FFmpeg reached the end of its input"),
+    ("reason.exitCode", "FFmpeg exited with code {code}"),
+    ("reason.terminated", "FFmpeg was terminated"),
+    ("reason.stopped", "FFmpeg was stopped"),
+    ("reason.restartFailed", "Restart failed: {error}"),
+    ("reason.endpointsExhausted", "Every ingest endpoint failed repeatedly - last: {reason}"),
+    ("reason.stopFailed", "Stopping failed: {error}"),
+    ("reason.interrupted", "Interrupted by shutdown"),
+];
+
+const VI: &[(&str, &str)] = &[
+    ("error.database", "Lỗi cơ sở dữ liệu: {error}"),
+    ("error.schemaTooNew", "Cơ sở dữ liệu được tạo bởi phiên bản mới hơn của ứng dụng (lược đồ {found}, phiên bản này hỗ trợ {supported}) - hãy cập nhật ứng dụng"),
+    ("error.notFound", "Không tìm thấy luồng: {id}"),
+    ("error.archived", "Luồng {id} đang trong kho lưu trữ - hãy khôi phục trước"),
+    ("error.notArchived", "Luồng {id} không nằm trong kho lưu trữ"),
+    ("error.channelNotFound", "Không tìm thấy kênh: {id}"),
+    ("error.templateNotFound", "Không tìm thấy mẫu: {id}"),
+    ("error.channelInUse", "Kênh {name} đang được {count} luồng sử dụng - hãy chuyển các luồng sang kênh khác hoặc xóa hẳn khỏi kho lưu trữ trước"),
+    ("error.alreadyRunning", "Luồng đang phát: {id}"),
+    ("error.duplicateKey", "Key {key} đã được sử dụng bởi luồng đang phát"),
+    ("error.ffmpeg", "Lỗi FFmpeg: {error}"),
+    ("error.noIngestEndpoint", "Không kết nối được máy chủ nhận luồng nào"),
+    ("error.exitedImmediately", "FFmpeg thoát ngay khi khởi động - kiểm tra tệp video hoặc stream key"),
+    ("error.io", "Lỗi đọc/ghi tệp: {error}"),
+    ("error.invalidKeyFile", "Tệp khóa chính {path} bị hỏng - không đọc được stream key"),
+    ("error.undecryptableKey", "Không giải mã được stream key bằng khóa chính của ứng dụng - hãy nhập lại key"),
+    ("error.keysNotResealed", "Không giải mã được {count} stream key của kênh bằng khóa chính của ứng dụng ({channels}) - khóa chính được giữ nguyên"),
+    ("error.invalidTrim", "Điểm cắt của {path} không hợp lệ: {error}"),
+    ("error.workerStopped", "Tiến trình xử lý luồng đã dừng: {id}"),
+    ("error.invalidTransition", "Không thể chuyển trạng thái: {from} -> {to}"),
+    ("error.invalidRange", "Khoảng thời gian báo cáo không hợp lệ: {from} đến {to} - cần thời điểm RFC 3339, thời điểm đầu trước thời điểm sau"),
+    ("error.validation", "Luồng không hợp lệ: {errors}"),
+    ("error.restartRequired", "Thay đổi {fields} cần khởi động lại luồng đang phát"),
+    ("error.invalidImport", "Không đọc được tệp nhập: {error}"),
+    ("error.backupNotFound", "Không tìm thấy bản sao lưu: {file}"),
+    ("error.backupDamaged", "Bản sao lưu {file} bị hỏng: {error}"),
+    ("error.streamsNotStopped", "Không dừng được {count} luồng đang phát ({streams}) - bản sao lưu chưa được khôi phục"),
+    ("error.databaseLost", "Không mở lại được cơ sở dữ liệu sau khi khôi phục thất bại: {error} - hãy khởi động lại ứng dụng"),
+    ("field.nameRequired", "Cần nhập tên luồng"),
+    ("field.keyRequired", "Cần nhập stream key"),
+    ("field.channelNameRequired", "Cần nhập tên kênh"),
+    ("field.channelNotFound", "Không tìm thấy kênh: {id}"),
+    ("field.keyInUse", "Key này đã thuộc về kênh {channel}"),
+    ("field.videoRequired", "Cần chọn tệp video"),
+    ("field.fileNotFound", "Không tìm thấy tệp: {path}"),
+    ("field.unreadableMedia", "Không đọc được {path}: {error}"),
+    ("field.invalidUrl", "{url} không phải địa chỉ rtmp:// hoặc rtmps://"),
+    ("field.durationRequired", "Lịch theo thời lượng cần có thời lượng"),
+    ("field.durationZero", "Thời lượng phải lớn hơn 0"),
+    ("field.stopTimeRequired", "Lịch theo giờ cụ thể cần có giờ dừng"),
+    ("field.stopTimeInPast", "{datetime} đã qua"),
+    ("field.invalidTimezone", "Múi giờ không hợp lệ: {timezone}"),
+    ("field.invalidDateTime", "{datetime} không đúng định dạng ngày giờ, ví dụ 2024-01-15T14:30"),
+    ("field.ambiguousTime", "{datetime} bị bỏ qua hoặc lặp lại khi đổi giờ mùa hè ở {timezone}"),
+    ("field.playsZero", "Số lần phát phải ít nhất là 1"),
+    ("field.invalidValue", "Giá trị không hợp lệ: {value}"),
+    ("field.invalidRecord", "Luồng không hợp lệ: {error}"),
+    ("field.limitZero", "Số luồng mỗi trang phải ít nhất là 1"),
+    ("field.cursorMismatch", "Con trỏ thuộc về danh sách theo thứ tự khác - hãy bắt đầu lại từ trang đầu"),
+    ("field.tagRequired", "Thẻ không được để trống"),
+    ("field.tagTooLong", "Thẻ {tag} dài quá {max} ký tự"),
+    ("field.retentionZero", "Luồng lưu trữ phải được giữ ít nhất 1 ngày"),
+    ("field.retentionTooLong", "Luồng lưu trữ chỉ được giữ tối đa {max} ngày"),
+    ("field.described", "{field}: {message}"),
+    ("trim.notFinite", "Điểm cắt phải là số giây"),
+    ("trim.negative", "Điểm cắt không được âm"),
+    ("trim.endBeforeStart", "Điểm kết thúc {end}s phải sau điểm bắt đầu {start}s"),
+    ("trim.startPastEnd", "Điểm bắt đầu {start}s vượt quá độ dài video ({duration}s)"),
+    ("trim.endPastEnd", "Điểm kết thúc {end}s vượt quá độ dài video ({duration}s)"),
+    ("reason.endOfInput", "FFmpeg đã phát hết nội dung"),
+    ("reason.exitCode", "FFmpeg thoát với mã {code}"),
+    ("reason.terminated", "FFmpeg bị dừng đột ngột"),
+    ("reason.stopped", "FFmpeg đã được dừng"),
+    ("reason.restartFailed", "Khởi động lại thất bại: {error}"),
+    ("reason.endpointsExhausted", "Mọi máy chủ nhận luồng đều lỗi liên tục - lần cuối: {reason}"),
+    ("reason.stopFailed", "Dừng thất bại: {error}"),
+    ("reason.interrupted", "Bị gián đoạn do ứng dụng tắt"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_params_are_substituted() {
+        let params = params([("key", "abcd".to_string())]);
+        assert_eq!(
+            translate(Locale::En, "error.duplicateKey", &params),
+            "Stream key abcd is already used by a live stream"
+        );
+        assert_eq!(
+            translate(Locale::Vi, "error.duplicateKey", &params),
+            "Key abcd đã được sử dụng bởi luồng đang phát"
+        );
+    }
+
+    #[test]
+    fn test_unknown_keys_fall_back_to_the_key() {
+        assert_eq!(translate(Locale::Vi, "error.unheardOf", &Params::new()), "error.unheardOf");
+    }
+
+    #[test]
+    fn test_stored_messages_render_in_the_current_language() {
+        let exit = Message::new("reason.exitCode", &params([("code", "1".to_string())]));
+        let reason = Message::new("reason.endpointsExhausted", &Params::new()).with("reason", vec![exit]);
+        let stored = Message::from_db(&reason.to_db());
+        assert_eq!(stored, reason);
+        assert_eq!(
+            stored.render(Locale::En),
+            "Every ingest endpoint failed repeatedly - last: FFmpeg exited with code 1"
+        );
+        assert_eq!(
+            stored.render(Locale::Vi),
+            "Mọi máy chủ nhận luồng đều lỗi liên tục - lần cuối: FFmpeg thoát với mã 1"
+        );
+
+        // Reasons stored as text before read back unchanged
+        assert_eq!(Message::from_db("FFmpeg exited with code 1").render(Locale::Vi), "FFmpeg exited with code 1");
+    }
+
+    #[test]
+    fn test_catalogues_have_the_same_keys() {
+        let keys = |catalogue: &[(&'static str, &str)]| catalogue.iter().map(|(k, _)| *k).collect::<Vec<_>>();
+        assert_eq!(keys(EN), keys(VI));
+    }
+}
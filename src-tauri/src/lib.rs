@@ -1,16 +1,20 @@
 mod commands;
 mod db;
+mod i18n;
 mod stream;
+mod vault;
 
-use std::sync::Arc;
-use tauri::Manager;
+use tauri::{Emitter, Manager};
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::RwLock;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+use crate::stream::events::STREAM_EVENT;
 use crate::stream::manager::StreamManager;
 
 pub struct AppState {
-    pub stream_manager: Arc<RwLock<StreamManager>>,
+    /// Cheap handle - commands call it directly and the manager orders work per stream
+    pub stream_manager: StreamManager,
     pub instance_id: RwLock<Option<String>>,
 }
 
@@ -24,8 +28,29 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .setup(|app| {
+            let manager = StreamManager::new();
+
+            // Forward manager events to the frontend
+            let mut events = manager.subscribe();
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    match events.recv().await {
+                        Ok(event) => {
+                            if let Err(e) = handle.emit(STREAM_EVENT, &event) {
+                                tracing::error!("Error emitting stream event: {}", e);
+                            }
+                        }
+                        Err(RecvError::Lagged(missed)) => {
+                            tracing::warn!("Dropped {} stream events", missed);
+                        }
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            });
+
             let state = AppState {
-                stream_manager: Arc::new(RwLock::new(StreamManager::new())),
+                stream_manager: manager,
                 instance_id: RwLock::new(None),
             };
             app.manage(state);
@@ -35,9 +60,45 @@ pub fn run() {
             commands::initialize,
             commands::get_streams,
             commands::add_stream,
+            commands::update_stream,
             commands::start_stream,
             commands::stop_stream,
             commands::delete_stream,
+            commands::restore_stream,
+            commands::purge_stream,
+            commands::empty_archive,
+            commands::get_archive_retention,
+            commands::set_archive_retention,
+            commands::restart_stream_from_beginning,
+            commands::get_stream_sessions,
+            commands::get_uptime_report,
+            commands::export_uptime_report,
+            commands::export_streams,
+            commands::import_streams,
+            commands::get_locale,
+            commands::set_locale,
+            commands::reveal_stream_key,
+            commands::rotate_master_key,
+            commands::list_backups,
+            commands::create_backup,
+            commands::restore_backup,
+            commands::get_channels,
+            commands::add_channel,
+            commands::update_channel,
+            commands::delete_channel,
+            commands::clone_stream,
+            commands::get_templates,
+            commands::add_template,
+            commands::update_template,
+            commands::delete_template,
+            commands::create_from_template,
+            commands::get_tags,
+            commands::tag_streams,
+            commands::untag_streams,
+            commands::bulk_start,
+            commands::bulk_stop,
+            commands::bulk_reschedule,
+            commands::bulk_delete,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
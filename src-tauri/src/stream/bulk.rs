@@ -0,0 +1,58 @@
+use serde::Serialize;
+
+use crate::stream::manager::ManagerError;
+
+/// Streams a bulk command works on at once. Starts probe media and spawn FFmpeg, so a large group goes in waves.
+pub const BULK_PARALLELISM: usize = 4;
+
+/// What a bulk command did with one stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BulkOutcome {
+    Done,
+    Skipped, // Already as asked, e.g. stopping a stream that isn't live
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkResult {
+    pub stream_id: String,
+    pub name: String,
+    pub outcome: BulkOutcome,
+    pub error: Option<ManagerError>, // Why the stream failed
+}
+
+impl BulkResult {
+    pub fn new(stream_id: String, name: String, result: Option<Result<(), ManagerError>>) -> Self {
+        let (outcome, error) = match result {
+            None => (BulkOutcome::Skipped, None),
+            Some(Ok(())) => (BulkOutcome::Done, None),
+            Some(Err(e)) => (BulkOutcome::Failed, Some(e)),
+        };
+        Self { stream_id, name, outcome, error }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkReport {
+    pub tag: String,
+    pub done: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub results: Vec<BulkResult>, // In stream name order
+}
+
+impl BulkReport {
+    pub fn new(tag: String, results: Vec<BulkResult>) -> Self {
+        let count = |outcome: BulkOutcome| results.iter().filter(|r| r.outcome == outcome).count();
+        Self {
+            tag,
+            done: count(BulkOutcome::Done),
+            skipped: count(BulkOutcome::Skipped),
+            failed: count(BulkOutcome::Failed),
+            results,
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use crate::stream::types::{ScheduleConfig, ScheduleType, Stream, StreamPatch};
+
+impl StreamPatch {
+    /// The stream with the patch applied
+    pub fn apply(&self, stream: &Stream) -> Stream {
+        let mut updated = stream.clone();
+
+        if let Some(name) = &self.name {
+            updated.name = name.trim().to_string();
+        }
+        if let Some(channel_id) = &self.channel_id {
+            updated.channel_id = channel_id.clone();
+        }
+        if let Some(key) = &self.youtube_key {
+            updated.youtube_key = key.trim().to_string();
+        }
+        if let Some(path) = &self.video_path {
+            updated.video_path = path.clone();
+        }
+        if let Some(trim) = self.trim {
+            updated.trim = trim;
+        }
+        if let Some(endpoints) = &self.ingest_endpoints {
+            updated.ingest_endpoints = endpoints.clone();
+        }
+        if let Some(clips) = &self.clips {
+            updated.clips = clips.clone();
+        }
+        if let Some(resume) = &self.resume {
+            updated.resume = resume.clone();
+        }
+        if let Some(schedule) = &self.schedule {
+            updated.schedule = schedule.clone();
+        }
+        if let Some(tags) = &self.tags {
+            updated.tags = normalize_tags(tags);
+        }
+
+        // A saved position means nothing in other media
+        if updated.video_path != stream.video_path || updated.trim != stream.trim {
+            updated.playback_position = None;
+        }
+
+        updated
+    }
+}
+
+/// Trimmed, without blanks, and each tag once whatever its case, in sorted order
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if !normalized.iter().any(|existing| existing.eq_ignore_ascii_case(tag)) {
+            normalized.push(tag.to_string());
+        }
+    }
+    normalized.sort_by_key(|tag| tag.to_lowercase());
+    normalized
+}
+
+/// Changed settings a running FFmpeg process was launched with. A live stream
+/// only picks these up by restarting.
+pub fn restart_fields(before: &Stream, after: &Stream) -> Vec<&'static str> {
+    let mut fields = Vec::new();
+
+    if before.channel_id != after.channel_id {
+        fields.push("channelId");
+    } else if before.youtube_key != after.youtube_key {
+        fields.push("youtubeKey");
+    }
+    if before.video_path != after.video_path {
+        fields.push("videoPath");
+    }
+    if before.trim != after.trim {
+        fields.push("trim");
+    }
+    if before.ingest_endpoints != after.ingest_endpoints {
+        fields.push("ingestEndpoints");
+    }
+    if before.clips != after.clips {
+        fields.push("clips");
+    }
+    if schedule_changed(before, after) && schedule_is_in_plan(before, after) {
+        fields.push("schedule");
+    }
+
+    fields
+}
+
+pub fn schedule_changed(before: &Stream, after: &Stream) -> bool {
+    before.schedule != after.schedule
+}
+
+/// A play count, or a fade into the deadline, is baked into the FFmpeg command line.
+/// Any other deadline is only a timer and can be re-armed.
+fn schedule_is_in_plan(before: &Stream, after: &Stream) -> bool {
+    let plays = |schedule: &ScheduleConfig| schedule.schedule_type == ScheduleType::Plays;
+    let has_deadline = |schedule: &ScheduleConfig| {
+        matches!(schedule.schedule_type, ScheduleType::Duration | ScheduleType::Absolute)
+    };
+    let fades_into_deadline = |stream: &Stream| {
+        stream.clips.outro_path.is_none() && stream.clips.fade_out_seconds > 0 && has_deadline(&stream.schedule)
+    };
+
+    plays(&before.schedule) || plays(&after.schedule) || fades_into_deadline(before) || fades_into_deadline(after)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::types::{DurationConfig, MediaTrim};
+
+    fn stream() -> Stream {
+        serde_json::from_value(serde_json::json!({
+            "id": "abc",
+            "name": "Lofi radio",
+            "youtubeKey": "aaaa-bbbb",
+            "videoPath": "/videos/lofi.mp4",
+            "status": "live",
+            "schedule": { "type": "manual", "duration": null, "absolute": null },
+            "startedAt": null,
+            "stoppedAt": null,
+            "createdAt": "2024-05-07T10:00:00+00:00",
+            "playbackPosition": 42.0,
+        }))
+        .unwrap()
+    }
+
+    fn duration(hours: u32) -> ScheduleConfig {
+        ScheduleConfig {
+            schedule_type: ScheduleType::Duration,
+            duration: Some(DurationConfig { hours, minutes: 0, seconds: 0 }),
+            absolute: None,
+            plays: None,
+        }
+    }
+
+    #[test]
+    fn test_timer_only_changes_apply_live() {
+        let before = stream();
+        let patch = StreamPatch {
+            name: Some("  Lofi radio 24/7 ".into()),
+            schedule: Some(duration(2)),
+            ..Default::default()
+        };
+        let after = patch.apply(&before);
+
+        assert_eq!(after.name, "Lofi radio 24/7");
+        assert_eq!(after.playback_position, Some(42.0));
+        assert!(restart_fields(&before, &after).is_empty());
+        assert!(schedule_changed(&before, &after));
+    }
+
+    #[test]
+    fn test_tags_are_normalized_and_apply_live() {
+        let before = stream();
+        let tags = vec![" Client A ".into(), "campaign".into(), "client a".into(), "  ".into()];
+        let after = StreamPatch { tags: Some(tags), ..Default::default() }.apply(&before);
+
+        assert_eq!(after.tags, vec!["campaign", "Client A"]);
+        assert!(restart_fields(&before, &after).is_empty());
+    }
+
+    #[test]
+    fn test_launch_settings_need_a_restart() {
+        let before = stream();
+        let patch = StreamPatch {
+            youtube_key: Some("cccc-dddd".into()),
+            trim: Some(MediaTrim { start_seconds: Some(5.0), end_seconds: None }),
+            ..Default::default()
+        };
+        let after = patch.apply(&before);
+
+        assert_eq!(restart_fields(&before, &after), vec!["youtubeKey", "trim"]);
+        assert_eq!(after.playback_position, None);
+    }
+
+    #[test]
+    fn test_schedule_with_fade_needs_a_restart() {
+        let mut before = stream();
+        before.clips.fade_out_seconds = 5;
+        let after = StreamPatch { schedule: Some(duration(1)), ..Default::default() }.apply(&before);
+
+        assert_eq!(restart_fields(&before, &after), vec!["schedule"]);
+    }
+}
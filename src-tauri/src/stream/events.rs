@@ -0,0 +1,131 @@
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::stream::types::StreamStatus;
+
+/// Tauri event name every `StreamEvent` is emitted under
+pub const STREAM_EVENT: &str = "stream-event";
+
+/// Events a slow subscriber can fall behind by before it starts missing them
+const CHANNEL_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScheduledAction {
+    Stop,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum StreamEvent {
+    StatusChanged {
+        stream_id: String,
+        status: StreamStatus,
+    },
+    Error {
+        stream_id: String,
+        reason: String,
+    },
+    /// Sent on every monitor tick for each running stream
+    Stats {
+        stream_id: String,
+        elapsed_seconds: u64,
+        live_seconds: u64,
+        playback_position: Option<f64>,
+        active_endpoint: Option<String>,
+    },
+    SchedulerArmed {
+        stream_id: String,
+        action: ScheduledAction,
+        fires_in_seconds: u64,
+    },
+    SchedulerFired {
+        stream_id: String,
+        action: ScheduledAction,
+    },
+}
+
+/// Broadcasts stream events to any number of subscribers, such as the Tauri window
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<StreamEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<StreamEvent> {
+        self.sender.subscribe()
+    }
+
+    pub fn emit(&self, event: StreamEvent) {
+        // Nobody listening is fine - events are informational
+        let _ = self.sender.send(event);
+    }
+
+    pub fn status(&self, stream_id: &str, status: StreamStatus) {
+        self.emit(StreamEvent::StatusChanged {
+            stream_id: stream_id.to_string(),
+            status,
+        });
+    }
+
+    pub fn error(&self, stream_id: &str, reason: impl Into<String>) {
+        self.emit(StreamEvent::Error {
+            stream_id: stream_id.to_string(),
+            reason: reason.into(),
+        });
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_serialization() {
+        let event = StreamEvent::Stats {
+            stream_id: "abc".into(),
+            elapsed_seconds: 42,
+            live_seconds: 50,
+            playback_position: None,
+            active_endpoint: Some("rtmp://a.rtmp.youtube.com/live2".into()),
+        };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "type": "stats",
+                "streamId": "abc",
+                "elapsedSeconds": 42,
+                "liveSeconds": 50,
+                "playbackPosition": null,
+                "activeEndpoint": "rtmp://a.rtmp.youtube.com/live2",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_receive_events() {
+        let bus = EventBus::new();
+        let mut first = bus.subscribe();
+        let mut second = bus.subscribe();
+
+        bus.status("abc", StreamStatus::Live);
+
+        let expected = StreamEvent::StatusChanged {
+            stream_id: "abc".into(),
+            status: StreamStatus::Live,
+        };
+        assert_eq!(first.recv().await.unwrap(), expected);
+        assert_eq!(second.recv().await.unwrap(), expected);
+    }
+}
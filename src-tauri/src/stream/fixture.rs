@@ -0,0 +1,48 @@
+//! Setup shared by the tests that drive a `StreamManager`
+
+use uuid::Uuid;
+
+use crate::stream::manager::StreamManager;
+use crate::stream::types::{ChannelInput, Stream, StreamInput};
+
+/// A manager working on a fresh database, without the background tasks
+pub async fn manager() -> StreamManager {
+    let dir = std::env::temp_dir().join(format!("ylm-manager-{}", Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let manager = StreamManager::new();
+    manager.attach(&dir.join("streams.db")).await.unwrap();
+    manager
+}
+
+/// A manually stopped stream playing this file
+pub fn input(name: &str, youtube_key: &str) -> StreamInput {
+    serde_json::from_value(serde_json::json!({
+        "name": name,
+        "youtubeKey": youtube_key,
+        "videoPath": file!(),
+        "schedule": { "type": "manual", "duration": null, "absolute": null },
+    }))
+    .unwrap()
+}
+
+/// A channel for this key, publishing to the default ingest
+pub fn channel(name: &str, youtube_key: &str) -> ChannelInput {
+    ChannelInput { name: name.to_string(), youtube_key: youtube_key.to_string(), destination: None, notes: String::new() }
+}
+
+/// A fresh manager holding a stream for each input, added in order
+pub async fn with_streams<const N: usize>(inputs: [StreamInput; N]) -> (StreamManager, [Stream; N]) {
+    let manager = manager().await;
+    let mut streams = Vec::with_capacity(N);
+    for input in inputs {
+        streams.push(manager.add_stream(input).await.unwrap());
+    }
+    (manager, streams.try_into().unwrap())
+}
+
+/// An ingest endpoint on a port nothing listens on
+pub async fn refusing_endpoint() -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    format!("rtmp://127.0.0.1:{}/live2", port)
+}
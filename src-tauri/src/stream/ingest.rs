@@ -0,0 +1,230 @@
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+pub const YOUTUBE_PRIMARY_INGEST: &str = "rtmp://a.rtmp.youtube.com/live2";
+pub const YOUTUBE_BACKUP_INGEST: &str = "rtmp://b.rtmp.youtube.com/live2?backup=1";
+
+/// Consecutive publish failures on one endpoint before moving to the next
+pub const FAILURES_BEFORE_ROTATE: u32 = 2;
+
+/// A process that stayed up this long is considered a successful publish
+pub const STABLE_AFTER_SECS: u64 = 30;
+
+const DEFAULT_RTMP_PORT: u16 = 1935;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+pub fn default_endpoints() -> Vec<String> {
+    vec![
+        YOUTUBE_PRIMARY_INGEST.to_string(),
+        YOUTUBE_BACKUP_INGEST.to_string(),
+    ]
+}
+
+/// Build the full publish URL for an ingest endpoint and stream key
+pub fn publish_url(endpoint: &str, stream_key: &str) -> String {
+    format!("{}/{}", endpoint.trim_end_matches('/'), stream_key)
+}
+
+/// Extract `host:port` from an RTMP endpoint URL
+pub fn host_port(endpoint: &str) -> Option<String> {
+    let rest = endpoint.split_once("://").map(|(_, r)| r).unwrap_or(endpoint);
+    let authority = rest.split(['/', '?']).next()?;
+    if authority.is_empty() {
+        return None;
+    }
+
+    // Bracketed IPv6 literals keep their colons
+    let has_port = match authority.rfind(']') {
+        Some(end) => authority[end..].contains(':'),
+        None => authority.contains(':'),
+    };
+
+    if has_port {
+        Some(authority.to_string())
+    } else {
+        Some(format!("{}:{}", authority, DEFAULT_RTMP_PORT))
+    }
+}
+
+/// Check that an endpoint accepts TCP connections
+pub async fn is_reachable(endpoint: &str) -> bool {
+    let Some(addr) = host_port(endpoint) else {
+        return false;
+    };
+
+    matches!(
+        tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(&addr)).await,
+        Ok(Ok(_))
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailoverAction {
+    /// Try the same endpoint again
+    Retry,
+    /// Moved on to the next endpoint
+    Rotated,
+    /// Every endpoint has failed repeatedly - give up
+    Exhausted,
+}
+
+/// Ordered ingest endpoints for one destination, rotating on repeated publish failures
+#[derive(Debug, Clone)]
+pub struct IngestFailover {
+    endpoints: Vec<String>,
+    current: usize,
+    consecutive_failures: u32,
+    failures_since_success: u32,
+}
+
+impl IngestFailover {
+    pub fn new(endpoints: Vec<String>) -> Self {
+        let endpoints = if endpoints.is_empty() {
+            default_endpoints()
+        } else {
+            endpoints
+        };
+
+        Self {
+            endpoints,
+            current: 0,
+            consecutive_failures: 0,
+            failures_since_success: 0,
+        }
+    }
+
+    pub fn active(&self) -> &str {
+        &self.endpoints[self.current]
+    }
+
+    pub fn endpoints(&self) -> &[String] {
+        &self.endpoints
+    }
+
+    pub fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.failures_since_success = 0;
+    }
+
+    pub fn record_failure(&mut self) -> FailoverAction {
+        self.consecutive_failures += 1;
+        self.failures_since_success += 1;
+
+        let budget = self.endpoints.len() as u32 * FAILURES_BEFORE_ROTATE;
+        if self.failures_since_success >= budget {
+            return FailoverAction::Exhausted;
+        }
+
+        if self.consecutive_failures >= FAILURES_BEFORE_ROTATE {
+            self.rotate();
+            FailoverAction::Rotated
+        } else {
+            FailoverAction::Retry
+        }
+    }
+
+    fn rotate(&mut self) {
+        self.current = (self.current + 1) % self.endpoints.len();
+        self.consecutive_failures = 0;
+        tracing::warn!("Rotating ingest to {}", self.active());
+    }
+
+    /// Starting at the active endpoint, move to the first one that accepts connections.
+    /// A refused connection is a publish failure, so it rotates immediately.
+    pub async fn select_reachable(&mut self) -> Option<&str> {
+        for _ in 0..self.endpoints.len() {
+            if is_reachable(self.active()).await {
+                return Some(self.active());
+            }
+            tracing::warn!("Ingest endpoint refused connection: {}", self.active());
+            self.failures_since_success += 1;
+            self.rotate();
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Bind then drop a listener so the port refuses connections
+    async fn refusing_endpoint() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+        format!("rtmp://127.0.0.1:{}/live2", port)
+    }
+
+    async fn accepting_endpoint() -> (TcpListener, String) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        (listener, format!("rtmp://127.0.0.1:{}/live2?backup=1", port))
+    }
+
+    #[test]
+    fn test_publish_url() {
+        assert_eq!(
+            publish_url(YOUTUBE_PRIMARY_INGEST, "abcd"),
+            "rtmp://a.rtmp.youtube.com/live2/abcd"
+        );
+        assert_eq!(
+            publish_url(YOUTUBE_BACKUP_INGEST, "abcd"),
+            "rtmp://b.rtmp.youtube.com/live2?backup=1/abcd"
+        );
+    }
+
+    #[test]
+    fn test_host_port() {
+        assert_eq!(host_port(YOUTUBE_PRIMARY_INGEST).as_deref(), Some("a.rtmp.youtube.com:1935"));
+        assert_eq!(host_port(YOUTUBE_BACKUP_INGEST).as_deref(), Some("b.rtmp.youtube.com:1935"));
+        assert_eq!(host_port("rtmp://127.0.0.1:1940/live2").as_deref(), Some("127.0.0.1:1940"));
+        assert_eq!(host_port("rtmp://[::1]/live2").as_deref(), Some("[::1]:1935"));
+        assert_eq!(host_port("rtmp:///live2"), None);
+    }
+
+    #[test]
+    fn test_rotates_after_repeated_failures() {
+        let mut failover = IngestFailover::new(Vec::new());
+        assert_eq!(failover.active(), YOUTUBE_PRIMARY_INGEST);
+
+        assert_eq!(failover.record_failure(), FailoverAction::Retry);
+        assert_eq!(failover.record_failure(), FailoverAction::Rotated);
+        assert_eq!(failover.active(), YOUTUBE_BACKUP_INGEST);
+
+        assert_eq!(failover.record_failure(), FailoverAction::Retry);
+        assert_eq!(failover.record_failure(), FailoverAction::Exhausted);
+    }
+
+    #[test]
+    fn test_success_resets_budget() {
+        let mut failover = IngestFailover::new(Vec::new());
+        failover.record_failure();
+        failover.record_failure();
+        failover.record_failure();
+        failover.record_success();
+
+        assert_eq!(failover.active(), YOUTUBE_BACKUP_INGEST);
+        assert_eq!(failover.record_failure(), FailoverAction::Retry);
+    }
+
+    #[tokio::test]
+    async fn test_select_skips_refusing_endpoint() {
+        let refusing = refusing_endpoint().await;
+        let (_listener, accepting) = accepting_endpoint().await;
+
+        let mut failover = IngestFailover::new(vec![refusing, accepting.clone()]);
+        assert_eq!(failover.select_reachable().await, Some(accepting.as_str()));
+        assert_eq!(failover.active(), accepting);
+    }
+
+    #[tokio::test]
+    async fn test_select_none_reachable() {
+        let mut failover = IngestFailover::new(vec![
+            refusing_endpoint().await,
+            refusing_endpoint().await,
+        ]);
+        assert_eq!(failover.select_reachable().await, None);
+    }
+}
@@ -1,60 +1,383 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
-use std::sync::Arc;
-use tokio::sync::RwLock;
-use thiserror::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot, RwLock};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
 use uuid::Uuid;
 
-use crate::db::Database;
-use crate::stream::process::FFmpegProcess;
+use crate::db::backup::{self, BackupError, BackupInfo};
+use crate::db::migrations;
+use crate::db::{Database, Heartbeat, MigrationError};
+use crate::i18n::{self, Locale, Message, Params};
+use crate::vault::{Vault, VaultError};
+use crate::stream::bulk::{BulkReport, BulkResult, BULK_PARALLELISM};
+use crate::stream::edit;
+use crate::stream::events::{EventBus, ScheduledAction, StreamEvent};
+use crate::stream::ingest::{self, FailoverAction, IngestFailover};
+use crate::stream::playout::{self, OutroSegment, PlayoutPlan};
+use crate::stream::probe;
+use crate::stream::query::{Cursor, SortDirection, StreamPage, StreamQuery, StreamSort};
+use crate::stream::redact;
+use crate::stream::process::{ExitReport, FFmpegProcess, ProcessExit};
+use crate::stream::report::{self, ReportFormat, ReportQuery, UptimeReport};
+use crate::stream::transfer::{
+    self, ConflictStrategy, ExportReport, ImportAction, ImportOptions, ImportReport, ImportRow, StreamRecord,
+    TransferFormat,
+};
 use crate::stream::scheduler::Scheduler;
-use crate::stream::types::{Stream, StreamInput, StreamStatus};
+use crate::stream::state::{SessionEnd, Transition, TransitionOutcome};
+use crate::stream::template;
+use crate::stream::validation::{self, ErrorCode, FieldError};
+use crate::stream::types::{
+    Channel, ChannelInput, ChannelPatch, EncoderStats, FailureKind, MediaTrim, ScheduleConfig, ScheduleType,
+    SessionEndReason, Stream, StreamInput, StreamPatch, StreamSession, StreamStatus, StreamTemplate, Tag, TemplateInput,
+    TrimError,
+};
 
-#[derive(Error, Debug)]
+#[derive(Debug)]
 pub enum ManagerError {
-    #[error("Database error: {0}")]
-    Database(#[from] sqlx::Error),
-    #[error("Stream not found: {0}")]
+    Database(sqlx::Error),
+    SchemaTooNew { found: u32, supported: u32 },
     NotFound(String),
-    #[error("Stream already running: {0}")]
+    Archived(String),
+    NotArchived(String),
+    ChannelNotFound(String),
+    TemplateNotFound(String),
+    ChannelInUse { name: String, count: usize },
     AlreadyRunning(String),
-    #[error("Duplicate stream key: {0} - Key đã được sử dụng bởi luồng đang phát")]
     DuplicateKey(String),
-    #[error("FFmpeg error: {0}")]
     FFmpeg(String),
-    #[error("IO error: {0}")]
-    Io(#[from] std::io::Error),
+    NoIngestEndpoint,
+    ExitedImmediately,
+    Io(std::io::Error),
+    InvalidKeyFile(String),
+    UndecryptableKey,
+    KeysNotResealed(Vec<String>),
+    InvalidTrim { path: String, error: TrimError },
+    WorkerStopped(String),
+    InvalidTransition { from: StreamStatus, to: StreamStatus },
+    InvalidRange { from: String, to: String },
+    Validation(Vec<FieldError>),
+    RestartRequired(String),
+    InvalidImport(String),
+    BackupNotFound(String),
+    BackupDamaged { file: String, error: String },
+    StreamsNotStopped(Vec<String>),
+    DatabaseLost(String),
 }
 
+impl ManagerError {
+    /// Stable name of the error for the frontend to branch on
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Database(_) => "database",
+            Self::SchemaTooNew { .. } => "schemaTooNew",
+            Self::NotFound(_) => "notFound",
+            Self::Archived(_) => "archived",
+            Self::NotArchived(_) => "notArchived",
+            Self::ChannelNotFound(_) => "channelNotFound",
+            Self::TemplateNotFound(_) => "templateNotFound",
+            Self::ChannelInUse { .. } => "channelInUse",
+            Self::AlreadyRunning(_) => "alreadyRunning",
+            Self::DuplicateKey(_) => "duplicateKey",
+            Self::FFmpeg(_) => "ffmpeg",
+            Self::NoIngestEndpoint => "noIngestEndpoint",
+            Self::ExitedImmediately => "exitedImmediately",
+            Self::Io(_) => "io",
+            Self::InvalidKeyFile(_) => "invalidKeyFile",
+            Self::UndecryptableKey => "undecryptableKey",
+            Self::KeysNotResealed(_) => "keysNotResealed",
+            Self::InvalidTrim { .. } => "invalidTrim",
+            Self::WorkerStopped(_) => "workerStopped",
+            Self::InvalidTransition { .. } => "invalidTransition",
+            Self::InvalidRange { .. } => "invalidRange",
+            Self::Validation(_) => "validation",
+            Self::RestartRequired(_) => "restartRequired",
+            Self::InvalidImport(_) => "invalidImport",
+            Self::BackupNotFound(_) => "backupNotFound",
+            Self::BackupDamaged { .. } => "backupDamaged",
+            Self::StreamsNotStopped(_) => "streamsNotStopped",
+            Self::DatabaseLost(_) => "databaseLost",
+        }
+    }
+
+    /// Values the message is built from, for the frontend to render its own text with
+    pub fn params(&self) -> Params {
+        match self {
+            Self::Database(e) => i18n::params([("error", e.to_string())]),
+            Self::SchemaTooNew { found, supported } => {
+                i18n::params([("found", found.to_string()), ("supported", supported.to_string())])
+            }
+            Self::Io(e) => i18n::params([("error", e.to_string())]),
+            Self::FFmpeg(error) => i18n::params([("error", error.clone())]),
+            Self::NotFound(id)
+            | Self::Archived(id)
+            | Self::NotArchived(id)
+            | Self::ChannelNotFound(id)
+            | Self::TemplateNotFound(id)
+            | Self::AlreadyRunning(id)
+            | Self::WorkerStopped(id) => i18n::params([("id", id.clone())]),
+            Self::ChannelInUse { name, count } => i18n::params([("name", name.clone()), ("count", count.to_string())]),
+            Self::DuplicateKey(key) => i18n::params([("key", key.clone())]),
+            Self::InvalidKeyFile(path) => i18n::params([("path", path.clone())]),
+            Self::KeysNotResealed(channels) => {
+                i18n::params([("count", channels.len().to_string()), ("channels", channels.join(", "))])
+            }
+            Self::NoIngestEndpoint | Self::ExitedImmediately | Self::UndecryptableKey => Params::new(),
+            Self::InvalidTrim { path, error } => {
+                let mut params = error.params();
+                params.insert("path", path.clone());
+                params.insert("trim", error.key().to_string());
+                params
+            }
+            Self::InvalidTransition { from, to } => {
+                i18n::params([("from", from.to_string()), ("to", to.to_string())])
+            }
+            Self::InvalidRange { from, to } => i18n::params([("from", from.clone()), ("to", to.clone())]),
+            Self::Validation(errors) => i18n::params([("count", errors.len().to_string())]),
+            Self::RestartRequired(fields) => i18n::params([("fields", fields.clone())]),
+            Self::InvalidImport(error) => i18n::params([("error", error.clone())]),
+            Self::BackupNotFound(file) => i18n::params([("file", file.clone())]),
+            Self::BackupDamaged { file, error } => i18n::params([("file", file.clone()), ("error", error.clone())]),
+            Self::StreamsNotStopped(ids) => {
+                i18n::params([("count", ids.len().to_string()), ("streams", ids.join(", "))])
+            }
+            Self::DatabaseLost(error) => i18n::params([("error", error.clone())]),
+        }
+    }
+
+    /// The message by key and values, to render later - e.g. stored as a failure reason
+    pub fn to_message(&self) -> Message {
+        let message = Message::new(format!("error.{}", self.kind()), &self.params());
+        // Nested messages follow the same language
+        match self {
+            Self::InvalidTrim { error, .. } => message.with("error", vec![Message::new(error.key(), &error.params())]),
+            Self::Validation(errors) => message.with("errors", errors.iter().map(FieldError::to_message).collect()),
+            _ => message,
+        }
+    }
+
+    pub fn message(&self, locale: Locale) -> String {
+        self.to_message().render(locale)
+    }
+
+    /// The message in the operator's language
+    pub fn localized(&self) -> String {
+        self.message(i18n::locale())
+    }
+}
+
+/// Logs stay in English whatever the operator's language
+impl std::fmt::Display for ManagerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message(Locale::En))
+    }
+}
+
+impl std::error::Error for ManagerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Database(e) => Some(e),
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<sqlx::Error> for ManagerError {
+    fn from(error: sqlx::Error) -> Self {
+        Self::Database(error)
+    }
+}
+
+impl From<MigrationError> for ManagerError {
+    fn from(error: MigrationError) -> Self {
+        match error {
+            MigrationError::Database(e) => Self::Database(e),
+            MigrationError::NewerSchema { found, supported } => Self::SchemaTooNew { found, supported },
+        }
+    }
+}
+
+impl From<VaultError> for ManagerError {
+    fn from(error: VaultError) -> Self {
+        match error {
+            VaultError::Io(e) => Self::Io(e),
+            VaultError::InvalidKeyFile(path) => Self::InvalidKeyFile(path.display().to_string()),
+            VaultError::UnknownKey | VaultError::Undecryptable => Self::UndecryptableKey,
+        }
+    }
+}
+
+impl From<BackupError> for ManagerError {
+    fn from(error: BackupError) -> Self {
+        match error {
+            BackupError::Database(e) => Self::Database(e),
+            BackupError::Io(e) => Self::Io(e),
+            BackupError::NotFound(file) => Self::BackupNotFound(file),
+            BackupError::Damaged { file, error } => Self::BackupDamaged { file, error },
+        }
+    }
+}
+
+impl From<std::io::Error> for ManagerError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Commands return errors to the frontend as `{ kind, params, message, errors }`, with
+/// `message` in the operator's language and `errors` listing the rejected fields of a validation error
+impl Serialize for ManagerError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let errors: &[FieldError] = match self {
+            Self::Validation(errors) => errors,
+            _ => &[],
+        };
+
+        let mut state = serializer.serialize_struct("ManagerError", 4)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("params", &self.params())?;
+        state.serialize_field("message", &self.localized())?;
+        state.serialize_field("errors", errors)?;
+        state.end()
+    }
+}
+
+type Reply = oneshot::Sender<Result<(), ManagerError>>;
+
+/// Work a stream's actor performs, strictly one message at a time
+enum StreamCommand {
+    Start(Reply),
+    Stop(Reply),
+    RestartFromBeginning(Reply),
+    /// Relaunch a live stream, e.g. to pick up its channel's new key
+    Restart(Reply),
+    /// Save new settings, restarting a live stream only if `restart` allows it
+    Update { patch: Box<StreamPatch>, restart: bool, reply: Reply },
+    /// Stop the stream if it is live and move it to the archive
+    Archive(Reply),
+    /// Erase an archived stream for good
+    Purge(Reply),
+    /// Sent by the supervisor when one of the stream's FFmpeg processes exits
+    ProcessExited(ExitReport),
+    /// The schedule's stop deadline arrived
+    ScheduledStop,
+}
+
+impl StreamCommand {
+    /// Answer a command that will never run
+    fn reject(self, error: ManagerError) {
+        match self {
+            Self::Start(reply)
+            | Self::Stop(reply)
+            | Self::RestartFromBeginning(reply)
+            | Self::Restart(reply)
+            | Self::Update { reply, .. }
+            | Self::Archive(reply)
+            | Self::Purge(reply) => {
+                let _ = reply.send(Err(error));
+            }
+            Self::ProcessExited(_) | Self::ScheduledStop => {}
+        }
+    }
+}
+
+/// Handle to the stream supervisor. Clones are cheap and share all state.
+///
+/// Each stream gets its own actor task, so operations on one stream run in
+/// order while different streams start and stop concurrently.
+#[derive(Clone)]
 pub struct StreamManager {
-    db: Option<Database>,
+    db: Arc<std::sync::RwLock<Option<Database>>>,
+    /// Seals stream keys at rest. Held for reading across a seal and its write, so a rotation never misses a row.
+    vault: Arc<RwLock<Option<Vault>>>,
     processes: Arc<RwLock<HashMap<String, FFmpegProcess>>>,
     schedulers: Arc<RwLock<HashMap<String, Scheduler>>>,
+    failovers: Arc<RwLock<HashMap<String, IngestFailover>>>,
+    events: EventBus,
+    /// Every FFmpeg wait task reports on this channel
+    exits: mpsc::UnboundedSender<ExitReport>,
+    exit_reports: Arc<Mutex<Option<mpsc::UnboundedReceiver<ExitReport>>>>,
+    actors: Arc<Mutex<HashMap<String, mpsc::UnboundedSender<StreamCommand>>>>,
+    /// Channels of streams part-way through a start, so two streams can't race onto one key
+    starting: Arc<tokio::sync::Mutex<HashMap<String, String>>>,
 }
 
 impl StreamManager {
     pub fn new() -> Self {
+        let (exits, exit_reports) = mpsc::unbounded_channel();
         Self {
-            db: None,
+            db: Arc::new(std::sync::RwLock::new(None)),
+            vault: Arc::new(RwLock::new(None)),
             processes: Arc::new(RwLock::new(HashMap::new())),
             schedulers: Arc::new(RwLock::new(HashMap::new())),
+            failovers: Arc::new(RwLock::new(HashMap::new())),
+            events: EventBus::new(),
+            exits,
+            exit_reports: Arc::new(Mutex::new(Some(exit_reports))),
+            actors: Arc::new(Mutex::new(HashMap::new())),
+            starting: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
         }
     }
 
-    pub async fn initialize(&mut self, instance_id: &str) -> Result<(), ManagerError> {
+    /// Receive status, error, stats and scheduler events for every stream
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<StreamEvent> {
+        self.events.subscribe()
+    }
+
+    pub async fn initialize(&self, instance_id: &str) -> Result<(), ManagerError> {
         let db_path = Self::get_db_path(instance_id);
         tracing::info!("Initializing database at: {:?}", db_path);
-        
-        let db = Database::new(&db_path).await?;
-        db.migrate().await?;
-        self.db = Some(db);
-        
-        // Start process monitor
-        self.start_process_monitor();
+        self.attach(&db_path).await?;
+
+        // Supervise FFmpeg processes - only once, the receiver is consumed here
+        let exit_reports = self.exit_reports.lock().unwrap_or_else(PoisonError::into_inner).take();
+        if let Some(exits) = exit_reports {
+            self.start_supervisor(exits);
+            self.start_stats_ticker();
+            self.start_backup_ticker();
+            self.start_archive_sweeper();
+        }
         
         Ok(())
     }
 
+    /// Work on the database at `db_path` from now on, without the background tasks `initialize` starts
+    pub async fn attach(&self, db_path: &Path) -> Result<(), ManagerError> {
+        let mut vault = Vault::load(&db_path.with_extension("key"))?;
+        let db = Self::open_database(db_path, &vault).await?;
+        vault.commit()?;
+        *self.vault.write().await = Some(vault);
+
+        // Text the backend generates follows the operator's language from the start
+        if let Some(locale) = db.get_setting(LOCALE_SETTING).await?.as_deref().and_then(Locale::from_db) {
+            i18n::set_locale(locale);
+        }
+        *self.db.write().unwrap_or_else(PoisonError::into_inner) = Some(db);
+        Ok(())
+    }
+
+    /// Open the database at `path` and bring it up to date, sealing its keys with the vault's master key
+    async fn open_database(path: &Path, vault: &Vault) -> Result<Database, ManagerError> {
+        let db = Database::new(path).await?;
+        db.migrate().await?;
+
+        // Streams left live by a previous run have no process any more
+        let interrupted = db.mark_interrupted_streams().await?;
+        if interrupted > 0 {
+            tracing::warn!("Marked {} streams interrupted by the last shutdown as error", interrupted);
+        }
+
+        // Seal keys still stored in the clear, and finish a rotation a crash interrupted
+        let resealed = Self::reseal_keys(&db, vault).await?;
+        if resealed > 0 {
+            tracing::info!("Sealed {} channel keys with the current master key", resealed);
+        }
+        Ok(db)
+    }
+
     fn get_db_path(instance_id: &str) -> PathBuf {
         let data_dir = dirs::data_dir()
             .unwrap_or_else(|| PathBuf::from("."))
@@ -63,107 +386,459 @@ impl StreamManager {
         data_dir.join(format!("streams_{}.db", &instance_id[..8]))
     }
 
-    fn db(&self) -> Result<&Database, ManagerError> {
-        self.db.as_ref().ok_or_else(|| {
+    fn db(&self) -> Result<Database, ManagerError> {
+        self.db.read().unwrap_or_else(PoisonError::into_inner).clone().ok_or_else(|| {
             ManagerError::Database(sqlx::Error::Configuration("Database not initialized".into()))
         })
     }
 
-    /// Monitor FFmpeg processes for unexpected exits (YouTube errors)
-    fn start_process_monitor(&self) {
-        let processes = self.processes.clone();
-        let db = self.db.clone();
-        
+    async fn vault(&self) -> Result<tokio::sync::RwLockReadGuard<'_, Vault>, ManagerError> {
+        tokio::sync::RwLockReadGuard::try_map(self.vault.read().await, Option::as_ref).map_err(|_| {
+            ManagerError::Database(sqlx::Error::Configuration("Database not initialized".into()))
+        })
+    }
+
+    /// A stored stream key in the clear - only for the publish URL and an explicit reveal
+    async fn open_key(&self, sealed: &str) -> Result<String, ManagerError> {
+        Ok(self.vault().await?.open(sealed)?)
+    }
+
+    /// Reseal every stored key not yet sealed with the vault's current master key, masking it alongside.
+    /// Writes nothing unless every key opens, so the master key is only committed once none is lost.
+    async fn reseal_keys(db: &Database, vault: &Vault) -> Result<usize, ManagerError> {
+        let mut resealed = Vec::new();
+        let mut failed = Vec::new();
+        for (id, key, masked) in db.get_channel_keys().await? {
+            if vault.is_current(&key) && masked.is_some() {
+                continue;
+            }
+            match vault.reveal(&key) {
+                Ok(plain) => resealed.push((id, vault.seal(&plain), redact::mask_key(&plain))),
+                Err(e) => {
+                    tracing::error!("Cannot reseal the key of channel {}: {}", id, e);
+                    failed.push(id);
+                }
+            }
+        }
+        if !failed.is_empty() {
+            return Err(ManagerError::KeysNotResealed(failed));
+        }
+        db.replace_channel_keys(&resealed).await?;
+        Ok(resealed.len())
+    }
+
+    /// Queue a command on the stream's actor, spawning the actor on first use
+    fn dispatch(&self, id: &str, command: StreamCommand) {
+        let mut actors = self.actors.lock().unwrap_or_else(PoisonError::into_inner);
+        let actor = actors.entry(id.to_string())
+            .or_insert_with(|| self.spawn_actor(id));
+        if actor.is_closed() {
+            *actor = self.spawn_actor(id);
+        }
+        if let Err(mpsc::error::SendError(command)) = actor.send(command) {
+            command.reject(ManagerError::WorkerStopped(id.to_string()));
+        }
+    }
+
+    /// Queue a command only if the stream already has an actor - streams
+    /// without one have nothing running that the command could concern
+    fn notify(&self, id: &str, command: StreamCommand) {
+        let actors = self.actors.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(actor) = actors.get(id) {
+            let _ = actor.send(command);
+        }
+    }
+
+    /// Run a command on the stream's actor and wait for its result
+    async fn request(&self, id: &str, command: impl FnOnce(Reply) -> StreamCommand) -> Result<(), ManagerError> {
+        let (reply, result) = oneshot::channel();
+        self.dispatch(id, command(reply));
+        result.await.map_err(|_| ManagerError::WorkerStopped(id.to_string()))?
+    }
+
+    fn spawn_actor(&self, id: &str) -> mpsc::UnboundedSender<StreamCommand> {
+        let (actor, mut commands) = mpsc::unbounded_channel();
+        let manager = self.clone();
+        let id = id.to_string();
+
         tokio::spawn(async move {
+            while let Some(command) = commands.recv().await {
+                if !manager.handle_command(&id, command).await {
+                    if manager.retire_if_idle(&id, &mut commands).await {
+                        break;
+                    }
+                    continue;
+                }
+
+                // Stream deleted - retire the actor and turn away anything already queued
+                manager.actors.lock().unwrap_or_else(PoisonError::into_inner).remove(&id);
+                commands.close();
+                while let Some(command) = commands.recv().await {
+                    command.reject(ManagerError::NotFound(id.clone()));
+                }
+            }
+        });
+
+        actor
+    }
+
+    /// Retire the actor of a stream with nothing running and nothing queued - the next command spawns a new one
+    async fn retire_if_idle(&self, id: &str, commands: &mut mpsc::UnboundedReceiver<StreamCommand>) -> bool {
+        // A leftover scheduler does not count - its stop is ignored once the failover is gone
+        let busy = self.processes.read().await.contains_key(id) || self.failovers.read().await.contains_key(id);
+        if busy {
+            return false;
+        }
+
+        // Commands are only sent under this lock, so none can slip in between the check and the removal
+        let mut actors = self.actors.lock().unwrap_or_else(PoisonError::into_inner);
+        if !commands.is_empty() {
+            return false;
+        }
+        actors.remove(id);
+        commands.close();
+        true
+    }
+
+    /// Run one command for a stream. Returns true once the stream has been deleted.
+    async fn handle_command(&self, id: &str, command: StreamCommand) -> bool {
+        match command {
+            StreamCommand::Start(reply) => {
+                let _ = reply.send(self.run_start(id, false).await);
+            }
+            StreamCommand::Stop(reply) => {
+                let _ = reply.send(self.run_stop(id).await);
+            }
+            StreamCommand::RestartFromBeginning(reply) => {
+                let _ = reply.send(self.run_restart_from_beginning(id).await);
+            }
+            StreamCommand::Restart(reply) => {
+                let _ = reply.send(self.run_restart(id).await);
+            }
+            StreamCommand::Update { patch, restart, reply } => {
+                let _ = reply.send(self.run_update(id, &patch, restart).await);
+            }
+            StreamCommand::Archive(reply) => {
+                let _ = reply.send(self.run_archive(id).await);
+            }
+            StreamCommand::Purge(reply) => {
+                let result = self.run_purge(id).await;
+                let deleted = result.is_ok();
+                let _ = reply.send(result);
+                return deleted;
+            }
+            StreamCommand::ProcessExited(report) => self.process_exited(report).await,
+            StreamCommand::ScheduledStop => self.scheduled_stop(id).await,
+        }
+        false
+    }
+
+    /// Route FFmpeg exits to their stream's actor the moment wait tasks report them
+    fn start_supervisor(&self, mut exits: mpsc::UnboundedReceiver<ExitReport>) {
+        let manager = self.clone();
+
+        tokio::spawn(async move {
+            while let Some(report) = exits.recv().await {
+                let id = report.stream_id.clone();
+                manager.notify(&id, StreamCommand::ProcessExited(report));
+            }
+        });
+    }
+
+    /// Act on an exit report if it is about the stream's current process -
+    /// stopped and replaced processes were already dealt with by whoever removed them
+    async fn process_exited(&self, report: ExitReport) {
+        let exited = {
+            let mut procs = self.processes.write().await;
+            match procs.get(&report.stream_id) {
+                Some(process) if process.id() == report.process_id => {
+                    procs.remove(&report.stream_id).map(|process| ExitedProcess {
+                        id: report.stream_id.clone(),
+                        exit: report.exit,
+                        elapsed: process.elapsed_seconds(),
+                        live: process.live_time(),
+                        plan: process.plan().for_restart(process.output_seconds()),
+                        position: process.media_position(),
+                        encoder_stats: process.encoder_stats(),
+                        log_tail: process.log_tail(),
+                    })
+                }
+                _ => None,
+            }
+        };
+
+        let Some(process) = exited else {
+            return;
+        };
+        // A process that outlived its stop has already failed the stream
+        if !self.failovers.read().await.contains_key(&process.id) {
+            tracing::info!("Process of stream {} exited after its stop failed", process.id);
+            return;
+        }
+        self.handle_exit(process).await;
+    }
+
+    /// Complete, restart or fail a stream whose FFmpeg process ended on its own
+    async fn handle_exit(&self, process: ExitedProcess) {
+        let ExitedProcess { id, exit, elapsed, live, plan, position, encoder_stats, log_tail } = process;
+
+        if exit == ProcessExit::EndOfInput {
+            // Streams that played their media to the end completed normally
+            tracing::info!("Stream {} reached end of input after {}s", id, elapsed);
+            self.failovers.write().await.remove(&id);
+
+            let end = SessionEnd::new(SessionEndReason::EndOfInput).with_output(encoder_stats, log_tail);
+            let completed = Transition::completed(Some(elapsed), end).live_for(Some(live.as_secs()));
+            if let Err(e) = self.transition(&id, completed).await {
+                tracing::error!("Error completing stream {}: {}", id, e);
+            }
+            return;
+        }
+
+        tracing::warn!("Stream {} died unexpectedly after {}s", id, elapsed);
+        let mut reason = exit_reason(exit);
+        self.events.error(&id, reason.localized());
+
+        // Treat the exit as a publish failure and retry or fail over
+        let action = {
+            let mut failovers = self.failovers.write().await;
+            match failovers.get_mut(&id) {
+                Some(failover) => {
+                    if elapsed >= ingest::STABLE_AFTER_SECS {
+                        failover.record_success();
+                    }
+                    failover.record_failure()
+                }
+                None => FailoverAction::Exhausted,
+            }
+        };
+
+        let failure_kind = if action != FailoverAction::Exhausted {
+            match self.respawn(&id, &plan, position, live).await {
+                Ok(()) => return,
+                Err(e) => {
+                    tracing::error!("Error restarting stream {}: {}", id, e);
+                    reason = Message::new("reason.restartFailed", &Params::new()).with("error", vec![e.to_message()]);
+                    FailureKind::RestartFailed
+                }
+            }
+        } else {
+            reason = Message::new("reason.endpointsExhausted", &Params::new()).with("reason", vec![reason]);
+            FailureKind::EndpointsExhausted
+        };
+        self.events.error(&id, reason.localized());
+
+        self.failovers.write().await.remove(&id);
+
+        // Mark as error with elapsed time
+        let end = SessionEnd::crash(failure_kind).with_output(encoder_stats, log_tail);
+        let failed = Transition::failed(reason, Some(elapsed), position, end).live_for(Some(live.as_secs()));
+        if let Err(e) = self.transition(&id, failed).await {
+            tracing::error!("Error failing stream {}: {}", id, e);
+        }
+    }
+
+    /// Move a stream to a new status, recording the transition atomically and announcing it
+    async fn transition(&self, id: &str, transition: Transition) -> Result<(), ManagerError> {
+        let to = transition.to.clone();
+        match self.db()?.transition_stream(id, &transition).await? {
+            TransitionOutcome::Applied { from } => {
+                tracing::info!("Stream {}: {} -> {}", id, from, to);
+                self.events.status(id, to);
+                Ok(())
+            }
+            TransitionOutcome::Rejected { from } => Err(ManagerError::InvalidTransition { from, to }),
+            TransitionOutcome::NotFound => Err(ManagerError::NotFound(id.to_string())),
+        }
+    }
+
+    /// Snapshot the database at startup and every `BACKUP_INTERVAL` after
+    fn start_backup_ticker(&self) {
+        let manager = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(backup::BACKUP_INTERVAL);
+            // After a sleep, one snapshot makes up for the ones missed
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            loop {
+                interval.tick().await;
+                if let Err(e) = manager.create_backup().await {
+                    tracing::error!("Scheduled database backup failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Purge expired archived streams at startup and every `ARCHIVE_SWEEP_INTERVAL` after
+    fn start_archive_sweeper(&self) {
+        let manager = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(ARCHIVE_SWEEP_INTERVAL);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
             loop {
-                tokio::time::sleep(std::time::Duration::from_secs(3)).await;
-                
-                let mut dead_streams: Vec<(String, u64)> = Vec::new();
-                
-                // Check for dead processes
+                interval.tick().await;
+                if let Err(e) = manager.purge_expired().await {
+                    tracing::error!("Archive sweep failed: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Publish stats for running streams and persist a heartbeat for each of them
+    fn start_stats_ticker(&self) {
+        let manager = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(STATS_INTERVAL);
+            let mut tick: u64 = 0;
+
+            loop {
+                interval.tick().await;
+                tick += 1;
+
+                let mut heartbeats: Vec<(String, Heartbeat)> = Vec::new();
                 {
-                    let mut procs = processes.write().await;
-                    let ids: Vec<String> = procs.keys().cloned().collect();
-                    
-                    for id in ids {
-                        if let Some(process) = procs.get_mut(&id) {
-                            if !process.is_running() {
-                                let elapsed = process.elapsed_seconds();
-                                dead_streams.push((id.clone(), elapsed));
-                                procs.remove(&id);
-                            }
-                        }
+                    let procs = manager.processes.read().await;
+                    let active = manager.failovers.read().await;
+                    for (id, process) in procs.iter() {
+                        let heartbeat = Heartbeat {
+                            elapsed_seconds: process.elapsed_seconds(),
+                            live_seconds: process.live_time().as_secs(),
+                            playback_position: process.media_position(),
+                        };
+                        manager.events.emit(StreamEvent::Stats {
+                            stream_id: id.clone(),
+                            elapsed_seconds: heartbeat.elapsed_seconds,
+                            live_seconds: heartbeat.live_seconds,
+                            playback_position: heartbeat.playback_position,
+                            active_endpoint: active.get(id).map(|f| f.active().to_string()),
+                        });
+                        heartbeats.push((id.clone(), heartbeat));
                     }
                 }
-                
-                // Update status for dead streams
-                if let Some(db) = &db {
-                    for (id, elapsed) in dead_streams {
-                        tracing::warn!("Stream {} died unexpectedly after {}s", id, elapsed);
-                        
-                        // Mark as error with elapsed time
-                        if let Err(e) = db.update_stream_status(&id, StreamStatus::Error).await {
-                            tracing::error!("Error updating stream status: {}", e);
-                        }
-                        if let Err(e) = db.update_stream_stopped_at(&id).await {
-                            tracing::error!("Error updating stopped_at: {}", e);
-                        }
-                        if let Err(e) = db.update_stream_last_elapsed(&id, elapsed).await {
-                            tracing::error!("Error updating last_elapsed: {}", e);
-                        }
+
+                // Persist run time and playback position so a crash of the app loses little
+                let Ok(db) = manager.db() else {
+                    continue;
+                };
+                if tick % HEARTBEAT_TICKS != 0 {
+                    continue;
+                }
+                for (id, heartbeat) in heartbeats {
+                    if let Err(e) = db.heartbeat(&id, &heartbeat).await {
+                        tracing::error!("Error recording heartbeat of stream {}: {}", id, e);
                     }
                 }
             }
         });
     }
 
-    pub async fn get_streams(&self) -> Result<Vec<Stream>, ManagerError> {
-        let mut streams = self.db()?.get_all_streams().await?;
+    /// Relaunch FFmpeg for a live stream on its currently active ingest endpoint,
+    /// picking the media up shortly before where the dead process left off
+    async fn respawn(
+        &self,
+        id: &str,
+        plan: &PlayoutPlan,
+        position: Option<f64>,
+        live_before: Duration,
+    ) -> Result<(), ManagerError> {
+        let db = self.db()?;
+        let stream = db.get_stream(id).await?
+            .ok_or_else(|| ManagerError::NotFound(id.to_string()))?;
+
+        let endpoint = self.failovers.read().await
+            .get(id)
+            .map(|f| f.active().to_string())
+            .ok_or_else(|| ManagerError::NotFound(id.to_string()))?;
+
+        let plan = match position {
+            Some(position) if stream.resume.enabled => {
+                plan.clone().resume_at(position - stream.resume.rewind_seconds as f64)
+            }
+            _ => plan.clone(),
+        };
+
+        tracing::info!("Restarting stream {} via {}", id, endpoint);
+
+        let mut process = FFmpegProcess::start(
+            &Self::get_ffmpeg_path(),
+            &plan,
+            &ingest::publish_url(&endpoint, &self.open_key(&stream.youtube_key).await?),
+            id,
+            &self.exits,
+        ).await.map_err(|e| ManagerError::FFmpeg(e.to_string()))?;
+        process.resume_live_time(live_before);
+
+        self.processes.write().await.insert(id.to_string(), process);
+
+        // The stream is back up either way - a lost count must not fail it
+        if let Err(e) = db.record_session_restart(id).await {
+            tracing::error!("Error recording restart of stream {}: {}", id, e);
+        }
+        Ok(())
+    }
+
+    pub async fn get_streams(&self, query: StreamQuery) -> Result<StreamPage, ManagerError> {
+        let (query, cursor) = query.normalize().map_err(ManagerError::Validation)?;
+        let sealed_search = match query.search_text() {
+            Some(search) => Some(self.vault().await?.seal(search)),
+            None => None,
+        };
+        let (mut streams, total) = self.db()?.query_streams(&query, sealed_search.as_deref(), cursor.as_ref()).await?;
         let processes = self.processes.read().await;
+        let failovers = self.failovers.read().await;
         
         // Update elapsed time for running streams or show last elapsed for stopped ones
         for stream in &mut streams {
             if let Some(process) = processes.get(&stream.id) {
                 // Running stream - show live elapsed time
                 stream.elapsed_seconds = Some(process.elapsed_seconds());
+                stream.live_seconds = Some(process.live_time().as_secs());
+                stream.active_endpoint = failovers.get(&stream.id).map(|f| f.active().to_string());
             } else if stream.last_elapsed_seconds.is_some() {
                 // Stopped stream with recorded elapsed - show it
                 stream.elapsed_seconds = stream.last_elapsed_seconds;
             }
         }
-        
-        Ok(streams)
+
+        // A full page may have more after it
+        let next_cursor = match (query.limit, streams.last()) {
+            (Some(limit), Some(last)) if streams.len() == limit as usize => {
+                Some(Cursor::after(last, query.sort, query.direction).encode())
+            }
+            _ => None,
+        };
+        Ok(StreamPage { streams, total, next_cursor })
     }
 
-    pub async fn add_stream(&mut self, input: StreamInput) -> Result<Stream, ManagerError> {
+    pub async fn add_stream(&self, input: StreamInput) -> Result<Stream, ManagerError> {
+        let mut stream = self.prepare_stream(&input).await?;
+
+        let vault = self.vault().await?;
+        let mut new_channel = None;
+        if input.channel_id.is_none() {
+            let channel_name = input.channel_name.as_deref().unwrap_or(&stream.name);
+            let (channel, is_new) = self.channel_for_key(&vault, &stream.youtube_key, channel_name).await?;
+            join_channel(&mut stream, &channel);
+            new_channel = is_new.then_some(channel);
+        }
+
         // Check for duplicate YouTube key on live streams
-        let existing_streams = self.db()?.get_all_streams().await?;
-        for existing in &existing_streams {
-            if existing.youtube_key == input.youtube_key && existing.status == StreamStatus::Live {
-                return Err(ManagerError::DuplicateKey(input.youtube_key.clone()));
-            }
+        self.check_key_free(None, &stream.channel_id).await?;
+
+        let db = self.db()?;
+        if let Some(channel) = &new_channel {
+            db.insert_channel(channel).await?;
+            tracing::info!("Created channel {} for stream {}", channel.id, stream.id);
         }
-        
-        let start_immediately = input.start_immediately;
-        
-        let stream = Stream {
-            id: Uuid::new_v4().to_string(),
-            name: input.name,
-            youtube_key: input.youtube_key,
-            video_path: input.video_path,
-            status: StreamStatus::Idle,
-            schedule: input.schedule,
-            started_at: None,
-            stopped_at: None,
-            created_at: input.created_at,
-            elapsed_seconds: None,
-            last_elapsed_seconds: None,
-        };
-        
-        self.db()?.insert_stream(&stream).await?;
-        
+        db.insert_stream(&stream).await?;
+        drop(vault);
+
         // Auto-start if requested
-        if start_immediately {
+        if input.start_immediately {
             let id = stream.id.clone();
             if let Err(e) = self.start_stream(&id).await {
                 tracing::error!("Failed to auto-start stream: {}", e);
@@ -177,87 +852,977 @@ impl StreamManager {
         Ok(updated)
     }
 
-    pub async fn start_stream(&mut self, id: &str) -> Result<(), ManagerError> {
+    /// The stream `input` describes, checked but not saved. A key it names is not sealed yet.
+    async fn prepare_stream(&self, input: &StreamInput) -> Result<Stream, ManagerError> {
+        let mut stream = new_stream(input);
+
+        if let Some(channel_id) = &input.channel_id {
+            join_channel(&mut stream, &self.find_channel(channel_id).await?);
+        }
+
+        let errors = validation::validate_stream(&stream, chrono::Utc::now());
+        if !errors.is_empty() {
+            return Err(ManagerError::Validation(errors));
+        }
+
+        // Catch trim points outside the media now rather than at go-live
+        Self::check_trims(&stream).await?;
+
+        Ok(stream)
+    }
+
+    /// Write every stream's settings to `path`, with the keys in the clear unless `redact_keys` is set.
+    /// A key that won't decrypt is left out and its stream named in the report, rather than failing the export.
+    pub async fn export_streams(&self, path: &Path, format: TransferFormat, redact_keys: bool) -> Result<ExportReport, ManagerError> {
+        let streams = self.db()?.get_all_streams().await?;
+
+        let mut records = Vec::with_capacity(streams.len());
+        let mut redacted = Vec::new();
+        for stream in streams.iter().rev().filter(|s| s.archived_at.is_none()) {
+            let key = if redact_keys {
+                None
+            } else {
+                match self.open_key(&stream.youtube_key).await {
+                    Ok(key) => Some(key),
+                    Err(e) => {
+                        tracing::warn!("Exporting stream {} without its key: {}", stream.id, e);
+                        redacted.push(stream.name.clone());
+                        None
+                    }
+                }
+            };
+            records.push(StreamRecord::new(stream, key));
+        }
+
+        tokio::fs::write(path, transfer::export(&records, format)).await?;
+        tracing::info!(
+            "Exported {} streams to {:?}, keys {}",
+            records.len(),
+            path,
+            if redact_keys { "redacted" } else { "included" }
+        );
+        Ok(ExportReport { exported: records.len(), redacted })
+    }
+
+    /// Add the streams in the file at `path`, row by row. A row that fails is reported and the rest go on.
+    /// Streams whose name is taken are skipped, overwritten or renamed as `options` say.
+    pub async fn import_streams(&self, path: &Path, format: TransferFormat, options: ImportOptions) -> Result<ImportReport, ManagerError> {
+        let text = tokio::fs::read_to_string(path).await?;
+        let records = transfer::parse(&text, format).map_err(ManagerError::InvalidImport)?;
+
+        let db = self.db()?;
+        let channels = db.get_channels().await?;
+        // Names taken so far, by stream - rows imported earlier in the file count too
+        let mut taken: HashMap<String, Option<String>> = db.get_all_streams().await?
+            .into_iter()
+            .filter(|s| s.archived_at.is_none())
+            .map(|s| (s.name, Some(s.id)))
+            .collect();
+
+        let mut rows = Vec::with_capacity(records.len());
+        for (index, record) in records.into_iter().enumerate() {
+            let record = match record {
+                Ok(record) => record,
+                Err(rejected) => {
+                    rows.push(ImportRow {
+                        row: index + 1,
+                        name: rejected.name,
+                        action: ImportAction::Create,
+                        stream_id: None,
+                        error: Some(ManagerError::Validation(rejected.errors)),
+                    });
+                    continue;
+                }
+            };
+
+            // Redacted rows publish to the channel they name
+            let channel_id = channels.iter().find(|c| c.name == record.channel.trim()).map(|c| c.id.clone());
+            let mut input = record.to_input(channel_id.clone());
+            let existing = taken.get(&input.name).cloned();
+            let action = match (&existing, options.on_conflict) {
+                (None, _) => ImportAction::Create,
+                (Some(_), ConflictStrategy::Skip) => ImportAction::Skip,
+                (Some(_), ConflictStrategy::Overwrite) => ImportAction::Overwrite,
+                (Some(_), ConflictStrategy::Rename) => {
+                    input.name = transfer::free_name(&input.name, &taken);
+                    ImportAction::Rename
+                }
+            };
+
+            let result = match (action, existing.flatten()) {
+                (ImportAction::Skip, _) => Ok(None),
+                _ if options.dry_run => self.prepare_stream(&input).await.map(|_| None),
+                (ImportAction::Overwrite, Some(id)) => {
+                    let mut patch = record.to_patch(channel_id);
+                    patch.name = Some(input.name.clone());
+                    self.update_stream(&id, patch, false).await.map(|s| Some(s.id))
+                }
+                _ => self.add_stream(input.clone()).await.map(|s| Some(s.id)),
+            };
+
+            if let Ok(stream_id) = &result {
+                taken.entry(input.name.clone()).or_insert_with(|| stream_id.clone());
+            }
+            rows.push(ImportRow {
+                row: index + 1,
+                name: input.name,
+                action,
+                stream_id: result.as_ref().ok().cloned().flatten(),
+                error: result.err(),
+            });
+        }
+
+        let report = ImportReport::new(options.dry_run, rows);
+        tracing::info!(
+            "Imported streams from {:?}{}: {} created, {} overwritten, {} renamed, {} skipped, {} failed",
+            path,
+            if options.dry_run { " (dry run)" } else { "" },
+            report.created,
+            report.overwritten,
+            report.renamed,
+            report.skipped,
+            report.failed
+        );
+        Ok(report)
+    }
+
+    /// Every go-live of a stream, newest first
+    pub async fn get_stream_sessions(&self, id: &str) -> Result<Vec<StreamSession>, ManagerError> {
+        Ok(self.db()?.get_stream_sessions(id).await?)
+    }
+
+    /// Live time, interruptions and uptime per stream or key over a date range
+    pub async fn get_uptime_report(&self, query: ReportQuery) -> Result<UptimeReport, ManagerError> {
+        let range = report::parse_range(&query).ok_or_else(|| ManagerError::InvalidRange {
+            from: query.from.clone(),
+            to: query.to.clone(),
+        })?;
+        let db = self.db()?;
+        let sessions = db.get_sessions_between(&range.0.to_rfc3339(), &range.1.to_rfc3339()).await?;
+        let streams = db.get_all_streams().await?;
+
+        Ok(report::build(&query, range, &sessions, &streams, chrono::Utc::now()))
+    }
+
+    pub async fn export_uptime_report(
+        &self,
+        query: ReportQuery,
+        format: ReportFormat,
+        path: &Path,
+    ) -> Result<(), ManagerError> {
+        let report = self.get_uptime_report(query).await?;
+        tokio::fs::write(path, report.export(format)).await?;
+        Ok(())
+    }
+
+    /// The full key of a stream, for the operator who asked to see it
+    pub async fn reveal_stream_key(&self, id: &str) -> Result<String, ManagerError> {
+        let stream = self.db()?.get_stream(id).await?
+            .ok_or_else(|| ManagerError::NotFound(id.to_string()))?;
+        let key = self.open_key(&stream.youtube_key).await?;
+        tracing::info!("Revealed the stream key of stream {}", id);
+        Ok(key)
+    }
+
+    /// Replace the master key, resealing every stored stream key with the new one
+    pub async fn rotate_master_key(&self) -> Result<(), ManagerError> {
+        let mut vault = self.vault.write().await;
+        let current = vault.as_ref().ok_or_else(|| {
+            ManagerError::Database(sqlx::Error::Configuration("Database not initialized".into()))
+        })?;
+
+        let mut rotated = current.rotate()?;
+        let resealed = match Self::reseal_keys(&self.db()?, &rotated).await {
+            Ok(resealed) => resealed,
+            Err(e) => {
+                // Keep the current master key, also for the next start
+                rotated.discard()?;
+                return Err(e);
+            }
+        };
+        rotated.commit()?;
+        *vault = Some(rotated);
+
+        tracing::info!("Rotated the master key and resealed {} channel keys", resealed);
+        Ok(())
+    }
+
+    /// Every channel with the streams that publish to it
+    pub async fn get_channels(&self) -> Result<Vec<Channel>, ManagerError> {
+        Ok(self.db()?.get_channels().await?)
+    }
+
+    /// Add a channel for a key no other channel holds
+    pub async fn add_channel(&self, input: ChannelInput) -> Result<Channel, ManagerError> {
+        let mut channel = Channel {
+            id: Uuid::new_v4().to_string(),
+            name: input.name.trim().to_string(),
+            destination: input.destination.map(|d| d.trim().to_string()).filter(|d| !d.is_empty()),
+            youtube_key: input.youtube_key.trim().to_string(),
+            masked_key: redact::mask_key(input.youtube_key.trim()),
+            notes: input.notes,
+            created_at: chrono::Utc::now().to_rfc3339(),
+            stream_ids: Vec::new(),
+        };
+
+        let mut errors = validation::validate_channel(&channel);
+        let vault = self.vault().await?;
+        channel.youtube_key = vault.seal(&channel.youtube_key);
+        errors.extend(self.check_key_unclaimed(&channel).await?);
+        if !errors.is_empty() {
+            return Err(ManagerError::Validation(errors));
+        }
+
+        self.db()?.insert_channel(&channel).await?;
+        drop(vault);
+        tracing::info!("Added channel {}", channel.id);
+        Ok(channel)
+    }
+
+    /// Change a channel. A new key or destination reaches live streams on the channel only
+    /// by relaunching them, which is refused unless `restart` is set.
+    pub async fn update_channel(&self, id: &str, patch: ChannelPatch, restart: bool) -> Result<Channel, ManagerError> {
+        let db = self.db()?;
+        let before = db.get_channel(id).await?
+            .ok_or_else(|| ManagerError::ChannelNotFound(id.to_string()))?;
+
+        let mut after = before.clone();
+        if let Some(name) = &patch.name {
+            after.name = name.trim().to_string();
+        }
+        if let Some(key) = &patch.youtube_key {
+            after.youtube_key = key.trim().to_string();
+            after.masked_key = redact::mask_key(&after.youtube_key);
+        }
+        if let Some(destination) = &patch.destination {
+            after.destination = Some(destination.trim().to_string()).filter(|d| !d.is_empty());
+        }
+        if let Some(notes) = &patch.notes {
+            after.notes = notes.clone();
+        }
+
+        let mut errors = validation::validate_channel(&after);
+        let vault = self.vault().await?;
+        if patch.youtube_key.is_some() {
+            after.youtube_key = vault.seal(&after.youtube_key);
+            errors.extend(self.check_key_unclaimed(&after).await?);
+        }
+        if !errors.is_empty() {
+            return Err(ManagerError::Validation(errors));
+        }
+
+        // Live streams were launched with the old key, or the old destination if they have no endpoints of their own
+        let mut fields = Vec::new();
+        if after.youtube_key != before.youtube_key {
+            fields.push("youtubeKey");
+        }
+        if after.destination != before.destination {
+            fields.push("destination");
+        }
+        let relaunch: Vec<String> = {
+            let processes = self.processes.read().await;
+            db.get_all_streams().await?
+                .into_iter()
+                .filter(|s| s.channel_id == id && processes.contains_key(&s.id))
+                .filter(|s| after.youtube_key != before.youtube_key || s.ingest_endpoints.is_empty())
+                .map(|s| s.id)
+                .collect()
+        };
+        if !relaunch.is_empty() && !fields.is_empty() && !restart {
+            return Err(ManagerError::RestartRequired(fields.join(", ")));
+        }
+
+        db.update_channel(&after).await?;
+        drop(vault);
+        tracing::info!("Updated channel {}", id);
+
+        if !fields.is_empty() {
+            for stream_id in relaunch {
+                tracing::info!("Restarting stream {} to apply its channel's {}", stream_id, fields.join(", "));
+                if let Err(e) = self.request(&stream_id, StreamCommand::Restart).await {
+                    tracing::error!("Error restarting stream {}: {}", stream_id, e);
+                }
+            }
+        }
+
+        db.get_channel(id).await?
+            .ok_or_else(|| ManagerError::ChannelNotFound(id.to_string()))
+    }
+
+    /// Delete a channel no stream publishes to any more
+    pub async fn delete_channel(&self, id: &str) -> Result<(), ManagerError> {
+        let db = self.db()?;
+        let channel = db.get_channel(id).await?
+            .ok_or_else(|| ManagerError::ChannelNotFound(id.to_string()))?;
+        if !channel.stream_ids.is_empty() {
+            return Err(ManagerError::ChannelInUse { name: channel.name, count: channel.stream_ids.len() });
+        }
+
+        db.delete_channel(id).await?;
+        tracing::info!("Deleted channel {}", id);
+        Ok(())
+    }
+
+    /// A channel picked for a stream, reported against the stream's `channelId` field if it is gone
+    async fn find_channel(&self, id: &str) -> Result<Channel, ManagerError> {
+        self.db()?.get_channel(id).await?.ok_or_else(|| {
+            ManagerError::Validation(vec![FieldError::new(
+                "channelId",
+                ErrorCode::UnknownChannel,
+                "field.channelNotFound",
+                i18n::params([("id", id.to_string())]),
+            )])
+        })
+    }
+
+    /// The channel holding a key typed into a stream, or a new one named after the stream, not yet
+    /// saved. Sealing is deterministic, so the sealed key finds the channel.
+    async fn channel_for_key(&self, vault: &Vault, key: &str, name: &str) -> Result<(Channel, bool), ManagerError> {
+        let sealed = vault.seal(key);
+        if let Some(channel) = self.db()?.get_channel_by_key(&sealed).await? {
+            return Ok((channel, false));
+        }
+
+        let channel = Channel {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            destination: None,
+            youtube_key: sealed,
+            masked_key: redact::mask_key(key),
+            notes: String::new(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            stream_ids: Vec::new(),
+        };
+        Ok((channel, true))
+    }
+
+    /// Refuse a key another channel already holds
+    async fn check_key_unclaimed(&self, channel: &Channel) -> Result<Vec<FieldError>, ManagerError> {
+        Ok(self.db()?.get_channel_by_key(&channel.youtube_key).await?
+            .filter(|other| other.id != channel.id)
+            .map(|other| FieldError::new(
+                "youtubeKey",
+                ErrorCode::InUse,
+                "field.keyInUse",
+                i18n::params([("channel", other.name)]),
+            ))
+            .into_iter()
+            .collect())
+    }
+
+    /// A new stream with the settings of stream `id` and the fields in `overrides`.
+    /// Without a new name it is called `Name (2)`, `Name (3)`, ...
+    pub async fn clone_stream(&self, id: &str, overrides: StreamPatch) -> Result<Stream, ManagerError> {
+        let db = self.db()?;
+        let source = db.get_stream(id).await?
+            .ok_or_else(|| ManagerError::NotFound(id.to_string()))?;
+        let taken: HashMap<String, ()> = db.get_all_streams().await?
+            .into_iter()
+            .filter(|s| s.archived_at.is_none())
+            .map(|s| (s.name, ()))
+            .collect();
+
+        let input = template::clone_input(&source, transfer::free_name(&source.name, &taken), &overrides);
+        let stream = self.add_stream(input).await?;
+        tracing::info!("Cloned stream {} as {}", id, stream.id);
+        Ok(stream)
+    }
+
+    pub async fn get_templates(&self) -> Result<Vec<StreamTemplate>, ManagerError> {
+        Ok(self.db()?.get_templates().await?)
+    }
+
+    pub async fn add_template(&self, input: TemplateInput) -> Result<StreamTemplate, ManagerError> {
+        let template = StreamTemplate::new(input);
+        self.check_template(&template).await?;
+
+        let db = self.db()?;
+        db.insert_template(&template).await?;
+        tracing::info!("Added template {}", template.id);
+        db.get_template(&template.id).await?
+            .ok_or_else(|| ManagerError::TemplateNotFound(template.id.clone()))
+    }
+
+    /// Replace every setting of a template. Streams already made from it keep theirs.
+    pub async fn update_template(&self, id: &str, input: TemplateInput) -> Result<StreamTemplate, ManagerError> {
+        let db = self.db()?;
+        let existing = db.get_template(id).await?
+            .ok_or_else(|| ManagerError::TemplateNotFound(id.to_string()))?;
+        let template = StreamTemplate { id: existing.id, created_at: existing.created_at, ..StreamTemplate::new(input) };
+        self.check_template(&template).await?;
+
+        db.update_template(&template).await?;
+        db.get_template(id).await?
+            .ok_or_else(|| ManagerError::TemplateNotFound(id.to_string()))
+    }
+
+    pub async fn delete_template(&self, id: &str) -> Result<(), ManagerError> {
+        if !self.db()?.delete_template(id).await? {
+            return Err(ManagerError::TemplateNotFound(id.to_string()));
+        }
+        tracing::info!("Deleted template {}", id);
+        Ok(())
+    }
+
+    /// Add a stream from a template. `overrides` names the stream and fills in whatever the template leaves out.
+    pub async fn create_from_template(
+        &self,
+        template_id: &str,
+        overrides: StreamPatch,
+        start_immediately: bool,
+    ) -> Result<Stream, ManagerError> {
+        let template = self.db()?.get_template(template_id).await?
+            .ok_or_else(|| ManagerError::TemplateNotFound(template_id.to_string()))?;
+
+        let input = StreamInput { start_immediately, ..template.to_input(&overrides) };
+        self.add_stream(input).await
+    }
+
+    /// Check a template like the stream it describes, but without the fields each stream fills in
+    async fn check_template(&self, template: &StreamTemplate) -> Result<(), ManagerError> {
+        let mut draft = new_stream(&template.to_input(&StreamPatch::default()));
+        draft.name = template.name.clone();
+        if let Some(channel_id) = &template.channel_id {
+            join_channel(&mut draft, &self.find_channel(channel_id).await?);
+        }
+
+        let errors = validation::validate_stream(&draft, chrono::Utc::now());
+        let errors = template::template_errors(&template.settings, errors);
+        if !errors.is_empty() {
+            return Err(ManagerError::Validation(errors));
+        }
+        // Trim points can only be checked against a file
+        if template.settings.video_path.is_some() {
+            Self::check_trims(&draft).await?;
+        }
+        Ok(())
+    }
+
+    /// Every tag on the streams in use, with how many of them carry it
+    pub async fn get_tags(&self) -> Result<Vec<Tag>, ManagerError> {
+        Ok(self.db()?.get_tags().await?)
+    }
+
+    /// Add a tag to streams, returning how many didn't have it. Unknown ids are ignored.
+    pub async fn tag_streams(&self, ids: &[String], tag: &str) -> Result<u64, ManagerError> {
+        let errors = validation::validate_tag(tag);
+        if !errors.is_empty() {
+            return Err(ManagerError::Validation(errors));
+        }
+        Ok(self.db()?.add_tag(ids, tag.trim()).await?)
+    }
+
+    /// Take a tag off streams, returning how many had it
+    pub async fn untag_streams(&self, ids: &[String], tag: &str) -> Result<u64, ManagerError> {
+        Ok(self.db()?.remove_tag(ids, tag.trim()).await?)
+    }
+
+    /// Start every stream with the tag that isn't live. Each start is checked like
+    /// `start_stream`, so streams sharing a channel don't all go live.
+    pub async fn bulk_start(&self, tag: &str) -> Result<BulkReport, ManagerError> {
+        self.run_bulk(tag, |stream| stream.status != StreamStatus::Live, |manager, id| async move {
+            manager.start_stream(&id).await
+        }).await
+    }
+
+    /// Stop every live stream with the tag
+    pub async fn bulk_stop(&self, tag: &str) -> Result<BulkReport, ManagerError> {
+        self.run_bulk(tag, |stream| stream.status == StreamStatus::Live, |manager, id| async move {
+            manager.stop_stream(&id).await
+        }).await
+    }
+
+    /// Give every stream with the tag the same schedule, restarting live streams that need it only if `restart` allows
+    pub async fn bulk_reschedule(&self, tag: &str, schedule: ScheduleConfig, restart: bool) -> Result<BulkReport, ManagerError> {
+        self.run_bulk(tag, |_| true, move |manager, id| {
+            let patch = StreamPatch { schedule: Some(schedule.clone()), ..Default::default() };
+            async move { manager.update_stream(&id, patch, restart).await.map(|_| ()) }
+        }).await
+    }
+
+    /// Archive every stream with the tag, stopping live ones first
+    pub async fn bulk_delete(&self, tag: &str) -> Result<BulkReport, ManagerError> {
+        self.run_bulk(tag, |_| true, |manager, id| async move {
+            manager.delete_stream(&id).await
+        }).await
+    }
+
+    /// Run `operation` on the streams with the tag that `applies` to, several at once, through each stream's actor
+    async fn run_bulk<F, Fut>(
+        &self,
+        tag: &str,
+        applies: impl Fn(&Stream) -> bool,
+        operation: F,
+    ) -> Result<BulkReport, ManagerError>
+    where
+        F: Fn(StreamManager, String) -> Fut,
+        Fut: std::future::Future<Output = Result<(), ManagerError>> + Send + 'static,
+    {
+        let errors = validation::validate_tag(tag);
+        if !errors.is_empty() {
+            return Err(ManagerError::Validation(errors));
+        }
+        let query = StreamQuery {
+            tags: vec![tag.trim().to_string()],
+            sort: StreamSort::Name,
+            direction: SortDirection::Asc,
+            ..Default::default()
+        };
+        let (streams, _) = self.db()?.query_streams(&query, None, None).await?;
+
+        let permits = Arc::new(tokio::sync::Semaphore::new(BULK_PARALLELISM));
+        let tasks: Vec<_> = streams.iter().map(|stream| {
+            if !applies(stream) {
+                return None;
+            }
+            let permits = permits.clone();
+            let work = operation(self.clone(), stream.id.clone());
+            Some(tokio::spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                work.await
+            }))
+        }).collect();
+
+        let mut results = Vec::with_capacity(streams.len());
+        for (stream, task) in streams.into_iter().zip(tasks) {
+            let result = match task {
+                Some(task) => Some(task.await.unwrap_or_else(|_| Err(ManagerError::WorkerStopped(stream.id.clone())))),
+                None => None,
+            };
+            results.push(BulkResult::new(stream.id, stream.name, result));
+        }
+        let report = BulkReport::new(tag.trim().to_string(), results);
+        tracing::info!(
+            "Bulk command on tag {}: {} done, {} skipped, {} failed",
+            report.tag, report.done, report.skipped, report.failed
+        );
+        Ok(report)
+    }
+
+    /// Snapshot the database now, dropping the oldest snapshots beyond `BACKUPS_KEPT`
+    pub async fn create_backup(&self) -> Result<BackupInfo, ManagerError> {
+        let db = self.db()?;
+        let info = backup::snapshot(&db).await?;
+        let pruned = backup::prune(db.path(), backup::BACKUPS_KEPT)?;
+        tracing::info!("Backed up the database to {}, {} old backups pruned", info.file_name, pruned);
+        Ok(info)
+    }
+
+    /// Snapshots of the database, newest first
+    pub async fn list_backups(&self) -> Result<Vec<BackupInfo>, ManagerError> {
+        Ok(backup::list(self.db()?.path())?)
+    }
+
+    /// Replace the database with one of its snapshots. Live streams are stopped first, and the
+    /// database as it was is snapshotted too, so the restore can itself be undone. A snapshot
+    /// whose keys the vault cannot open is refused before anything changes.
+    pub async fn restore_backup(&self, file_name: &str) -> Result<(), ManagerError> {
+        let db = self.db()?;
+        let path = db.path().to_path_buf();
+        let source = backup::find(&path, file_name)?;
+        let version = backup::verify(&source).await?;
+        if version > migrations::latest_version() {
+            return Err(ManagerError::SchemaTooNew { found: version, supported: migrations::latest_version() });
+        }
+
+        // Through their actors, so each session is closed as a manual stop. Before the start
+        // claims are taken, as a stop can restart the stream while its command drains.
+        let live: Vec<String> = self.processes.read().await.keys().cloned().collect();
+        let mut not_stopped = Vec::new();
+        for id in &live {
+            if let Err(e) = self.stop_stream(id).await {
+                tracing::error!("Stopping stream {} before restoring backup {} failed: {}", id, file_name, e);
+                not_stopped.push(id.clone());
+            }
+        }
+        if !not_stopped.is_empty() {
+            return Err(ManagerError::StreamsNotStopped(not_stopped));
+        }
+
+        // No stream goes live until the restored database is in place. One that started since
+        // the stops above, or is starting now, makes the restore wait for a quiet moment.
+        let claims = self.starting.lock().await;
+        if let Some(id) = claims.keys().next() {
+            return Err(ManagerError::AlreadyRunning(id.clone()));
+        }
+        if let Some(id) = self.processes.read().await.keys().next() {
+            return Err(ManagerError::AlreadyRunning(id.clone()));
+        }
+
+        // No key is sealed and written while the file is swapped
+        let vault = self.vault.write().await;
+        let vault = vault.as_ref().ok_or_else(|| {
+            ManagerError::Database(sqlx::Error::Configuration("Database not initialized".into()))
+        })?;
+
+        // Migrate and reseal a copy first, so a snapshot that won't open leaves the database as it is
+        let staged = backup::stage(&source, &path)?;
+        match Self::open_database(&staged, vault).await {
+            Ok(prepared) => prepared.close().await,
+            Err(e) => {
+                std::fs::remove_file(&staged).ok();
+                return Err(e);
+            }
+        }
+
+        let undo = backup::snapshot(&db).await?;
+        tracing::info!("Restoring database backup {}, the current database saved as {}", file_name, undo.file_name);
+
+        // Other commands find no database until the restored one is open
+        self.db.write().unwrap_or_else(PoisonError::into_inner).take();
+        db.close().await;
+
+        let reopened = match backup::swap_in(&staged, &path) {
+            Ok(()) => Database::new(&path).await.map_err(ManagerError::from),
+            Err(e) => Err(e.into()),
+        };
+        let restored = match reopened {
+            Ok(restored) => restored,
+            Err(e) => {
+                tracing::error!("Restoring database backup {} failed, putting the database back: {}", file_name, e);
+                let db = Self::reattach_database(&path, &undo.file_name, vault).await?;
+                *self.db.write().unwrap_or_else(PoisonError::into_inner) = Some(db);
+                return Err(e);
+            }
+        };
+        *self.db.write().unwrap_or_else(PoisonError::into_inner) = Some(restored);
+        drop(claims);
+
+        tracing::info!("Restored database backup {} after stopping {} live streams", file_name, live.len());
+        Ok(())
+    }
+
+    /// Put a database back after a restore failed with the file closed: the snapshot taken
+    /// before it if that can be swapped in, or else whatever file is at the path
+    async fn reattach_database(path: &Path, undo: &str, vault: &Vault) -> Result<Database, ManagerError> {
+        let rolled_back = async {
+            let staged = backup::stage(&backup::find(path, undo)?, path)?;
+            backup::swap_in(&staged, path)?;
+            Self::open_database(path, vault).await
+        }
+        .await;
+        match rolled_back {
+            Ok(db) => Ok(db),
+            Err(e) => {
+                tracing::error!("Putting back the database saved as {} failed, reopening {}: {}", undo, path.display(), e);
+                Database::new(path).await.map_err(|e| ManagerError::DatabaseLost(e.to_string()))
+            }
+        }
+    }
+
+    pub fn get_locale(&self) -> Locale {
+        i18n::locale()
+    }
+
+    /// Switch the language of errors, failure reasons and reports, remembered across restarts
+    pub async fn set_locale(&self, locale: Locale) -> Result<(), ManagerError> {
+        self.db()?.set_setting(LOCALE_SETTING, locale.as_str()).await?;
+        i18n::set_locale(locale);
+        Ok(())
+    }
+
+    pub async fn start_stream(&self, id: &str) -> Result<(), ManagerError> {
+        self.request(id, StreamCommand::Start).await
+    }
+
+    pub async fn stop_stream(&self, id: &str) -> Result<(), ManagerError> {
+        self.request(id, StreamCommand::Stop).await
+    }
+
+    /// Start a stream from the top of its media, discarding any saved position
+    pub async fn restart_from_beginning(&self, id: &str) -> Result<(), ManagerError> {
+        self.request(id, StreamCommand::RestartFromBeginning).await
+    }
+
+    /// Move a stream to the archive, stopping it first if it is live. It keeps its settings and history until purged.
+    pub async fn delete_stream(&self, id: &str) -> Result<(), ManagerError> {
+        self.request(id, StreamCommand::Archive).await
+    }
+
+    /// Bring an archived stream back to the list
+    pub async fn restore_stream(&self, id: &str) -> Result<Stream, ManagerError> {
+        let db = self.db()?;
+        let stream = db.get_stream(id).await?
+            .ok_or_else(|| ManagerError::NotFound(id.to_string()))?;
+        if !db.unarchive_stream(id).await? {
+            return Err(ManagerError::NotArchived(id.to_string()));
+        }
+        tracing::info!("Restored stream {} from the archive", id);
+        Ok(Stream { archived_at: None, ..stream })
+    }
+
+    /// Erase an archived stream and its settings for good. Its sessions stay in the reports.
+    pub async fn purge_stream(&self, id: &str) -> Result<(), ManagerError> {
+        self.request(id, StreamCommand::Purge).await
+    }
+
+    /// Erase every archived stream, returning how many were purged
+    pub async fn empty_archive(&self) -> Result<usize, ManagerError> {
+        let ids = self.db()?.get_archived_ids(None).await?;
+        Ok(self.purge_all(&ids).await)
+    }
+
+    /// Days archived streams are kept before they are purged, None to keep them until purged by hand
+    pub async fn get_archive_retention(&self) -> Result<Option<u32>, ManagerError> {
+        let value = self.db()?.get_setting(ARCHIVE_RETENTION_SETTING).await?;
+        Ok(match value.as_deref() {
+            None => Some(DEFAULT_ARCHIVE_RETENTION_DAYS),
+            Some("never") => None,
+            Some(days) => days.parse().ok().or(Some(DEFAULT_ARCHIVE_RETENTION_DAYS)),
+        })
+    }
+
+    /// Keep archived streams `days` days, at most ten years, or until purged by hand with None.
+    /// Streams already past the new retention are purged straight away.
+    pub async fn set_archive_retention(&self, days: Option<u32>) -> Result<(), ManagerError> {
+        if days == Some(0) {
+            return Err(ManagerError::Validation(vec![FieldError::new(
+                "days",
+                ErrorCode::OutOfRange,
+                "field.retentionZero",
+                Params::new(),
+            )]));
+        }
+        if days.is_some_and(|days| days > MAX_ARCHIVE_RETENTION_DAYS) {
+            return Err(ManagerError::Validation(vec![FieldError::new(
+                "days",
+                ErrorCode::OutOfRange,
+                "field.retentionTooLong",
+                i18n::params([("max", MAX_ARCHIVE_RETENTION_DAYS.to_string())]),
+            )]));
+        }
+        let value = days.map_or_else(|| "never".to_string(), |d| d.to_string());
+        self.db()?.set_setting(ARCHIVE_RETENTION_SETTING, &value).await?;
+        // A shorter retention takes effect now rather than at the next sweep
+        self.purge_expired().await
+    }
+
+    /// Purge streams archived for longer than the retention period
+    async fn purge_expired(&self) -> Result<(), ManagerError> {
+        let Some(days) = self.get_archive_retention().await? else {
+            return Ok(());
+        };
+        // A retention reaching back before the calendar begins has nothing expired yet
+        let Some(cutoff) = chrono::Utc::now().checked_sub_signed(chrono::Duration::days(days as i64)) else {
+            return Ok(());
+        };
+        let ids = self.db()?.get_archived_ids(Some(&cutoff.to_rfc3339())).await?;
+        if !ids.is_empty() {
+            let purged = self.purge_all(&ids).await;
+            tracing::info!("Purged {} streams archived more than {} days ago", purged, days);
+        }
+        Ok(())
+    }
+
+    async fn purge_all(&self, ids: &[String]) -> usize {
+        let mut purged = 0;
+        for id in ids {
+            match self.purge_stream(id).await {
+                Ok(()) => purged += 1,
+                Err(e) => tracing::error!("Error purging stream {}: {}", id, e),
+            }
+        }
+        purged
+    }
+
+    /// Change a stream's settings. Settings a live stream was launched with are refused
+    /// unless `restart` is set, in which case the stream restarts with them.
+    pub async fn update_stream(&self, id: &str, patch: StreamPatch, restart: bool) -> Result<Stream, ManagerError> {
+        let patch = Box::new(patch);
+        self.request(id, |reply| StreamCommand::Update { patch, restart, reply }).await?;
+
+        self.db()?.get_stream(id).await?
+            .ok_or_else(|| ManagerError::NotFound(id.to_string()))
+    }
+
+    /// Refuse a channel another live stream is publishing to. A channel is one key.
+    async fn check_key_free(&self, id: Option<&str>, channel_id: &str) -> Result<(), ManagerError> {
+        let existing_streams = self.db()?.get_all_streams().await?;
+        for existing in &existing_streams {
+            if Some(existing.id.as_str()) != id && existing.channel_id == channel_id && existing.status == StreamStatus::Live {
+                return Err(ManagerError::DuplicateKey(existing.masked_key.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Probe every trimmed file and check its trim points against the media's length
+    async fn check_trims(stream: &Stream) -> Result<(), ManagerError> {
+        let ffprobe_path = Self::get_ffprobe_path();
+        let trimmed_files = [
+            ("trim", Some(stream.video_path.as_str()), &stream.trim),
+            ("clips.introTrim", stream.clips.intro_path.as_deref(), &stream.clips.intro_trim),
+            ("clips.outroTrim", stream.clips.outro_path.as_deref(), &stream.clips.outro_trim),
+        ];
+
+        let mut errors = Vec::new();
+        for (field, path, trim) in trimmed_files {
+            let Some(path) = path.filter(|_| !trim.is_empty()) else {
+                continue;
+            };
+            match probe::duration_seconds(&ffprobe_path, path).await {
+                Ok(duration) => {
+                    if let Err(e) = trim.validate(duration) {
+                        errors.push(FieldError::trim(field, &e));
+                    }
+                }
+                Err(e) => errors.push(FieldError::new(
+                    field,
+                    ErrorCode::UnreadableMedia,
+                    "field.unreadableMedia",
+                    i18n::params([("path", path.to_string()), ("error", e.to_string())]),
+                )),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ManagerError::Validation(errors))
+        }
+    }
+
+    async fn run_start(&self, id: &str, from_beginning: bool) -> Result<(), ManagerError> {
         let stream = self.db()?.get_stream(id).await?
             .ok_or_else(|| ManagerError::NotFound(id.to_string()))?;
 
+        if stream.archived_at.is_some() {
+            return Err(ManagerError::Archived(id.to_string()));
+        }
         if stream.status == StreamStatus::Live {
             return Err(ManagerError::AlreadyRunning(id.to_string()));
         }
+        if !stream.status.can_transition_to(&StreamStatus::Live) {
+            return Err(ManagerError::InvalidTransition { from: stream.status, to: StreamStatus::Live });
+        }
+
+        // Check for other live or starting streams on the channel, then claim it
+        {
+            let all_streams = self.db()?.get_all_streams().await?;
+            let mut starting = self.starting.lock().await;
+            let processes = self.processes.read().await;
+            let live_elsewhere = all_streams.iter().any(|other| {
+                other.id != id
+                    && other.channel_id == stream.channel_id
+                    && processes.contains_key(&other.id)
+            });
+            let starting_elsewhere = starting.iter()
+                .any(|(other, channel)| other != id && *channel == stream.channel_id);
+            if live_elsewhere || starting_elsewhere {
+                return Err(ManagerError::DuplicateKey(stream.masked_key.clone()));
+            }
+            starting.insert(id.to_string(), stream.channel_id.clone());
+        }
+
+        // The claim hands over to the processes map once launch has registered the process
+        let result = self.launch(&stream, from_beginning).await;
+        self.starting.lock().await.remove(id);
+        result
+    }
+
+    async fn launch(&self, stream: &Stream, from_beginning: bool) -> Result<(), ManagerError> {
+        let id = stream.id.as_str();
+
+        // Streams without endpoints of their own publish to their channel's destination
+        let mut endpoints = stream.ingest_endpoints.clone();
+        if endpoints.is_empty() {
+            let channel = self.db()?.get_channel(&stream.channel_id).await?;
+            endpoints.extend(channel.and_then(|c| c.destination));
+        }
+
+        // Pick the first ingest endpoint that accepts connections
+        let mut failover = IngestFailover::new(endpoints);
+        let Some(endpoint) = failover.select_reachable().await.map(str::to_string) else {
+            tracing::error!("No ingest endpoint of stream {} accepts connections: {}", id, failover.endpoints().join(", "));
+            return Err(ManagerError::NoIngestEndpoint);
+        };
 
-        // Check for duplicate YouTube key on other live streams
-        {
-            let processes = self.processes.read().await;
-            let all_streams = self.db()?.get_all_streams().await?;
-            for other in &all_streams {
-                if other.id != id 
-                    && other.youtube_key == stream.youtube_key 
-                    && processes.contains_key(&other.id) 
-                {
-                    return Err(ManagerError::DuplicateKey(stream.youtube_key.clone()));
-                }
+        // Pick up where a crashed or interrupted run left off, unless asked to start over
+        let resume_from = match (stream.status == StreamStatus::Error, stream.playback_position) {
+            (true, Some(position)) if stream.resume.enabled && !from_beginning => {
+                Some(position - stream.resume.rewind_seconds as f64)
             }
-        }
+            _ => None,
+        };
+        // Starting over forgets the saved position as the stream goes live
+        let playback_position = from_beginning.then_some(None);
+
+        // Resolve the deadline up front so intro, outro and fades line up with process start
+        let stop_after = Self::stop_after(&stream.schedule);
+        let plan = Self::build_plan(stream, stop_after, resume_from).await?;
 
         // Get FFmpeg path
         let ffmpeg_path = Self::get_ffmpeg_path();
         
         // Start FFmpeg process
+        let launched_at = Instant::now();
         let process = FFmpegProcess::start(
             &ffmpeg_path,
-            &stream.video_path,
-            &stream.youtube_key,
+            &plan,
+            &ingest::publish_url(&endpoint, &self.open_key(&stream.youtube_key).await?),
+            id,
+            &self.exits,
         ).await.map_err(|e| ManagerError::FFmpeg(e.to_string()))?;
 
-        // Store process temporarily
-        let process_id = id.to_string();
-        {
-            let mut processes = self.processes.write().await;
-            processes.insert(process_id.clone(), process);
+        // Wait a moment and verify FFmpeg is still running. A report for an exit after
+        // this check queues behind us on the actor and finds the registered process.
+        let exit = process.exited_within(STARTUP_CHECK).await;
+
+        if exit == Some(ProcessExit::EndOfInput) {
+            // Media shorter than the startup check - it went live and already played through
+            self.transition(id, Transition { playback_position, ..Transition::live() }).await?;
+            let end = session_end(SessionEndReason::EndOfInput, Some(&process));
+            let completed = Transition::completed(Some(process.elapsed_seconds()), end)
+                .live_for(Some(process.live_time().as_secs()));
+            return self.transition(id, completed).await;
         }
 
-        // Wait a moment and verify FFmpeg is still running
-        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-        
-        // Check if process is still alive
-        let is_running = {
-            let mut processes = self.processes.write().await;
-            if let Some(proc) = processes.get_mut(&process_id) {
-                proc.is_running()
-            } else {
-                false
+        if exit.is_some() {
+            // Process died - report error
+            let error = ManagerError::ExitedImmediately;
+            self.events.error(id, error.localized());
+            // Never went live, so there is no session to close. A failed stream stays failed.
+            if stream.status != StreamStatus::Error {
+                let end = SessionEnd::new(SessionEndReason::Crash);
+                self.transition(id, Transition::failed(error.to_message(), None, None, end)).await?;
             }
-        };
+            return Err(error);
+        }
 
-        if !is_running {
-            // Process died - remove it and report error
-            {
-                let mut processes = self.processes.write().await;
-                processes.remove(&process_id);
+        // Process is running - update stream status to Live
+        let remaining = stop_after.map(|d| d.saturating_sub(launched_at.elapsed()));
+        let live = Transition {
+            scheduled_seconds: remaining.map(|d| d.as_secs()),
+            playback_position,
+            ..Transition::live()
+        };
+        let mut process = process;
+        if let Err(e) = self.transition(id, live).await {
+            if let Err(stop_error) = process.stop().await {
+                tracing::error!("Error stopping process: {}", stop_error);
             }
-            self.db()?.update_stream_status(id, StreamStatus::Error).await?;
-            return Err(ManagerError::FFmpeg("FFmpeg process exited immediately - check video file or stream key".into()));
+            return Err(e);
         }
 
-        // Process is running - update stream status to Live
-        self.db()?.update_stream_status(id, StreamStatus::Live).await?;
-        self.db()?.update_stream_started_at(id).await?;
+        // Hand the process over to exit supervision and failover handling
+        self.processes.write().await.insert(id.to_string(), process);
+        self.failovers.write().await.insert(id.to_string(), failover);
 
         // Setup scheduler if needed
-        self.setup_scheduler(id, &stream).await?;
+        self.setup_scheduler(id, remaining).await;
 
         Ok(())
     }
 
-    pub async fn stop_stream(&mut self, id: &str) -> Result<(), ManagerError> {
-        self.stop_stream_with_status(id, StreamStatus::Completed).await
-    }
+    async fn run_stop(&self, id: &str) -> Result<(), ManagerError> {
+        // Update status to stopping - refused unless the stream is live
+        self.transition(id, Transition::stopping()).await?;
 
-    async fn stop_stream_with_status(&mut self, id: &str, final_status: StreamStatus) -> Result<(), ManagerError> {
         // Get elapsed before stopping
-        let elapsed = {
+        let (elapsed, live) = {
             let processes = self.processes.read().await;
-            processes.get(id).map(|p| p.elapsed_seconds())
+            let process = processes.get(id);
+            (process.map(|p| p.elapsed_seconds()), process.map(|p| p.live_time().as_secs()))
         };
 
         // Cancel scheduler first
@@ -268,128 +1833,372 @@ impl StreamManager {
             }
         }
 
-        // Update status to stopping
-        self.db()?.update_stream_status(id, StreamStatus::Stopping).await?;
+        self.failovers.write().await.remove(id);
 
-        // Stop FFmpeg process
-        {
-            let mut processes = self.processes.write().await;
-            if let Some(mut process) = processes.remove(id) {
-                process.stop().await.map_err(|e| ManagerError::FFmpeg(e.to_string()))?;
+        let process = self.stop_process(id, elapsed, live).await?;
+
+        // A deliberate stop ends the run - the next start begins at the top
+        let end = session_end(SessionEndReason::Manual, process.as_ref());
+        let completed = Transition::completed(elapsed, end).live_for(live);
+        self.transition(id, completed).await
+    }
+
+    /// Stop the stream's FFmpeg process. One that won't stop fails the stream rather than leave it
+    /// stopping, and stays tracked so that its exit is still seen.
+    async fn stop_process(&self, id: &str, elapsed: Option<u64>, live: Option<u64>) -> Result<Option<FFmpegProcess>, ManagerError> {
+        let Some(mut process) = self.processes.write().await.remove(id) else {
+            return Ok(None);
+        };
+        if let Err(e) = process.stop().await {
+            let error = ManagerError::FFmpeg(e.to_string());
+            let reason = Message::new("reason.stopFailed", &Params::new()).with("error", vec![error.to_message()]);
+            self.events.error(id, reason.localized());
+            let end = SessionEnd::crash(FailureKind::StopFailed)
+                .with_output(process.encoder_stats(), process.log_tail());
+            let failed = Transition::failed(reason, elapsed, process.media_position(), end).live_for(live);
+            if let Err(e) = self.transition(id, failed).await {
+                tracing::error!("Error failing stream {}: {}", id, e);
             }
+            // Its exit report queues behind this command, so it finds the process back in place
+            self.processes.write().await.insert(id.to_string(), process);
+            return Err(error);
         }
+        Ok(Some(process))
+    }
 
-        // Update stream status and store elapsed
-        self.db()?.update_stream_status(id, final_status).await?;
-        self.db()?.update_stream_stopped_at(id).await?;
-        
-        if let Some(secs) = elapsed {
-            self.db()?.update_stream_last_elapsed(id, secs).await?;
+    async fn run_restart_from_beginning(&self, id: &str) -> Result<(), ManagerError> {
+        let stream = self.db()?.get_stream(id).await?
+            .ok_or_else(|| ManagerError::NotFound(id.to_string()))?;
+
+        if stream.status == StreamStatus::Live {
+            self.run_stop(id).await?;
+        }
+
+        self.run_start(id, true).await
+    }
+
+    async fn run_restart(&self, id: &str) -> Result<(), ManagerError> {
+        if !self.processes.read().await.contains_key(id) {
+            return Ok(());
+        }
+        self.run_stop(id).await?;
+        self.run_start(id, false).await
+    }
+
+    async fn run_update(&self, id: &str, patch: &StreamPatch, restart: bool) -> Result<(), ManagerError> {
+        let before = self.db()?.get_stream(id).await?
+            .ok_or_else(|| ManagerError::NotFound(id.to_string()))?;
+        let mut after = patch.apply(&before);
+        if let Some(channel_id) = &patch.channel_id {
+            join_channel(&mut after, &self.find_channel(channel_id).await?);
+        }
+
+        let schedule_changed = edit::schedule_changed(&before, &after);
+        let mut errors = validation::validate_settings(&after);
+        if schedule_changed {
+            errors.extend(validation::validate_schedule(&after.schedule, chrono::Utc::now()));
+        }
+        if !errors.is_empty() {
+            return Err(ManagerError::Validation(errors));
+        }
+
+        if after.video_path != before.video_path || after.trim != before.trim || after.clips != before.clips {
+            Self::check_trims(&after).await?;
+        }
+
+        // A typed key moves the stream to the key's channel, unless a channel was picked too
+        let vault = self.vault().await?;
+        let mut new_channel = None;
+        if patch.youtube_key.is_some() && patch.channel_id.is_none() {
+            let (channel, is_new) = self.channel_for_key(&vault, &after.youtube_key, &after.name).await?;
+            join_channel(&mut after, &channel);
+            new_channel = is_new.then_some(channel);
+        }
+        if after.channel_id != before.channel_id {
+            self.check_key_free(Some(id), &after.channel_id).await?;
+        }
+
+        let (live, outro_planned) = {
+            let processes = self.processes.read().await;
+            let process = processes.get(id);
+            (process.is_some(), process.is_some_and(|p| p.plan().outro.is_some() || p.plan().hold_at_end))
+        };
+        let mut restart_fields = edit::restart_fields(&before, &after);
+
+        // The running process plays the outro at a time worked out from the old deadline - moving it means starting over
+        if schedule_changed && outro_planned && !restart_fields.contains(&"schedule") {
+            restart_fields.push("schedule");
+        }
+
+        if live && !restart_fields.is_empty() && !restart {
+            return Err(ManagerError::RestartRequired(restart_fields.join(", ")));
+        }
+
+        // Work out a new deadline up front - once the settings are written nothing may fail
+        let rearm = (live && schedule_changed && restart_fields.is_empty()).then(|| Self::remaining_after_edit(&after));
+
+        let db = self.db()?;
+        if let Some(channel) = &new_channel {
+            db.insert_channel(channel).await?;
+            tracing::info!("Created channel {} for stream {}", channel.id, id);
         }
+        db.update_stream(&after).await?;
+        drop(vault);
+        tracing::info!("Updated stream {}", id);
 
+        if !live {
+            return Ok(());
+        }
+        if !restart_fields.is_empty() {
+            tracing::info!("Restarting stream {} to apply {}", id, restart_fields.join(", "));
+            self.run_stop(id).await?;
+            return self.run_start(id, false).await;
+        }
+        if let Some(remaining) = rearm {
+            self.rearm_scheduler(&db, id, remaining).await;
+        }
         Ok(())
     }
 
-    pub async fn delete_stream(&mut self, id: &str) -> Result<(), ManagerError> {
+    /// Time left until the edited schedule of a live stream stops it
+    fn remaining_after_edit(stream: &Stream) -> Option<Duration> {
+        // Durations count from when the stream went live
+        let live_for = stream.started_at.as_deref()
+            .and_then(|started| chrono::DateTime::parse_from_rfc3339(started).ok())
+            .map(|started| (chrono::Utc::now() - started.with_timezone(&chrono::Utc)).to_std().unwrap_or_default())
+            .unwrap_or_default();
+        match stream.schedule.schedule_type {
+            ScheduleType::Duration => Self::stop_after(&stream.schedule).map(|d| d.saturating_sub(live_for)),
+            _ => Self::stop_after(&stream.schedule),
+        }
+    }
+
+    /// Replace a live stream's deadline after its schedule was edited
+    async fn rearm_scheduler(&self, db: &Database, id: &str, remaining: Option<Duration>) {
+        if let Some(scheduler) = self.schedulers.write().await.remove(id) {
+            scheduler.cancel();
+        }
+        tracing::info!("Re-arming scheduler for stream {}: {:?} left", id, remaining);
+        self.setup_scheduler(id, remaining).await;
+
+        if let Err(e) = db.update_session_schedule(id, remaining.map(|d| d.as_secs())).await {
+            tracing::error!("Error updating session deadline of stream {}: {}", id, e);
+        }
+    }
+
+    async fn run_archive(&self, id: &str) -> Result<(), ManagerError> {
+        let stream = self.db()?.get_stream(id).await?
+            .ok_or_else(|| ManagerError::NotFound(id.to_string()))?;
+        if stream.archived_at.is_some() {
+            return Ok(());
+        }
+
         // Make sure stream is stopped first
-        let stream = self.db()?.get_stream(id).await?;
-        if let Some(s) = stream {
-            if s.status == StreamStatus::Live {
-                self.stop_stream(id).await?;
-            }
+        if stream.status == StreamStatus::Live {
+            self.run_stop(id).await?;
+        }
+
+        self.db()?.archive_stream(id, &chrono::Utc::now().to_rfc3339()).await?;
+        tracing::info!("Archived stream {}", id);
+        Ok(())
+    }
+
+    async fn run_purge(&self, id: &str) -> Result<(), ManagerError> {
+        let stream = self.db()?.get_stream(id).await?
+            .ok_or_else(|| ManagerError::NotFound(id.to_string()))?;
+        if stream.archived_at.is_none() {
+            return Err(ManagerError::NotArchived(id.to_string()));
         }
         
         self.db()?.delete_stream(id).await?;
+        tracing::info!("Purged stream {}", id);
         Ok(())
     }
 
-    async fn setup_scheduler(&self, id: &str, stream: &Stream) -> Result<(), ManagerError> {
-        use crate::stream::types::ScheduleType;
-
-        let stop_after_seconds = match &stream.schedule.schedule_type {
+    /// Time until the schedule's stop deadline, if it has one
+    fn stop_after(schedule: &ScheduleConfig) -> Option<Duration> {
+        let seconds = match &schedule.schedule_type {
             ScheduleType::Duration => {
-                stream.schedule.duration.as_ref().map(|d| d.to_seconds())
+                schedule.duration.as_ref().map(|d| d.to_seconds())
             }
             ScheduleType::Absolute => {
-                stream.schedule.absolute.as_ref().and_then(|abs| {
+                schedule.absolute.as_ref().and_then(|abs| {
                     Scheduler::calculate_seconds_until(&abs.datetime, &abs.timezone)
                 })
             }
-            ScheduleType::Manual => None,
-        };
-
-        if let Some(seconds) = stop_after_seconds {
-            let id_for_scheduler = id.to_string();
-            let id_for_insert = id.to_string();
-            let processes = self.processes.clone();
-            let db = self.db.clone();
-            let schedulers = self.schedulers.clone();
-
-            let scheduler = Scheduler::new(seconds, move || {
-                let id = id_for_scheduler.clone();
-                let processes = processes.clone();
-                let db = db.clone();
-                let schedulers = schedulers.clone();
-                
-                tokio::spawn(async move {
-                    tracing::info!("Scheduled stop triggered for stream: {}", id);
-                    
-                    // Get elapsed before stopping
-                    let elapsed = {
-                        let procs = processes.read().await;
-                        procs.get(&id).map(|p| p.elapsed_seconds())
-                    };
-                    
-                    // Remove scheduler
-                    {
-                        let mut scheds = schedulers.write().await;
-                        scheds.remove(&id);
-                    }
-                    
-                    // Stop process
-                    {
-                        let mut procs = processes.write().await;
-                        if let Some(mut process) = procs.remove(&id) {
-                            if let Err(e) = process.stop().await {
-                                tracing::error!("Error stopping process: {}", e);
-                            }
-                        }
-                    }
-                    
-                    // Update DB - mark as Completed (scheduled stop)
-                    if let Some(db) = db {
-                        if let Err(e) = db.update_stream_status(&id, StreamStatus::Completed).await {
-                            tracing::error!("Error updating stream status: {}", e);
-                        }
-                        if let Err(e) = db.update_stream_stopped_at(&id).await {
-                            tracing::error!("Error updating stopped_at: {}", e);
-                        }
-                        if let Some(secs) = elapsed {
-                            if let Err(e) = db.update_stream_last_elapsed(&id, secs).await {
-                                tracing::error!("Error updating last_elapsed: {}", e);
-                            }
-                        }
-                    }
+            ScheduleType::Manual | ScheduleType::Plays => None,
+        };
+
+        seconds.map(Duration::from_secs)
+    }
+
+    /// Build what the first FFmpeg process plays: intro, main content, and the
+    /// outro ending at the stop, or a fade into it when there is no outro
+    async fn build_plan(
+        stream: &Stream,
+        stop_after: Option<Duration>,
+        resume_from: Option<f64>,
+    ) -> Result<PlayoutPlan, ManagerError> {
+        let ffprobe_path = Self::get_ffprobe_path();
+        let play_count = match stream.schedule.schedule_type {
+            ScheduleType::Plays => Some(stream.schedule.plays.unwrap_or(1).max(1)),
+            _ => None,
+        };
+        let mut plan = PlayoutPlan::main(&stream.video_path, play_count);
+        plan.main_trim = stream.trim;
+
+        // Media length is only needed for position tracking - streaming works without it,
+        // unless trim points have to be checked against it
+        if !stream.trim.is_empty() {
+            plan.media_seconds = Some(Self::trimmed_length(&ffprobe_path, &stream.video_path, &stream.trim).await?);
+        } else if stream.resume.enabled {
+            match probe::duration_seconds(&ffprobe_path, &stream.video_path).await {
+                Ok(length) => plan.media_seconds = Some(length),
+                Err(e) => tracing::warn!("Playback position tracking disabled: {}", e),
+            }
+        }
+
+        match (resume_from, &stream.clips.intro_path) {
+            // Viewers already saw the intro before the interruption
+            (Some(position), _) => {
+                tracing::info!("Resuming stream {} at {:.1}s", stream.id, position.max(0.0));
+                plan = plan.resume_at(position);
+            }
+            (None, Some(intro)) => {
+                plan.intro_seconds = Self::trimmed_length(&ffprobe_path, intro, &stream.clips.intro_trim).await?;
+                plan.intro_has_audio = Self::has_audio(&ffprobe_path, intro).await?;
+                plan.intro_path = Some(intro.clone());
+                plan.intro_trim = stream.clips.intro_trim;
+            }
+            (None, None) => {}
+        }
+
+        match (&stream.clips.outro_path, stop_after) {
+            // Give way to the outro early enough that it ends exactly at the deadline
+            (Some(outro), Some(deadline)) => {
+                let trim = stream.clips.outro_trim;
+                let length_seconds = Self::trimmed_length(&ffprobe_path, outro, &trim).await?;
+                plan.outro = Some(OutroSegment {
+                    path: outro.clone(),
+                    trim,
+                    length_seconds,
+                    fade_seconds: stream.clips.fade_out_seconds,
+                    starts_at: (deadline.as_secs_f64() - length_seconds).max(0.0),
+                    has_audio: Self::has_audio(&ffprobe_path, outro).await?,
                 });
-            });
+            }
+            (None, Some(deadline)) => {
+                plan.fade_out = playout::fade_at_end(deadline.as_secs_f64(), stream.clips.fade_out_seconds);
+            }
+            (_, None) => {}
+        }
 
-            let mut schedulers = self.schedulers.write().await;
-            schedulers.insert(id_for_insert, scheduler);
+        // Clips are scaled to the main content and given sound if they have none
+        if plan.intro_path.is_some() || plan.outro.is_some() {
+            plan.canvas = Some(probe::resolution(&ffprobe_path, &stream.video_path).await
+                .map_err(|e| ManagerError::FFmpeg(e.to_string()))?);
+            plan.has_audio = Self::has_audio(&ffprobe_path, &stream.video_path).await?;
         }
 
-        Ok(())
+        Ok(plan)
+    }
+
+    async fn has_audio(ffprobe_path: &Path, path: &str) -> Result<bool, ManagerError> {
+        probe::has_audio(ffprobe_path, path).await.map_err(|e| ManagerError::FFmpeg(e.to_string()))
+    }
+
+    /// Probe a file and return the length of its trimmed segment
+    async fn trimmed_length(ffprobe_path: &Path, path: &str, trim: &MediaTrim) -> Result<f64, ManagerError> {
+        let duration = probe::duration_seconds(ffprobe_path, path).await
+            .map_err(|e| ManagerError::FFmpeg(e.to_string()))?;
+        trim.validate(duration)
+            .map_err(|error| ManagerError::InvalidTrim { path: path.to_string(), error })?;
+        Ok(trim.length(duration))
+    }
+
+    async fn setup_scheduler(&self, id: &str, stop_after: Option<Duration>) {
+        let Some(stop_after) = stop_after else {
+            return;
+        };
+
+        let manager = self.clone();
+        let id_for_scheduler = id.to_string();
+
+        // Firing only queues the stop on the stream's actor
+        let scheduler = Scheduler::new(stop_after, move || {
+            manager.notify(&id_for_scheduler, StreamCommand::ScheduledStop);
+        });
+        self.events.emit(StreamEvent::SchedulerArmed {
+            stream_id: id.to_string(),
+            action: ScheduledAction::Stop,
+            fires_in_seconds: stop_after.as_secs(),
+        });
+
+        let mut schedulers = self.schedulers.write().await;
+        schedulers.insert(id.to_string(), scheduler);
+    }
+
+    async fn scheduled_stop(&self, id: &str) {
+        // A stop that was already handled cancelled this deadline, but it may have fired first
+        if !self.failovers.read().await.contains_key(id) {
+            return;
+        }
+
+        tracing::info!("Scheduled stop triggered for stream: {}", id);
+        self.events.emit(StreamEvent::SchedulerFired {
+            stream_id: id.to_string(),
+            action: ScheduledAction::Stop,
+        });
+        
+        // Get elapsed before stopping
+        let (elapsed, live) = {
+            let procs = self.processes.read().await;
+            let process = procs.get(id);
+            (process.map(|p| p.elapsed_seconds()), process.map(|p| p.live_time().as_secs()))
+        };
+        
+        // Remove scheduler
+        {
+            let mut scheds = self.schedulers.write().await;
+            scheds.remove(id);
+        }
+        self.failovers.write().await.remove(id);
+
+        // Stop process
+        let process = match self.stop_process(id, elapsed, live).await {
+            Ok(process) => process,
+            Err(e) => {
+                tracing::error!("Error stopping process: {}", e);
+                return;
+            }
+        };
+
+        // Mark as Completed (scheduled stop)
+        let end = session_end(SessionEndReason::Schedule, process.as_ref());
+        let completed = Transition::completed(elapsed, end).live_for(live);
+        if let Err(e) = self.transition(id, completed).await {
+            tracing::error!("Error completing stream {}: {}", id, e);
+        }
     }
 
     fn get_ffmpeg_path() -> PathBuf {
+        Self::get_binary_path("ffmpeg")
+    }
+
+    fn get_ffprobe_path() -> PathBuf {
+        Self::get_binary_path("ffprobe")
+    }
+
+    fn get_binary_path(name: &str) -> PathBuf {
         // Check bundled binary first
         if let Ok(exe_path) = std::env::current_exe() {
             let resource_dir = exe_path.parent().unwrap_or(&exe_path);
             
             #[cfg(windows)]
-            let bundled = resource_dir.join("binaries").join("ffmpeg.exe");
+            let bundled = resource_dir.join("binaries").join(format!("{}.exe", name));
             
             #[cfg(not(windows))]
-            let bundled = resource_dir.join("binaries").join("ffmpeg");
+            let bundled = resource_dir.join("binaries").join(name);
             
             if bundled.exists() {
                 return bundled;
@@ -401,7 +2210,7 @@ impl StreamManager {
                 let macos_resources = resource_dir
                     .parent()
                     .and_then(|p| p.parent())
-                    .map(|p| p.join("Resources").join("binaries").join("ffmpeg"));
+                    .map(|p| p.join("Resources").join("binaries").join(name));
                 if let Some(path) = macos_resources {
                     if path.exists() {
                         return path;
@@ -410,12 +2219,12 @@ impl StreamManager {
             }
         }
 
-        // Fallback to system ffmpeg
+        // Fallback to system binary
         #[cfg(windows)]
-        return PathBuf::from("ffmpeg.exe");
+        return PathBuf::from(format!("{}.exe", name));
         
         #[cfg(not(windows))]
-        PathBuf::from("ffmpeg")
+        PathBuf::from(name)
     }
 }
 
@@ -424,3 +2233,527 @@ impl Default for StreamManager {
         Self::new()
     }
 }
+
+/// How often stats are published for running streams
+const STATS_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Stats ticks between heartbeats persisted to the database
+const HEARTBEAT_TICKS: u64 = 5;
+
+/// How long FFmpeg has to stay up before a start counts as successful
+const STARTUP_CHECK: Duration = Duration::from_secs(2);
+
+/// Settings key the operator's locale is saved under
+const LOCALE_SETTING: &str = "locale";
+
+/// Settings key of the archive retention, in days or `never`
+const ARCHIVE_RETENTION_SETTING: &str = "archiveRetentionDays";
+
+const DEFAULT_ARCHIVE_RETENTION_DAYS: u32 = 30;
+
+/// Longest archive retention that can be set - ten years
+const MAX_ARCHIVE_RETENTION_DAYS: u32 = 3650;
+
+/// How often streams past the archive retention are looked for
+const ARCHIVE_SWEEP_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// A process the supervisor found dead, captured before it is dropped
+struct ExitedProcess {
+    id: String,
+    exit: ProcessExit,
+    elapsed: u64,
+    live: Duration,
+    plan: PlayoutPlan,
+    position: Option<f64>,
+    encoder_stats: EncoderStats,
+    log_tail: Vec<String>,
+}
+
+/// A new stream with the input's settings, not yet on a channel
+fn new_stream(input: &StreamInput) -> Stream {
+    Stream {
+        id: Uuid::new_v4().to_string(),
+        name: input.name.trim().to_string(),
+        channel_id: String::new(),
+        channel_name: String::new(),
+        youtube_key: input.youtube_key.trim().to_string(),
+        masked_key: String::new(),
+        video_path: input.video_path.clone(),
+        trim: input.trim,
+        ingest_endpoints: input.ingest_endpoints.clone(),
+        clips: input.clips.clone(),
+        status: StreamStatus::Idle,
+        status_reason: None,
+        schedule: input.schedule.clone(),
+        started_at: None,
+        stopped_at: None,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        elapsed_seconds: None,
+        last_elapsed_seconds: None,
+        live_seconds: None,
+        last_seen_at: None,
+        active_endpoint: None,
+        resume: input.resume.clone(),
+        playback_position: None,
+        tags: edit::normalize_tags(&input.tags),
+        archived_at: None,
+    }
+}
+
+/// Publish a stream to a channel, taking the channel's key
+fn join_channel(stream: &mut Stream, channel: &Channel) {
+    stream.channel_id = channel.id.clone();
+    stream.channel_name = channel.name.clone();
+    stream.youtube_key = channel.youtube_key.clone();
+    stream.masked_key = channel.masked_key.clone();
+}
+
+/// How a session ended, with the final output of its last FFmpeg process if it had one
+fn session_end(reason: SessionEndReason, process: Option<&FFmpegProcess>) -> SessionEnd {
+    let end = SessionEnd::new(reason);
+    match process {
+        Some(process) => end.with_output(process.encoder_stats(), process.log_tail()),
+        None => end,
+    }
+}
+
+/// Why FFmpeg exited
+fn exit_reason(exit: ProcessExit) -> Message {
+    match exit {
+        ProcessExit::EndOfInput => Message::new("reason.endOfInput", &Params::new()),
+        ProcessExit::Crashed(Some(code)) => Message::new("reason.exitCode", &i18n::params([("code", code.to_string())])),
+        ProcessExit::Crashed(None) => Message::new("reason.terminated", &Params::new()),
+        ProcessExit::Stopped => Message::new("reason.stopped", &Params::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::bulk::BulkOutcome;
+    use crate::stream::fixture::{channel, input, manager, refusing_endpoint, with_streams};
+    use crate::stream::types::DurationConfig;
+
+    /// Queue a command on the stream's actor without waiting for it
+    fn send(manager: &StreamManager, id: &str, command: impl FnOnce(Reply) -> StreamCommand) -> oneshot::Receiver<Result<(), ManagerError>> {
+        let (reply, result) = oneshot::channel();
+        manager.dispatch(id, command(reply));
+        result
+    }
+
+    /// Put a stream live on a process that publishes nowhere, standing in for FFmpeg
+    #[cfg(unix)]
+    async fn go_live(manager: &StreamManager, id: &str) {
+        use std::os::unix::fs::PermissionsExt;
+
+        let ffmpeg = std::env::temp_dir().join(format!("ylm-ffmpeg-{}", Uuid::new_v4()));
+        std::fs::write(&ffmpeg, "#!/bin/sh\nexec sleep 60\n").unwrap();
+        std::fs::set_permissions(&ffmpeg, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let plan = PlayoutPlan::main(file!(), None);
+        let process = FFmpegProcess::start(&ffmpeg, &plan, "rtmp://127.0.0.1/live2/x", id, &manager.exits).await.unwrap();
+
+        manager.processes.write().await.insert(id.to_string(), process);
+        manager.transition(id, Transition::live()).await.unwrap();
+    }
+
+    async fn actors_retired(manager: &StreamManager) -> bool {
+        for _ in 0..100 {
+            if manager.actors.lock().unwrap().is_empty() {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        false
+    }
+
+    #[tokio::test]
+    async fn test_commands_run_in_the_order_they_were_sent() {
+        let unreachable = StreamInput { ingest_endpoints: vec![refusing_endpoint().await], ..input("Lofi radio", "aaaa-bbbb") };
+        let (manager, [stream]) = with_streams([unreachable]).await;
+
+        let started = send(&manager, &stream.id, StreamCommand::Start);
+        let archived = send(&manager, &stream.id, StreamCommand::Archive);
+        let purged = send(&manager, &stream.id, StreamCommand::Purge);
+        let late = send(&manager, &stream.id, StreamCommand::Start);
+
+        // The start ran before the archive, and nothing queued behind the purge runs
+        assert!(matches!(started.await.unwrap(), Err(ManagerError::NoIngestEndpoint)));
+        archived.await.unwrap().unwrap();
+        purged.await.unwrap().unwrap();
+        assert!(matches!(late.await.unwrap(), Err(ManagerError::NotFound(_))));
+        assert!(manager.db().unwrap().get_stream(&stream.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_streams_do_not_wait_for_each_other() {
+        let unreachable = StreamInput { ingest_endpoints: vec![refusing_endpoint().await], ..input("Lofi radio", "aaaa-bbbb") };
+        let (manager, [first, second]) = with_streams([unreachable, input("Rain sounds", "cccc-dddd")]).await;
+
+        // Hold the start claims so the first stream's start stalls on its actor
+        let claims = manager.starting.lock().await;
+        let started = send(&manager, &first.id, StreamCommand::Start);
+        let mut deleted = send(&manager, &first.id, StreamCommand::Archive);
+
+        // The second stream's actor carries on meanwhile, the first stream's queue does not
+        manager.delete_stream(&second.id).await.unwrap();
+        assert!(deleted.try_recv().is_err());
+
+        drop(claims);
+        assert!(matches!(started.await.unwrap(), Err(ManagerError::NoIngestEndpoint)));
+        deleted.await.unwrap().unwrap();
+        assert!(manager.get_streams(StreamQuery::default()).await.unwrap().streams.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_added_channels_hold_their_key_sealed() {
+        let manager = manager().await;
+        let added = manager.add_channel(channel(" Music ", " aaaa-bbbb-cccc ")).await.unwrap();
+        assert_eq!(added.name, "Music");
+        assert_eq!(added.masked_key, "••••cccc");
+        assert!(manager.vault().await.unwrap().is_current(&added.youtube_key));
+
+        let result = manager.add_channel(channel("Music again", "aaaa-bbbb-cccc")).await;
+        let Err(ManagerError::Validation(errors)) = result else { panic!("expected a validation error") };
+        assert_eq!(errors.iter().map(|e| (e.field.as_str(), e.code)).collect::<Vec<_>>(), [("youtubeKey", ErrorCode::InUse)]);
+        assert_eq!(manager.get_channels().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_streams_typing_the_same_key_share_a_channel() {
+        let (manager, [first, second]) = with_streams([input("Lofi radio", "aaaa-bbbb"), input("Rain sounds", " aaaa-bbbb ")]).await;
+
+        assert_eq!(first.channel_id, second.channel_id);
+        let channels = manager.get_channels().await.unwrap();
+        assert_eq!(channels.len(), 1);
+        assert_eq!(channels[0].name, "Lofi radio");
+    }
+
+    #[tokio::test]
+    async fn test_channels_in_use_are_not_deleted() {
+        let (manager, [stream]) = with_streams([input("Lofi radio", "aaaa-bbbb")]).await;
+        let unused = manager.add_channel(channel("Spare", "cccc-dddd")).await.unwrap();
+
+        let result = manager.delete_channel(&stream.channel_id).await;
+        assert!(matches!(result, Err(ManagerError::ChannelInUse { ref name, count: 1 }) if name == "Lofi radio"));
+
+        manager.delete_channel(&unused.id).await.unwrap();
+        let ids: Vec<String> = manager.get_channels().await.unwrap().into_iter().map(|c| c.id).collect();
+        assert_eq!(ids, [stream.channel_id]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_channel_changes_reach_live_streams_only_by_restarting_them() {
+        let (manager, [stream]) = with_streams([input("Lofi radio", "aaaa-bbbb")]).await;
+        go_live(&manager, &stream.id).await;
+
+        // The live stream publishes to the channel's destination, having no endpoints of its own
+        let destination = refusing_endpoint().await;
+        let patch = ChannelPatch { destination: Some(destination.clone()), ..Default::default() };
+        let result = manager.update_channel(&stream.channel_id, patch.clone(), false).await;
+        assert!(matches!(result, Err(ManagerError::RestartRequired(ref fields)) if fields == "destination"));
+        assert_eq!(manager.db().unwrap().get_channel(&stream.channel_id).await.unwrap().unwrap().destination, None);
+
+        // A rename alone leaves it running
+        let renamed = ChannelPatch { name: Some("Music".into()), ..Default::default() };
+        manager.update_channel(&stream.channel_id, renamed, false).await.unwrap();
+        assert!(manager.processes.read().await.contains_key(&stream.id));
+
+        let updated = manager.update_channel(&stream.channel_id, patch, true).await.unwrap();
+        assert_eq!(updated.destination, Some(destination));
+        // Stopped to relaunch on the new destination, which refuses it here
+        assert!(!manager.processes.read().await.contains_key(&stream.id));
+        let stopped = manager.db().unwrap().get_stream(&stream.id).await.unwrap().unwrap();
+        assert_eq!(stopped.status, StreamStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_commands_report_each_tagged_stream() {
+        let endpoint = refusing_endpoint().await;
+        let tagged = |name: &str, key: &str| StreamInput {
+            tags: vec!["lofi".into()],
+            ingest_endpoints: vec![endpoint.clone()],
+            ..input(name, key)
+        };
+        let (manager, [lofi, beats, untagged]) =
+            with_streams([tagged("Lofi radio", "aaaa-bbbb"), tagged("Beats", "cccc-dddd"), input("Rain sounds", "eeee-ffff")]).await;
+        let outcomes = |report: &BulkReport| report.results.iter().map(|r| (r.name.clone(), r.outcome)).collect::<Vec<_>>();
+
+        // In name order, skipping streams already as asked
+        let stopped = manager.bulk_stop("lofi").await.unwrap();
+        assert_eq!(outcomes(&stopped), [("Beats".into(), BulkOutcome::Skipped), ("Lofi radio".into(), BulkOutcome::Skipped)]);
+
+        let started = manager.bulk_start("lofi").await.unwrap();
+        assert_eq!(started.failed, 2);
+        assert!(started.results.iter().all(|r| matches!(r.error, Some(ManagerError::NoIngestEndpoint))));
+
+        let schedule = ScheduleConfig {
+            schedule_type: ScheduleType::Duration,
+            duration: Some(DurationConfig { hours: 1, minutes: 0, seconds: 0 }),
+            absolute: None,
+            plays: None,
+        };
+        let rescheduled = manager.bulk_reschedule("lofi", schedule, false).await.unwrap();
+        assert_eq!((rescheduled.done, rescheduled.failed), (2, 0));
+        for id in [&lofi.id, &beats.id] {
+            let stream = manager.db().unwrap().get_stream(id).await.unwrap().unwrap();
+            assert_eq!(stream.schedule.schedule_type, ScheduleType::Duration);
+        }
+
+        let deleted = manager.bulk_delete("lofi").await.unwrap();
+        assert_eq!(deleted.done, 2);
+        let left = manager.get_streams(StreamQuery::default()).await.unwrap();
+        assert_eq!(left.streams.into_iter().map(|s| s.id).collect::<Vec<_>>(), [untagged.id]);
+
+        assert!(matches!(manager.bulk_stop(" ").await, Err(ManagerError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_deleted_streams_wait_in_the_archive_until_purged() {
+        let (manager, [kept, purged]) = with_streams([input("Lofi radio", "aaaa-bbbb"), input("Rain sounds", "cccc-dddd")]).await;
+        let listed = |archived: bool| {
+            let manager = &manager;
+            async move {
+                let query = StreamQuery { archived, sort: StreamSort::Name, direction: SortDirection::Asc, ..Default::default() };
+                manager.get_streams(query).await.unwrap().streams.into_iter().map(|s| s.name).collect::<Vec<_>>()
+            }
+        };
+
+        // Only archived streams are restored or purged
+        assert!(matches!(manager.restore_stream(&kept.id).await, Err(ManagerError::NotArchived(_))));
+        assert!(matches!(manager.purge_stream(&kept.id).await, Err(ManagerError::NotArchived(_))));
+
+        manager.delete_stream(&kept.id).await.unwrap();
+        manager.delete_stream(&purged.id).await.unwrap();
+        assert!(listed(false).await.is_empty());
+        assert_eq!(listed(true).await, ["Lofi radio", "Rain sounds"]);
+
+        let restored = manager.restore_stream(&kept.id).await.unwrap();
+        assert_eq!(restored.archived_at, None);
+        manager.purge_stream(&purged.id).await.unwrap();
+        assert_eq!(listed(false).await, ["Lofi radio"]);
+        assert!(listed(true).await.is_empty());
+        assert!(matches!(manager.restore_stream(&purged.id).await, Err(ManagerError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_clones_take_the_next_free_name_and_share_the_channel() {
+        let (manager, [source]) = with_streams([input("Lofi radio", "aaaa-bbbb")]).await;
+        manager.tag_streams(std::slice::from_ref(&source.id), "lofi").await.unwrap();
+
+        let first = manager.clone_stream(&source.id, StreamPatch::default()).await.unwrap();
+        let second = manager.clone_stream(&source.id, StreamPatch::default()).await.unwrap();
+        assert_eq!((first.name.as_str(), second.name.as_str()), ("Lofi radio (2)", "Lofi radio (3)"));
+        assert_eq!((first.channel_id.as_str(), first.status), (source.channel_id.as_str(), StreamStatus::Idle));
+        assert_eq!(first.tags, ["lofi"]);
+
+        // A new key puts the clone on a channel of its own
+        let moved = manager.clone_stream(&source.id, StreamPatch {
+            name: Some("Rain sounds".into()),
+            youtube_key: Some("cccc-dddd".into()),
+            ..Default::default()
+        }).await.unwrap();
+        assert_eq!(moved.name, "Rain sounds");
+        assert_ne!(moved.channel_id, source.channel_id);
+
+        // Overrides are validated like a new stream
+        let result = manager.clone_stream(&source.id, StreamPatch { video_path: Some(" ".into()), ..Default::default() }).await;
+        assert!(matches!(result, Err(ManagerError::Validation(_))));
+        assert!(matches!(manager.clone_stream("missing", StreamPatch::default()).await, Err(ManagerError::NotFound(_))));
+        assert_eq!(manager.get_streams(StreamQuery::default()).await.unwrap().streams.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_idle_actors_are_retired_and_respawned() {
+        let (manager, [stream]) = with_streams([input("Lofi radio", "aaaa-bbbb")]).await;
+        let rename = |name: &str| StreamPatch { name: Some(name.to_string()), ..Default::default() };
+
+        manager.update_stream(&stream.id, rename("Lofi radio 24/7"), false).await.unwrap();
+        assert!(actors_retired(&manager).await);
+
+        // The next command gets a fresh actor
+        manager.update_stream(&stream.id, rename("Lofi radio"), false).await.unwrap();
+        assert!(actors_retired(&manager).await);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_update_writes_nothing() {
+        let (manager, [stream]) = with_streams([input("Lofi radio", "aaaa-bbbb")]).await;
+
+        let patch = StreamPatch {
+            name: Some("Lofi radio 24/7".into()),
+            schedule: Some(ScheduleConfig {
+                schedule_type: ScheduleType::Duration,
+                duration: Some(DurationConfig { hours: 0, minutes: 0, seconds: 0 }),
+                absolute: None,
+                plays: None,
+            }),
+            ..Default::default()
+        };
+        let result = manager.update_stream(&stream.id, patch, false).await;
+        assert!(matches!(result, Err(ManagerError::Validation(_))));
+        assert_eq!(manager.db().unwrap().get_stream(&stream.id).await.unwrap().unwrap().name, "Lofi radio");
+    }
+
+    #[tokio::test]
+    async fn test_rotation_keeps_the_master_key_when_a_key_does_not_open() {
+        let (manager, [stream, broken]) = with_streams([input("Lofi radio", "aaaa-bbbb"), input("Rain sounds", "cccc-dddd")]).await;
+        let db = manager.db().unwrap();
+        db.replace_channel_keys(&[(broken.channel_id.clone(), "enc:v1:deadbeef:AAAA".into(), "••••dddd".into())])
+            .await
+            .unwrap();
+
+        let result = manager.rotate_master_key().await;
+        assert!(matches!(result, Err(ManagerError::KeysNotResealed(ref ids)) if *ids == [broken.channel_id]));
+
+        // Nothing was resealed with the dropped key
+        assert_eq!(manager.reveal_stream_key(&stream.id).await.unwrap(), "aaaa-bbbb");
+        let keys = db.get_channel_keys().await.unwrap();
+        let vault = manager.vault().await.unwrap();
+        assert!(keys.iter().any(|(id, key, _)| *id == stream.channel_id && vault.is_current(key)));
+    }
+
+    #[tokio::test]
+    async fn test_export_leaves_out_keys_that_do_not_open() {
+        let (manager, [_, broken]) = with_streams([input("Lofi radio", "aaaa-bbbb"), input("Rain sounds", "cccc-dddd")]).await;
+        manager.db().unwrap()
+            .replace_channel_keys(&[(broken.channel_id.clone(), "enc:v1:deadbeef:AAAA".into(), "••••dddd".into())])
+            .await
+            .unwrap();
+
+        let path = std::env::temp_dir().join(format!("ylm-export-{}.json", Uuid::new_v4()));
+        let report = manager.export_streams(&path, TransferFormat::Json, false).await.unwrap();
+        assert_eq!(report.exported, 2);
+        assert_eq!(report.redacted, ["Rain sounds"]);
+
+        let records = transfer::parse(&std::fs::read_to_string(&path).unwrap(), TransferFormat::Json).unwrap();
+        let keys: HashMap<String, Option<String>> = records.into_iter()
+            .map(|r| r.unwrap())
+            .map(|r| (r.name, r.youtube_key))
+            .collect();
+        assert_eq!(keys["Lofi radio"].as_deref(), Some("aaaa-bbbb"));
+        assert_eq!(keys["Rain sounds"], None);
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_restored_keys_sealed_before_a_rotation_still_open() {
+        let (manager, [stream]) = with_streams([input("Lofi radio", "aaaa-bbbb")]).await;
+        let snapshot = manager.create_backup().await.unwrap();
+        manager.rotate_master_key().await.unwrap();
+        manager.rotate_master_key().await.unwrap();
+        let added = manager.add_stream(input("Rain sounds", "cccc-dddd")).await.unwrap();
+
+        manager.restore_backup(&snapshot.file_name).await.unwrap();
+        assert_eq!(manager.reveal_stream_key(&stream.id).await.unwrap(), "aaaa-bbbb");
+        assert!(manager.db().unwrap().get_stream(&added.id).await.unwrap().is_none());
+
+        // Resealed with the current master key on the way in
+        let vault = manager.vault().await.unwrap();
+        let keys = manager.db().unwrap().get_channel_keys().await.unwrap();
+        assert!(keys.iter().all(|(_, key, _)| vault.is_current(key)));
+    }
+
+    #[tokio::test]
+    async fn test_snapshots_whose_keys_do_not_open_are_not_restored() {
+        let (manager, [stream]) = with_streams([input("Lofi radio", "aaaa-bbbb")]).await;
+        let db = manager.db().unwrap();
+        let keys = db.get_channel_keys().await.unwrap();
+        db.replace_channel_keys(&[(stream.channel_id.clone(), "enc:v1:deadbeef:AAAA".into(), "••••bbbb".into())])
+            .await
+            .unwrap();
+        let snapshot = manager.create_backup().await.unwrap();
+        let (id, key, masked) = keys.into_iter().next().unwrap();
+        db.replace_channel_keys(&[(id, key, masked.unwrap())]).await.unwrap();
+
+        let result = manager.restore_backup(&snapshot.file_name).await;
+        assert!(matches!(result, Err(ManagerError::KeysNotResealed(_))));
+
+        // The database in use was left as it was, and no undo snapshot was taken
+        assert_eq!(manager.reveal_stream_key(&stream.id).await.unwrap(), "aaaa-bbbb");
+        assert_eq!(manager.list_backups().await.unwrap(), [snapshot]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_restore_waits_for_a_restart_already_queued_on_a_live_stream() {
+        let unreachable = StreamInput { ingest_endpoints: vec![refusing_endpoint().await], ..input("Lofi radio", "aaaa-bbbb") };
+        let (manager, [stream]) = with_streams([unreachable]).await;
+        let snapshot = manager.create_backup().await.unwrap();
+        go_live(&manager, &stream.id).await;
+
+        // The relaunch claims a start while the restore's stop waits behind it
+        let restarted = send(&manager, &stream.id, StreamCommand::Restart);
+        let restored = tokio::time::timeout(Duration::from_secs(10), manager.restore_backup(&snapshot.file_name)).await;
+        assert!(matches!(restored, Ok(Ok(()))));
+        assert!(restarted.await.unwrap().is_err());
+        assert!(manager.processes.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_a_failed_rollback_still_reattaches_a_database() {
+        let (manager, [stream]) = with_streams([input("Lofi radio", "aaaa-bbbb")]).await;
+        let db = manager.db().unwrap();
+        let path = db.path().to_path_buf();
+        db.close().await;
+        let vault = manager.vault().await.unwrap();
+
+        // No snapshot to put back: the file at the path is reopened as it is
+        let db = StreamManager::reattach_database(&path, "missing.db", &vault).await.unwrap();
+        assert!(db.get_stream(&stream.id).await.unwrap().is_some());
+
+        let lost = StreamManager::reattach_database(&path.join("missing.db"), "missing.db", &vault).await;
+        assert!(matches!(lost, Err(ManagerError::DatabaseLost(_))));
+    }
+
+    #[tokio::test]
+    async fn test_archive_retention_is_bounded_and_purges_what_it_outlived() {
+        let (manager, [old, recent]) = with_streams([input("Lofi radio", "aaaa-bbbb"), input("Rain sounds", "cccc-dddd")]).await;
+        for days in [0, MAX_ARCHIVE_RETENTION_DAYS + 1, u32::MAX] {
+            let Err(ManagerError::Validation(errors)) = manager.set_archive_retention(Some(days)).await else {
+                panic!("expected a validation error for {} days", days)
+            };
+            assert_eq!(errors[0].code, ErrorCode::OutOfRange);
+        }
+        assert_eq!(manager.get_archive_retention().await.unwrap(), Some(DEFAULT_ARCHIVE_RETENTION_DAYS));
+
+        let db = manager.db().unwrap();
+        db.archive_stream(&old.id, "2001-05-07T10:00:00+00:00").await.unwrap();
+        db.archive_stream(&recent.id, &chrono::Utc::now().to_rfc3339()).await.unwrap();
+
+        manager.set_archive_retention(Some(MAX_ARCHIVE_RETENTION_DAYS)).await.unwrap();
+        assert_eq!(manager.get_archive_retention().await.unwrap(), Some(MAX_ARCHIVE_RETENTION_DAYS));
+        assert!(db.get_stream(&old.id).await.unwrap().is_none());
+        assert!(db.get_stream(&recent.id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_templates_outlive_their_stop_time() {
+        let manager = manager().await;
+        let template: TemplateInput = serde_json::from_value(serde_json::json!({
+            "name": "Evening show",
+            "schedule": {
+                "type": "absolute",
+                "duration": null,
+                "absolute": { "datetime": "2024-05-07T22:00", "timezone": "UTC" },
+            },
+        }))
+        .unwrap();
+        let template = manager.add_template(template).await.unwrap();
+
+        // Each stream made from it still needs a stop time to come
+        let overrides = StreamPatch {
+            name: Some("Evening show".into()),
+            youtube_key: Some("aaaa-bbbb".into()),
+            video_path: Some(file!().into()),
+            ..Default::default()
+        };
+        let result = manager.create_from_template(&template.id, overrides.clone(), false).await;
+        let Err(ManagerError::Validation(errors)) = result else { panic!("expected a validation error") };
+        assert_eq!(errors.iter().map(|e| e.code).collect::<Vec<_>>(), [ErrorCode::InPast]);
+
+        let schedule = ScheduleConfig { schedule_type: ScheduleType::Manual, duration: None, absolute: None, plays: None };
+        let stream = manager.create_from_template(&template.id, StreamPatch { schedule: Some(schedule), ..overrides }, false).await.unwrap();
+        assert_eq!(stream.schedule.schedule_type, ScheduleType::Manual);
+    }
+}
@@ -1,4 +1,19 @@
+pub mod bulk;
+pub mod edit;
+pub mod events;
+#[cfg(test)]
+pub mod fixture;
+pub mod ingest;
 pub mod manager;
+pub mod playout;
+pub mod probe;
 pub mod process;
+pub mod query;
+pub mod redact;
+pub mod report;
 pub mod scheduler;
+pub mod state;
+pub mod template;
+pub mod transfer;
 pub mod types;
+pub mod validation;
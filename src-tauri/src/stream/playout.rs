@@ -0,0 +1,558 @@
+use std::path::{Path, PathBuf};
+
+use crate::stream::types::MediaTrim;
+
+/// Seconds the last frame and silence are held after a one-shot clip ends,
+/// so the process is still publishing when the scheduled stop kills it
+const HOLD_SECONDS: u32 = 10;
+
+/// Normalised audio so intro, main content and outro can be concatenated
+const AUDIO_FORMAT: &str = "aresample=44100,aformat=channel_layouts=stereo";
+
+/// Stands in for the sound of a clip that has none
+const SILENCE: &str = "anullsrc=r=44100:cl=stereo";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FadeOut {
+    /// Position in the output timeline where the fade begins
+    pub start_seconds: f64,
+    pub duration_seconds: u32,
+}
+
+/// A clip the main content gives way to, timed so that it ends at the scheduled stop
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutroSegment {
+    pub path: String,
+    pub trim: MediaTrim,
+    pub length_seconds: f64,
+    pub fade_seconds: u32,
+    /// Position in the output timeline where the main content gives way to the outro
+    pub starts_at: f64,
+    pub has_audio: bool,
+}
+
+/// What a single FFmpeg process plays, independent of encoder and destination
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayoutPlan {
+    pub video_path: String,
+    /// Segment of the main content that gets played and looped
+    pub main_trim: MediaTrim,
+    /// Played once before the main content
+    pub intro_path: Option<String>,
+    pub intro_trim: MediaTrim,
+    pub intro_has_audio: bool,
+    /// Whether the main content has a sound track
+    pub has_audio: bool,
+    /// Played once after the main content, in the same process so the publish never drops
+    pub outro: Option<OutroSegment>,
+    /// Canvas the intro and outro are scaled to, normally the main content's resolution
+    pub canvas: Option<(u32, u32)>,
+    /// Full plays of the main content, None = loop forever
+    pub play_count: Option<u32>,
+    /// Probed length of the main content, needed to resume and track position
+    pub media_seconds: Option<f64>,
+    /// Length of the intro, which comes before the main content in the output
+    pub intro_seconds: f64,
+    /// Media seconds already played in this run, across loops; the first pass seeks here
+    pub resume_from: f64,
+    /// Hold the last frame after a one-shot clip instead of ending the process
+    pub hold_at_end: bool,
+    pub fade_out: Option<FadeOut>,
+    /// ffconcat script carrying the trim points when a trimmed input loops
+    pub concat_script: Option<PathBuf>,
+}
+
+impl PlayoutPlan {
+    /// Main content played a number of times, or looped forever
+    pub fn main(video_path: &str, play_count: Option<u32>) -> Self {
+        Self {
+            video_path: video_path.to_string(),
+            main_trim: MediaTrim::default(),
+            intro_path: None,
+            intro_trim: MediaTrim::default(),
+            intro_has_audio: true,
+            has_audio: true,
+            outro: None,
+            canvas: None,
+            play_count,
+            media_seconds: None,
+            intro_seconds: 0.0,
+            resume_from: 0.0,
+            hold_at_end: false,
+            fade_out: None,
+            concat_script: None,
+        }
+    }
+
+    /// The outro on its own, played once and publishing until it is stopped
+    pub fn outro(outro: &OutroSegment) -> Self {
+        Self {
+            video_path: outro.path.clone(),
+            main_trim: outro.trim,
+            intro_path: None,
+            intro_trim: MediaTrim::default(),
+            intro_has_audio: true,
+            has_audio: outro.has_audio,
+            outro: None,
+            canvas: None,
+            play_count: Some(1),
+            media_seconds: None,
+            intro_seconds: 0.0,
+            resume_from: 0.0,
+            hold_at_end: true,
+            fade_out: fade_at_end(outro.length_seconds, outro.fade_seconds),
+            concat_script: None,
+        }
+    }
+
+    /// Plan for relaunching after a crash `output_seconds` into this one: the intro has
+    /// already played, the outro is that much closer, and fade timings were relative
+    /// to the original process start
+    pub fn for_restart(&self, output_seconds: f64) -> Self {
+        match &self.outro {
+            // The main content had already given way - finish with the outro
+            Some(outro) if output_seconds >= outro.starts_at => Self { canvas: self.canvas, ..Self::outro(outro) },
+            _ => Self {
+                intro_path: None,
+                intro_trim: MediaTrim::default(),
+                intro_seconds: 0.0,
+                outro: self.outro.clone().map(|outro| OutroSegment {
+                    starts_at: outro.starts_at - output_seconds,
+                    ..outro
+                }),
+                fade_out: None,
+                concat_script: None,
+                ..self.clone()
+            },
+        }
+    }
+
+    /// Whether FFmpeg reaches the end of its input on its own rather than looping until stopped
+    pub fn is_finite(&self) -> bool {
+        self.play_count.is_some() || self.outro.is_some()
+    }
+
+    /// Output seconds the main content plays before the outro takes over
+    fn main_seconds(&self) -> Option<f64> {
+        self.outro.as_ref().map(|outro| (outro.starts_at - self.intro_seconds).max(0.0))
+    }
+
+    /// Continue the main content from a position instead of from its start
+    pub fn resume_at(mut self, position: f64) -> Self {
+        if self.media_seconds.is_some() && !self.hold_at_end {
+            self.resume_from = position.max(0.0);
+        }
+        self
+    }
+
+    /// Media seconds of main content consumed after `output_seconds` of output.
+    /// None for one-shot clips and when the media length is unknown.
+    pub fn media_position(&self, output_seconds: f64) -> Option<f64> {
+        self.media_seconds?;
+        if self.hold_at_end {
+            return None;
+        }
+        let played = (output_seconds - self.intro_seconds).max(0.0);
+        Some(self.resume_from + self.main_seconds().map_or(played, |main| played.min(main)))
+    }
+
+    /// Where the first pass seeks to and how many plays remain from there
+    fn resume_point(&self) -> (f64, Option<u32>) {
+        match self.media_seconds {
+            Some(length) if length > 0.0 && self.resume_from > 0.0 => {
+                let plays_done = (self.resume_from / length).floor() as u32;
+                let remaining = self.play_count.map(|n| n.saturating_sub(plays_done).max(1));
+                (self.resume_from % length, remaining)
+            }
+            _ => (0.0, self.play_count),
+        }
+    }
+
+    /// `-ss`/`-to` only hold for the first pass of a looped input, so a trimmed
+    /// input that loops is read through an ffconcat script with in/out points
+    pub fn needs_concat_script(&self) -> bool {
+        !self.main_trim.is_empty() && self.play_count != Some(1)
+    }
+
+    pub fn concat_script_contents(&self) -> String {
+        let mut script = String::from("ffconcat version 1.0\n");
+        script.push_str(&format!("file '{}'\n", self.video_path.replace('\'', "'\\''")));
+        if let Some(start) = self.main_trim.start_seconds {
+            script.push_str(&format!("inpoint {:.3}\n", start));
+        }
+        if let Some(end) = self.main_trim.end_seconds {
+            script.push_str(&format!("outpoint {:.3}\n", end));
+        }
+        script
+    }
+
+    /// Write the concat script, if this plan needs one, into `dir`
+    pub fn prepare(&self, dir: &Path) -> std::io::Result<Self> {
+        let mut plan = self.clone();
+        if plan.needs_concat_script() {
+            let path = dir.join(format!("ylm-{}.ffconcat", uuid::Uuid::new_v4()));
+            std::fs::write(&path, plan.concat_script_contents())?;
+            plan.concat_script = Some(path);
+        }
+        Ok(plan)
+    }
+
+    pub fn input_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+
+        if let Some(intro) = &self.intro_path {
+            args.push("-re".into());
+            args.extend(trim_args(&self.intro_trim, 0.0));
+            args.extend(["-i".into(), intro.clone()]);
+        }
+
+        let (seek, play_count) = self.resume_point();
+
+        args.push("-re".into());
+        match play_count {
+            // -stream_loop counts repeats after the first play
+            Some(plays) if plays > 1 => {
+                args.extend(["-stream_loop".into(), (plays - 1).to_string()]);
+            }
+            Some(_) => {}
+            None => args.extend(["-stream_loop".into(), "-1".into()]),
+        }
+        // Cut short, however often it loops, where the outro takes over
+        if let Some(main_seconds) = self.main_seconds() {
+            args.extend(["-t".into(), format!("{:.3}", main_seconds)]);
+        }
+        // Looping restarts from the top of the input, so the seek only affects the first pass
+        match &self.concat_script {
+            Some(script) => {
+                if seek > 0.0 {
+                    args.extend(["-ss".into(), format!("{:.3}", seek)]);
+                }
+                args.extend([
+                    "-f".into(),
+                    "concat".into(),
+                    "-safe".into(),
+                    "0".into(),
+                    "-i".into(),
+                    script.to_string_lossy().into_owned(),
+                ]);
+            }
+            None => {
+                args.extend(trim_args(&self.main_trim, seek));
+                args.extend(["-i".into(), self.video_path.clone()]);
+            }
+        }
+
+        if let Some(outro) = &self.outro {
+            args.push("-re".into());
+            args.extend(trim_args(&outro.trim, 0.0));
+            args.extend(["-i".into(), outro.path.clone()]);
+        }
+
+        args
+    }
+
+    pub fn filter_args(&self) -> Vec<String> {
+        let mut video = Vec::new();
+        let mut audio = Vec::new();
+
+        if self.hold_at_end || self.outro.is_some() {
+            video.push(format!("tpad=stop_mode=clone:stop_duration={}", HOLD_SECONDS));
+            audio.push(format!("apad=pad_dur={}", HOLD_SECONDS));
+        }
+
+        if let Some(fade) = &self.fade_out {
+            video.push(format!(
+                "fade=t=out:st={:.3}:d={}",
+                fade.start_seconds, fade.duration_seconds
+            ));
+            audio.push(format!(
+                "afade=t=out:st={:.3}:d={}",
+                fade.start_seconds, fade.duration_seconds
+            ));
+        }
+
+        // Held silence has to come from somewhere too
+        if self.intro_path.is_some() || self.outro.is_some() || (self.hold_at_end && !self.has_audio) {
+            return self.concat_args(&video, &audio);
+        }
+
+        let mut args = Vec::new();
+        if !video.is_empty() {
+            args.extend(["-vf".into(), video.join(",")]);
+        }
+        if !audio.is_empty() {
+            args.extend(["-af".into(), audio.join(",")]);
+        }
+        args
+    }
+
+    /// Intro, main content and outro, whichever the plan has, in input order as one continuous output
+    fn concat_args(&self, video: &[String], audio: &[String]) -> Vec<String> {
+        let (width, height) = self.canvas.unwrap_or((1920, 1080));
+        let fit = format!(
+            "scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2,setsar=1,fps=30,format=yuv420p",
+            w = width,
+            h = height
+        );
+
+        // Each segment's sound, and the fade that ends it
+        let mut segments = Vec::new();
+        if self.intro_path.is_some() {
+            segments.push((self.intro_has_audio, None));
+        }
+        segments.push((self.has_audio, None));
+        if let Some(outro) = &self.outro {
+            segments.push((outro.has_audio, fade_at_end(outro.length_seconds, outro.fade_seconds)));
+        }
+
+        let last = segments.len() - 1;
+        let mut graph = String::new();
+        let mut pads = String::new();
+        for (input, (has_audio, fade)) in segments.iter().enumerate() {
+            let (video_fade, audio_fade) = match fade {
+                Some(fade) => (
+                    format!(",fade=t=out:st={:.3}:d={}", fade.start_seconds, fade.duration_seconds),
+                    format!(",afade=t=out:st={:.3}:d={}", fade.start_seconds, fade.duration_seconds),
+                ),
+                None => (String::new(), String::new()),
+            };
+            graph.push_str(&format!("[{input}:v]{fit}{video_fade}[v{input}];"));
+            let sound = match (has_audio, input == last) {
+                (true, _) => format!("[{input}:a]{AUDIO_FORMAT}"),
+                // Concat pads a short sound track with silence, except in the last segment
+                (false, false) => format!("{SILENCE},atrim=end=0.1"),
+                (false, true) => SILENCE.to_string(),
+            };
+            graph.push_str(&format!("{sound}{audio_fade}[a{input}];"));
+            pads.push_str(&format!("[v{input}][a{input}]"));
+        }
+        graph.push_str(&format!("{pads}concat=n={}:v=1:a=1[cv][ca]", segments.len()));
+
+        let video_chain = if video.is_empty() { "null".to_string() } else { video.join(",") };
+        let audio_chain = if audio.is_empty() { "anull".to_string() } else { audio.join(",") };
+        graph.push_str(&format!(";[cv]{}[v];[ca]{}[a]", video_chain, audio_chain));
+
+        let mut args = vec![
+            "-filter_complex".into(),
+            graph,
+            "-map".into(),
+            "[v]".into(),
+            "-map".into(),
+            "[a]".into(),
+        ];
+        // Silence never ends on its own, so the pictures decide when the output does
+        if !segments[last].0 {
+            args.push("-shortest".into());
+        }
+        args
+    }
+}
+
+/// Input seek and stop for a trimmed file, with `seek` measured from the trim start
+fn trim_args(trim: &MediaTrim, seek: f64) -> Vec<String> {
+    let mut args = Vec::new();
+    let start = trim.start() + seek;
+    if start > 0.0 {
+        args.extend(["-ss".into(), format!("{:.3}", start)]);
+    }
+    if let Some(end) = trim.end_seconds {
+        args.extend(["-to".into(), format!("{:.3}", end)]);
+    }
+    args
+}
+
+/// Fade covering the last seconds of something that plays for `length_seconds`
+pub fn fade_at_end(length_seconds: f64, fade_seconds: u32) -> Option<FadeOut> {
+    if fade_seconds == 0 {
+        return None;
+    }
+
+    Some(FadeOut {
+        start_seconds: (length_seconds - fade_seconds as f64).max(0.0),
+        duration_seconds: fade_seconds,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_looped_plan_has_no_filters() {
+        let plan = PlayoutPlan::main("/videos/main.mp4", None);
+        assert_eq!(
+            plan.input_args(),
+            vec!["-re", "-stream_loop", "-1", "-i", "/videos/main.mp4"]
+        );
+        assert!(plan.filter_args().is_empty());
+    }
+
+    #[test]
+    fn test_play_count_sets_repeats() {
+        let once = PlayoutPlan::main("/videos/main.mp4", Some(1));
+        assert_eq!(once.input_args(), vec!["-re", "-i", "/videos/main.mp4"]);
+
+        let thrice = PlayoutPlan::main("/videos/main.mp4", Some(3));
+        assert_eq!(
+            thrice.input_args(),
+            vec!["-re", "-stream_loop", "2", "-i", "/videos/main.mp4"]
+        );
+    }
+
+    #[test]
+    fn test_resume_seeks_within_file_and_keeps_play_budget() {
+        let plan = PlayoutPlan {
+            media_seconds: Some(100.0),
+            ..PlayoutPlan::main("/videos/main.mp4", Some(3))
+        }
+        .resume_at(250.0);
+
+        // Two full plays done, halfway through the third
+        assert_eq!(
+            plan.input_args(),
+            vec!["-re", "-ss", "50.000", "-i", "/videos/main.mp4"]
+        );
+        assert_eq!(plan.media_position(10.0), Some(260.0));
+    }
+
+    #[test]
+    fn test_position_excludes_intro() {
+        let plan = PlayoutPlan {
+            media_seconds: Some(100.0),
+            intro_seconds: 8.0,
+            ..PlayoutPlan::main("/videos/main.mp4", None)
+        };
+        assert_eq!(plan.media_position(5.0), Some(0.0));
+        assert_eq!(plan.media_position(20.0), Some(12.0));
+        assert_eq!(PlayoutPlan::outro(&outro()).media_position(5.0), None);
+    }
+
+    #[test]
+    fn test_single_play_trim_uses_input_seek() {
+        let plan = PlayoutPlan {
+            main_trim: MediaTrim { start_seconds: Some(300.0), end_seconds: Some(900.0) },
+            ..PlayoutPlan::main("/videos/main.mp4", Some(1))
+        };
+        assert!(!plan.needs_concat_script());
+        assert_eq!(
+            plan.input_args(),
+            vec!["-re", "-ss", "300.000", "-to", "900.000", "-i", "/videos/main.mp4"]
+        );
+    }
+
+    #[test]
+    fn test_looped_trim_goes_through_concat_script() {
+        let plan = PlayoutPlan {
+            main_trim: MediaTrim { start_seconds: Some(300.0), end_seconds: None },
+            media_seconds: Some(600.0),
+            ..PlayoutPlan::main("/videos/it's.mp4", None)
+        };
+        assert!(plan.needs_concat_script());
+        assert_eq!(
+            plan.concat_script_contents(),
+            "ffconcat version 1.0\nfile '/videos/it'\\''s.mp4'\ninpoint 300.000\n"
+        );
+
+        let dir = std::env::temp_dir();
+        let prepared = plan.prepare(&dir).unwrap();
+        let script = prepared.concat_script.clone().unwrap();
+        assert_eq!(std::fs::read_to_string(&script).unwrap(), plan.concat_script_contents());
+
+        let args = prepared.resume_at(10.0).input_args();
+        assert_eq!(&args[..6], &["-re", "-stream_loop", "-1", "-ss", "10.000", "-f"]);
+        assert_eq!(args.last().unwrap(), &script.to_string_lossy());
+        std::fs::remove_file(script).unwrap();
+    }
+
+    #[test]
+    fn test_intro_is_concatenated_before_main() {
+        let plan = PlayoutPlan {
+            intro_path: Some("/videos/intro.mp4".into()),
+            canvas: Some((1280, 720)),
+            ..PlayoutPlan::main("/videos/main.mp4", None)
+        };
+
+        assert_eq!(
+            plan.input_args(),
+            vec!["-re", "-i", "/videos/intro.mp4", "-re", "-stream_loop", "-1", "-i", "/videos/main.mp4"]
+        );
+
+        let args = plan.filter_args();
+        assert_eq!(args[0], "-filter_complex");
+        assert!(args[1].contains("scale=1280:720"));
+        assert!(args[1].contains("[v0][a0][v1][a1]concat=n=2:v=1:a=1[cv][ca]"));
+        assert!(args[1].ends_with("[cv]null[v];[ca]anull[a]"));
+    }
+
+    fn outro() -> OutroSegment {
+        OutroSegment {
+            path: "/videos/outro.mp4".into(),
+            trim: MediaTrim::default(),
+            length_seconds: 20.5,
+            fade_seconds: 3,
+            starts_at: 100.0,
+            has_audio: false,
+        }
+    }
+
+    #[test]
+    fn test_outro_follows_main_in_the_same_process() {
+        let plan = PlayoutPlan {
+            intro_path: Some("/videos/intro.mp4".into()),
+            intro_seconds: 10.0,
+            has_audio: false,
+            outro: Some(outro()),
+            media_seconds: Some(60.0),
+            ..PlayoutPlan::main("/videos/main.mp4", None)
+        };
+        assert!(plan.is_finite());
+        assert_eq!(
+            plan.input_args(),
+            vec![
+                "-re", "-i", "/videos/intro.mp4",
+                "-re", "-stream_loop", "-1", "-t", "90.000", "-i", "/videos/main.mp4",
+                "-re", "-i", "/videos/outro.mp4",
+            ]
+        );
+
+        // Clips without sound get silence, and the outro fades on its own timeline
+        let args = plan.filter_args();
+        assert!(args[1].contains("[1:v]scale="));
+        assert!(args[1].contains("anullsrc=r=44100:cl=stereo,atrim=end=0.1[a1];"));
+        assert!(args[1].contains(",fade=t=out:st=17.500:d=3[v2];anullsrc=r=44100:cl=stereo,afade=t=out:st=17.500:d=3[a2];"));
+        assert!(args[1].contains("concat=n=3:v=1:a=1[cv][ca];[cv]tpad=stop_mode=clone:stop_duration=10[v]"));
+        assert_eq!(args.last().unwrap(), "-shortest");
+
+        // The main content stops counting once the outro is on
+        assert_eq!(plan.media_position(50.0), Some(40.0));
+        assert_eq!(plan.media_position(110.0), Some(90.0));
+    }
+
+    #[test]
+    fn test_restart_brings_the_outro_closer() {
+        let plan = PlayoutPlan { outro: Some(outro()), ..PlayoutPlan::main("/videos/main.mp4", None) };
+
+        let restarted = plan.for_restart(40.0);
+        assert_eq!(restarted.outro.map(|o| o.starts_at), Some(60.0));
+
+        // Past the switch only the outro is left to play
+        let restarted = plan.for_restart(110.0);
+        assert_eq!(restarted.video_path, "/videos/outro.mp4");
+        assert!(restarted.hold_at_end && restarted.outro.is_none());
+    }
+
+    #[test]
+    fn test_outro_fades_and_holds() {
+        let plan = PlayoutPlan::outro(&OutroSegment { has_audio: true, ..outro() });
+        assert_eq!(plan.input_args(), vec!["-re", "-i", "/videos/outro.mp4"]);
+        assert_eq!(
+            plan.filter_args(),
+            vec![
+                "-vf",
+                "tpad=stop_mode=clone:stop_duration=10,fade=t=out:st=17.500:d=3",
+                "-af",
+                "apad=pad_dur=10,afade=t=out:st=17.500:d=3",
+            ]
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use std::path::Path;
+use std::process::Stdio;
+use tokio::process::Command;
+
+use crate::stream::process::ProcessError;
+
+/// Run ffprobe and return its trimmed stdout
+async fn run(ffprobe_path: &Path, args: &[&str], media_path: &str) -> Result<String, ProcessError> {
+    if !Path::new(media_path).exists() {
+        return Err(ProcessError::VideoNotFound(media_path.to_string()));
+    }
+
+    let output = Command::new(ffprobe_path)
+        .arg("-v").arg("error")
+        .args(args)
+        .arg("-of").arg("default=noprint_wrappers=1:nokey=1")
+        .arg(media_path)
+        .stdin(Stdio::null())
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ProcessError::Probe(format!("{}: {}", media_path, stderr.trim())));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Container duration in seconds
+pub async fn duration_seconds(ffprobe_path: &Path, media_path: &str) -> Result<f64, ProcessError> {
+    let out = run(ffprobe_path, &["-show_entries", "format=duration"], media_path).await?;
+    out.parse::<f64>()
+        .map_err(|_| ProcessError::Probe(format!("{}: no duration", media_path)))
+}
+
+/// Width and height of the first video stream
+pub async fn resolution(ffprobe_path: &Path, media_path: &str) -> Result<(u32, u32), ProcessError> {
+    let out = run(
+        ffprobe_path,
+        &["-select_streams", "v:0", "-show_entries", "stream=width,height"],
+        media_path,
+    ).await?;
+
+    let mut lines = out.lines().map(|l| l.trim().parse::<u32>());
+    match (lines.next(), lines.next()) {
+        (Some(Ok(width)), Some(Ok(height))) => Ok((width, height)),
+        _ => Err(ProcessError::Probe(format!("{}: no video stream", media_path))),
+    }
+}
+
+/// Whether the file has a sound track
+pub async fn has_audio(ffprobe_path: &Path, media_path: &str) -> Result<bool, ProcessError> {
+    let out = run(ffprobe_path, &["-select_streams", "a", "-show_entries", "stream=index"], media_path).await?;
+    Ok(!out.is_empty())
+}
@@ -1,10 +1,20 @@
+use std::collections::VecDeque;
 use std::path::Path;
 use std::process::Stdio;
-use std::time::Instant;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::{Child, Command};
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::task::JoinHandle;
 
 use thiserror::Error;
 
+use crate::stream::playout::PlayoutPlan;
+use crate::stream::redact;
+use crate::stream::types::EncoderStats;
+
 #[derive(Error, Debug)]
 pub enum ProcessError {
     #[error("Failed to spawn FFmpeg: {0}")]
@@ -13,51 +23,245 @@ pub enum ProcessError {
     Exit(String),
     #[error("Video file not found: {0}")]
     VideoNotFound(String),
+    #[error("FFprobe failed: {0}")]
+    Probe(String),
+    #[error("FFmpeg did not exit within {0}s of being killed")]
+    StopTimeout(u64),
+}
+
+/// How an FFmpeg process that nobody asked to stop has ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessExit {
+    /// A finite play plan reached the end of its input
+    EndOfInput,
+    /// Anything else: publish rejected, connection dropped, crash
+    Crashed(Option<i32>),
+    /// Killed on request through `stop` or by dropping the handle
+    Stopped,
+}
+
+impl std::fmt::Display for ProcessExit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EndOfInput => write!(f, "FFmpeg reached the end of its input"),
+            Self::Crashed(Some(code)) => write!(f, "FFmpeg exited with code {}", code),
+            Self::Crashed(None) => write!(f, "FFmpeg was terminated"),
+            Self::Stopped => write!(f, "FFmpeg was stopped"),
+        }
+    }
+}
+
+/// Sent by a process's wait task the moment FFmpeg exits
+#[derive(Debug, Clone)]
+pub struct ExitReport {
+    pub stream_id: String,
+    /// Tells a report from a replaced process apart from one about the current process
+    pub process_id: u64,
+    pub exit: ProcessExit,
+}
+
+static NEXT_PROCESS_ID: AtomicU64 = AtomicU64::new(1);
+
+/// How long `stop` waits for FFmpeg to go away after killing it
+const STOP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// FFmpeg log lines kept for the session history
+const LOG_TAIL_LINES: usize = 20;
+
+/// How long an exit waits for the last of FFmpeg's output to be read
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// What the output readers collect while FFmpeg runs
+#[derive(Default)]
+struct ProcessOutput {
+    stats: EncoderStats,
+    log_tail: VecDeque<String>,
 }
 
 pub struct FFmpegProcess {
-    child: Child,
+    id: u64,
+    pid: Option<u32>,
+    /// Dropping or firing this makes the wait task kill FFmpeg
+    kill: Option<oneshot::Sender<()>>,
+    exited: watch::Receiver<Option<ProcessExit>>,
     started_at: Instant,
+    /// Live time of earlier processes of the same run, before automatic restarts
+    live_before: Duration,
+    plan: PlayoutPlan,
+    /// Output timeline position in microseconds, from `-progress`
+    out_time_us: Arc<AtomicU64>,
+    output: Arc<Mutex<ProcessOutput>>,
 }
 
 impl FFmpegProcess {
+    /// Spawn FFmpeg and a task that owns the child, reporting its exit on `exits`
     pub async fn start(
         ffmpeg_path: &Path,
-        video_path: &str,
-        stream_key: &str,
+        plan: &PlayoutPlan,
+        rtmp_url: &str,
+        stream_id: &str,
+        exits: &mpsc::UnboundedSender<ExitReport>,
     ) -> Result<Self, ProcessError> {
-        // Validate video file exists
-        if !Path::new(video_path).exists() {
-            return Err(ProcessError::VideoNotFound(video_path.to_string()));
+        // Validate video files exist
+        let outro_path = plan.outro.as_ref().map(|outro| &outro.path);
+        for path in plan.intro_path.iter().chain(std::iter::once(&plan.video_path)).chain(outro_path) {
+            if !Path::new(path).exists() {
+                return Err(ProcessError::VideoNotFound(path.to_string()));
+            }
         }
 
-        let rtmp_url = format!("rtmp://a.rtmp.youtube.com/live2/{}", stream_key);
-        
-        tracing::info!("Starting FFmpeg stream: {} -> YouTube", video_path);
+        tracing::info!("Starting FFmpeg stream: {} -> YouTube", plan.video_path);
+
+        let plan = plan.prepare(&std::env::temp_dir())?;
 
         // Try hardware encoding first, fallback to software
-        let child = Self::try_hardware_encoding(ffmpeg_path, video_path, &rtmp_url).await
-            .or_else(|_| Self::start_software(ffmpeg_path, video_path, &rtmp_url))?;
+        let spawned = Self::try_hardware_encoding(ffmpeg_path, &plan, rtmp_url).await
+            .or_else(|_| Self::start_software(ffmpeg_path, &plan, rtmp_url));
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(e) => {
+                remove_concat_script(&plan);
+                return Err(e);
+            }
+        };
+
+        let out_time_us = Arc::new(AtomicU64::new(0));
+        let output = Arc::new(Mutex::new(ProcessOutput::default()));
+        let readers = Self::spawn_output_readers(&mut child, out_time_us.clone(), output.clone());
+
+        let id = NEXT_PROCESS_ID.fetch_add(1, Ordering::Relaxed);
+        let pid = child.id();
+        let (kill_tx, kill_rx) = oneshot::channel();
+        let (exited_tx, exited) = watch::channel(None);
+
+        let report = ExitReport {
+            stream_id: stream_id.to_string(),
+            process_id: id,
+            exit: ProcessExit::Stopped,
+        };
+        tokio::spawn(Self::wait_for_exit(child, plan.clone(), readers, kill_rx, exited_tx, report, exits.clone()));
 
         Ok(Self {
-            child,
+            id,
+            pid,
+            kill: Some(kill_tx),
+            exited,
             started_at: Instant::now(),
+            live_before: Duration::ZERO,
+            plan,
+            out_time_us,
+            output,
         })
     }
 
+    /// Own the child until it exits, killing it when asked, then publish how it ended
+    async fn wait_for_exit(
+        mut child: Child,
+        plan: PlayoutPlan,
+        readers: Vec<JoinHandle<()>>,
+        kill: oneshot::Receiver<()>,
+        exited: watch::Sender<Option<ProcessExit>>,
+        mut report: ExitReport,
+        exits: mpsc::UnboundedSender<ExitReport>,
+    ) {
+        let exit = tokio::select! {
+            status = child.wait() => match status {
+                // A plan that loops forever has no end of input to reach
+                Ok(status) if status.success() && plan.is_finite() => ProcessExit::EndOfInput,
+                Ok(status) => ProcessExit::Crashed(status.code()),
+                Err(_) => ProcessExit::Crashed(None),
+            },
+            // Fires on request and when the handle is dropped
+            _ = kill => {
+                if let Err(e) = child.start_kill() {
+                    tracing::warn!("Kill failed: {}", e);
+                }
+                if let Ok(status) = child.wait().await {
+                    tracing::info!("FFmpeg exited with status: {}", status);
+                }
+                ProcessExit::Stopped
+            }
+        };
+
+        // FFmpeg is gone, so nothing is reading the script any more
+        remove_concat_script(&plan);
+
+        // Let the readers catch the last log lines - they usually say why FFmpeg died
+        let drained = async {
+            for reader in readers {
+                let _ = reader.await;
+            }
+        };
+        if tokio::time::timeout(OUTPUT_DRAIN_TIMEOUT, drained).await.is_err() {
+            tracing::warn!("FFmpeg output was still open {}s after exit", OUTPUT_DRAIN_TIMEOUT.as_secs());
+        }
+
+        let _ = exited.send(Some(exit));
+        report.exit = exit;
+        let _ = exits.send(report);
+    }
+
+    /// Follow `-progress` on stdout and keep the tail of the log on stderr,
+    /// reading both to the end so FFmpeg never blocks on a full pipe
+    fn spawn_output_readers(
+        child: &mut Child,
+        out_time_us: Arc<AtomicU64>,
+        output: Arc<Mutex<ProcessOutput>>,
+    ) -> Vec<JoinHandle<()>> {
+        let mut readers = Vec::new();
+
+        if let Some(stdout) = child.stdout.take() {
+            let output = output.clone();
+            readers.push(tokio::spawn(async move {
+                let mut lines = BufReader::new(stdout).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    if let Some(us) = parse_out_time_us(&line) {
+                        out_time_us.store(us, Ordering::Relaxed);
+                    }
+                    let mut output = output.lock().unwrap_or_else(PoisonError::into_inner);
+                    apply_progress_line(&mut output.stats, &line);
+                }
+            }));
+        }
+
+        if let Some(stderr) = child.stderr.take() {
+            readers.push(tokio::spawn(async move {
+                let mut reader = BufReader::new(stderr);
+                let mut buf = Vec::new();
+                // Lines are read as bytes - a file name in another encoding must not end the drain
+                while let Ok(n) = reader.read_until(b'\n', &mut buf).await {
+                    if n == 0 {
+                        break;
+                    }
+                    // The publish URL in FFmpeg's errors carries the stream key
+                    let line = redact::redact_publish_urls(String::from_utf8_lossy(&buf).trim_end());
+                    buf.clear();
+                    if line.is_empty() {
+                        continue;
+                    }
+                    let mut output = output.lock().unwrap_or_else(PoisonError::into_inner);
+                    if output.log_tail.len() == LOG_TAIL_LINES {
+                        output.log_tail.pop_front();
+                    }
+                    output.log_tail.push_back(line);
+                }
+            }));
+        }
+
+        readers
+    }
+
     #[cfg(target_os = "windows")]
     async fn try_hardware_encoding(
         ffmpeg_path: &Path,
-        video_path: &str,
+        plan: &PlayoutPlan,
         rtmp_url: &str,
     ) -> Result<Child, ProcessError> {
         tracing::info!("Trying NVIDIA NVENC hardware encoding...");
         
         // Windows: Try NVENC (NVIDIA GPU) first
         let result = Command::new(ffmpeg_path)
-            .arg("-re")
-            .arg("-stream_loop").arg("-1")
-            .arg("-i").arg(video_path)
+            .args(plan.input_args())
             
             // NVIDIA NVENC encoder
             .arg("-c:v").arg("h264_nvenc")
@@ -76,6 +280,8 @@ impl FFmpegProcess {
             .arg("-profile:v").arg("high")
             .arg("-pix_fmt").arg("yuv420p")
             
+            .args(plan.filter_args())
+            
             .arg("-c:a").arg("aac")
             .arg("-b:a").arg("128k")
             .arg("-ar").arg("44100")
@@ -86,13 +292,14 @@ impl FFmpegProcess {
             .arg(rtmp_url)
             
             .arg("-loglevel").arg("warning")
-            .arg("-stats")
+            .arg("-nostats")
+            .arg("-progress").arg("pipe:1")   // key=value progress on stdout
             
             .stdin(Stdio::null())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             
-            .spawn();
+            .spawn_logged();
 
         match result {
             Ok(child) => {
@@ -101,7 +308,7 @@ impl FFmpegProcess {
             }
             Err(_) => {
                 tracing::warn!("NVENC not available, trying Intel QuickSync...");
-                Self::try_qsv_encoding(ffmpeg_path, video_path, rtmp_url).await
+                Self::try_qsv_encoding(ffmpeg_path, plan, rtmp_url).await
             }
         }
     }
@@ -109,14 +316,12 @@ impl FFmpegProcess {
     #[cfg(target_os = "windows")]
     async fn try_qsv_encoding(
         ffmpeg_path: &Path,
-        video_path: &str,
+        plan: &PlayoutPlan,
         rtmp_url: &str,
     ) -> Result<Child, ProcessError> {
         // Windows: Try Intel QuickSync
         Command::new(ffmpeg_path)
-            .arg("-re")
-            .arg("-stream_loop").arg("-1")
-            .arg("-i").arg(video_path)
+            .args(plan.input_args())
             
             // Intel QuickSync encoder
             .arg("-c:v").arg("h264_qsv")
@@ -132,6 +337,8 @@ impl FFmpegProcess {
             .arg("-profile:v").arg("high")
             .arg("-pix_fmt").arg("yuv420p")
             
+            .args(plan.filter_args())
+            
             .arg("-c:a").arg("aac")
             .arg("-b:a").arg("128k")
             .arg("-ar").arg("44100")
@@ -142,29 +349,28 @@ impl FFmpegProcess {
             .arg(rtmp_url)
             
             .arg("-loglevel").arg("warning")
-            .arg("-stats")
+            .arg("-nostats")
+            .arg("-progress").arg("pipe:1")   // key=value progress on stdout
             
             .stdin(Stdio::null())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             
-            .spawn()
+            .spawn_logged()
             .map_err(ProcessError::from)
     }
 
     #[cfg(target_os = "macos")]
     async fn try_hardware_encoding(
         ffmpeg_path: &Path,
-        video_path: &str,
+        plan: &PlayoutPlan,
         rtmp_url: &str,
     ) -> Result<Child, ProcessError> {
         tracing::info!("Trying VideoToolbox hardware encoding...");
         
         // macOS: Use VideoToolbox
         Command::new(ffmpeg_path)
-            .arg("-re")
-            .arg("-stream_loop").arg("-1")
-            .arg("-i").arg(video_path)
+            .args(plan.input_args())
             
             .arg("-c:v").arg("h264_videotoolbox")
             
@@ -178,6 +384,8 @@ impl FFmpegProcess {
             .arg("-profile:v").arg("high")
             .arg("-pix_fmt").arg("yuv420p")
             
+            .args(plan.filter_args())
+            
             .arg("-c:a").arg("aac")
             .arg("-b:a").arg("128k")
             .arg("-ar").arg("44100")
@@ -188,20 +396,21 @@ impl FFmpegProcess {
             .arg(rtmp_url)
             
             .arg("-loglevel").arg("warning")
-            .arg("-stats")
+            .arg("-nostats")
+            .arg("-progress").arg("pipe:1")   // key=value progress on stdout
             
             .stdin(Stdio::null())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             
-            .spawn()
+            .spawn_logged()
             .map_err(ProcessError::from)
     }
 
     #[cfg(not(any(target_os = "windows", target_os = "macos")))]
     async fn try_hardware_encoding(
         _ffmpeg_path: &Path,
-        _video_path: &str,
+        _plan: &PlayoutPlan,
         _rtmp_url: &str,
     ) -> Result<Child, ProcessError> {
         // Linux: Skip to software encoding
@@ -210,15 +419,13 @@ impl FFmpegProcess {
 
     fn start_software(
         ffmpeg_path: &Path,
-        video_path: &str,
+        plan: &PlayoutPlan,
         rtmp_url: &str,
     ) -> Result<Child, ProcessError> {
         tracing::info!("Using software encoding (libx264)...");
         
         Command::new(ffmpeg_path)
-            .arg("-re")
-            .arg("-stream_loop").arg("-1")
-            .arg("-i").arg(video_path)
+            .args(plan.input_args())
             
             // Software encoding - optimized for speed
             .arg("-c:v").arg("libx264")
@@ -237,6 +444,8 @@ impl FFmpegProcess {
             .arg("-profile:v").arg("main")
             .arg("-pix_fmt").arg("yuv420p")
             
+            .args(plan.filter_args())
+            
             .arg("-c:a").arg("aac")
             .arg("-b:a").arg("128k")
             .arg("-ar").arg("44100")
@@ -247,70 +456,243 @@ impl FFmpegProcess {
             .arg(rtmp_url)
             
             .arg("-loglevel").arg("warning")
-            .arg("-stats")
+            .arg("-nostats")
+            .arg("-progress").arg("pipe:1")   // key=value progress on stdout
             
             .stdin(Stdio::null())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             
-            .spawn()
+            .spawn_logged()
             .map_err(ProcessError::from)
     }
 
+    /// Uptime of this process
     pub fn elapsed_seconds(&self) -> u64 {
         self.started_at.elapsed().as_secs()
     }
 
+    /// Time the run has been live, across automatic restarts
+    pub fn live_time(&self) -> Duration {
+        self.live_before + self.started_at.elapsed()
+    }
+
+    pub fn plan(&self) -> &PlayoutPlan {
+        &self.plan
+    }
+
+    /// Seconds of output FFmpeg has produced so far
+    pub fn output_seconds(&self) -> f64 {
+        self.out_time_us.load(Ordering::Relaxed) as f64 / 1_000_000.0
+    }
+
+    /// Media seconds consumed by the main content, counting from the start of the run
+    pub fn media_position(&self) -> Option<f64> {
+        self.plan.media_position(self.output_seconds())
+    }
+
+    /// Continue a run whose previous process died after being live for `live_before`
+    pub fn resume_live_time(&mut self, live_before: Duration) {
+        self.live_before = live_before;
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Latest encoder counters reported through `-progress`
+    pub fn encoder_stats(&self) -> EncoderStats {
+        self.output.lock().unwrap_or_else(PoisonError::into_inner).stats.clone()
+    }
+
+    /// The last lines FFmpeg logged, oldest first
+    pub fn log_tail(&self) -> Vec<String> {
+        self.output.lock().unwrap_or_else(PoisonError::into_inner).log_tail.iter().cloned().collect()
+    }
+
     pub async fn stop(&mut self) -> Result<(), ProcessError> {
         tracing::info!("Stopping FFmpeg process...");
-        
-        // Get process ID for logging
-        let pid = self.child.id();
-        tracing::info!("FFmpeg PID: {:?}", pid);
-        
-        // First, try to kill the process
-        if let Err(e) = self.child.kill().await {
-            tracing::warn!("First kill attempt failed: {}", e);
+        tracing::info!("FFmpeg PID: {:?}", self.pid);
+
+        if let Some(kill) = self.kill.take() {
+            let _ = kill.send(());
         }
-        
-        // Wait for process to exit with timeout
-        let wait_result = tokio::time::timeout(
-            std::time::Duration::from_secs(3),
-            self.child.wait()
-        ).await;
-        
-        match wait_result {
-            Ok(Ok(status)) => {
-                tracing::info!("FFmpeg exited with status: {}", status);
-            }
-            Ok(Err(e)) => {
-                tracing::warn!("Wait error: {}", e);
-            }
-            Err(_) => {
-                // Timeout - force kill again
-                tracing::warn!("Timeout waiting for FFmpeg, force killing...");
-                let _ = self.child.kill().await;
-                let _ = self.child.wait().await;
-            }
+
+        // The wait task keeps killing it, but the caller must not assume it is gone
+        if self.exited_within(STOP_TIMEOUT).await.is_none() {
+            return Err(ProcessError::StopTimeout(STOP_TIMEOUT.as_secs()));
         }
-        
+
         tracing::info!("FFmpeg process stopped");
         Ok(())
     }
 
-    pub fn is_running(&mut self) -> bool {
-        match self.child.try_wait() {
-            Ok(None) => true,
-            _ => false,
+    /// How the process ended, or None while it is still running
+    pub fn exit(&self) -> Option<ProcessExit> {
+        *self.exited.borrow()
+    }
+
+    /// Wait up to `timeout` for the process to exit
+    pub async fn exited_within(&self, timeout: Duration) -> Option<ProcessExit> {
+        let mut exited = self.exited.clone();
+        let result = tokio::time::timeout(timeout, exited.wait_for(Option::is_some)).await;
+        match result {
+            Ok(Ok(exit)) => *exit,
+            // The wait task always publishes before going away
+            Ok(Err(_)) => self.exit(),
+            Err(_) => None,
         }
     }
 }
 
+/// Parse an `out_time_us=` (or the older, misnamed `out_time_ms=`) progress line
+fn parse_out_time_us(line: &str) -> Option<u64> {
+    let (key, value) = line.trim().split_once('=')?;
+    match key {
+        "out_time_us" | "out_time_ms" => value.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Fold one `-progress` line into the encoder counters. `N/A` values leave them unchanged.
+fn apply_progress_line(stats: &mut EncoderStats, line: &str) {
+    let Some((key, value)) = line.trim().split_once('=') else {
+        return;
+    };
+    let value = value.trim();
+    match key {
+        "frame" => stats.frames = value.parse().unwrap_or(stats.frames),
+        "fps" => stats.fps = value.parse().ok().or(stats.fps),
+        "bitrate" => {
+            stats.bitrate_kbps = value.trim_end_matches("kbits/s").parse().ok().or(stats.bitrate_kbps);
+        }
+        "total_size" => stats.total_size_bytes = value.parse().unwrap_or(stats.total_size_bytes),
+        "drop_frames" => stats.dropped_frames = value.parse().unwrap_or(stats.dropped_frames),
+        "dup_frames" => stats.duplicated_frames = value.parse().unwrap_or(stats.duplicated_frames),
+        "speed" => stats.speed = value.trim_end_matches('x').parse().ok().or(stats.speed),
+        _ => {}
+    }
+}
+
 impl Drop for FFmpegProcess {
     fn drop(&mut self) {
-        if let Ok(None) = self.child.try_wait() {
+        // Dropping the kill sender wakes the wait task, which kills FFmpeg
+        if self.kill.is_some() && self.exit().is_none() {
             tracing::info!("Killing FFmpeg on drop");
-            let _ = self.child.start_kill();
         }
     }
 }
+
+fn remove_concat_script(plan: &PlayoutPlan) {
+    if let Some(script) = &plan.concat_script {
+        let _ = std::fs::remove_file(script);
+    }
+}
+
+trait SpawnLogged {
+    fn spawn_logged(&mut self) -> std::io::Result<Child>;
+}
+
+impl SpawnLogged for Command {
+    /// Log the command line, with the stream key masked, then spawn it
+    fn spawn_logged(&mut self) -> std::io::Result<Child> {
+        let command = self.as_std();
+        let preview = std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| arg.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ");
+        tracing::info!("FFmpeg command: {}", redact::redact_publish_urls(&preview));
+        self.spawn()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_out_time() {
+        assert_eq!(parse_out_time_us("out_time_us=12500000"), Some(12_500_000));
+        assert_eq!(parse_out_time_us("out_time_ms=12500000\n"), Some(12_500_000));
+        assert_eq!(parse_out_time_us("out_time_us=N/A"), None);
+        assert_eq!(parse_out_time_us("out_time=00:00:12.500000"), None);
+        assert_eq!(parse_out_time_us("progress=continue"), None);
+    }
+
+    #[test]
+    fn test_progress_updates_encoder_stats() {
+        let mut stats = EncoderStats::default();
+        let block = "frame=1500\nfps=30.00\nbitrate=2500.5kbits/s\ntotal_size=31250000\n\
+                     drop_frames=3\ndup_frames=1\nspeed=1.01x\nprogress=continue";
+        for line in block.lines() {
+            apply_progress_line(&mut stats, line);
+        }
+        assert_eq!(stats.frames, 1500);
+        assert_eq!(stats.fps, Some(30.0));
+        assert_eq!(stats.bitrate_kbps, Some(2500.5));
+        assert_eq!(stats.total_size_bytes, 31_250_000);
+        assert_eq!(stats.dropped_frames, 3);
+        assert_eq!(stats.duplicated_frames, 1);
+        assert_eq!(stats.speed, Some(1.01));
+
+        // Unknown values early in a run keep what was already known
+        apply_progress_line(&mut stats, "bitrate=N/A");
+        apply_progress_line(&mut stats, "speed=N/A");
+        assert_eq!(stats.bitrate_kbps, Some(2500.5));
+        assert_eq!(stats.speed, Some(1.01));
+    }
+
+    /// Start a stand-in for FFmpeg that ignores its arguments and runs `body`
+    #[cfg(unix)]
+    async fn run(body: &str, play_count: Option<u32>) -> (FFmpegProcess, mpsc::UnboundedReceiver<ExitReport>) {
+        use std::os::unix::fs::PermissionsExt;
+
+        let ffmpeg = std::env::temp_dir().join(format!("ylm-ffmpeg-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&ffmpeg, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        std::fs::set_permissions(&ffmpeg, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let (exits, reports) = mpsc::unbounded_channel();
+        let plan = PlayoutPlan::main(file!(), play_count);
+        let process = FFmpegProcess::start(&ffmpeg, &plan, "rtmp://127.0.0.1/live2/x", "lofi", &exits).await.unwrap();
+        (process, reports)
+    }
+
+    /// The next exit reported, without polling for it
+    #[cfg(unix)]
+    async fn next_exit(reports: &mut mpsc::UnboundedReceiver<ExitReport>) -> ExitReport {
+        tokio::time::timeout(Duration::from_secs(5), reports.recv()).await.unwrap().unwrap()
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_exits_are_reported_as_they_happen() {
+        // A finite plan that ends cleanly has reached the end of its input
+        let (process, mut reports) = run("exit 0", Some(1)).await;
+        let report = next_exit(&mut reports).await;
+        assert_eq!((report.stream_id.as_str(), report.process_id), ("lofi", process.id()));
+        assert_eq!(report.exit, ProcessExit::EndOfInput);
+        assert_eq!(process.exited_within(Duration::from_secs(1)).await, Some(ProcessExit::EndOfInput));
+
+        // A looping plan has no end, so even a clean exit is a crash
+        let (_process, mut reports) = run("exit 0", None).await;
+        assert_eq!(next_exit(&mut reports).await.exit, ProcessExit::Crashed(Some(0)));
+        let (_process, mut reports) = run("exit 3", Some(1)).await;
+        assert_eq!(next_exit(&mut reports).await.exit, ProcessExit::Crashed(Some(3)));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_killed_processes_report_stopped() {
+        let (mut process, mut reports) = run("exec sleep 60", None).await;
+        assert_eq!(process.exited_within(Duration::from_millis(200)).await, None);
+        process.stop().await.unwrap();
+        assert_eq!(process.exit(), Some(ProcessExit::Stopped));
+        assert_eq!(next_exit(&mut reports).await.exit, ProcessExit::Stopped);
+
+        // Dropping the handle kills FFmpeg as well
+        let (process, mut reports) = run("exec sleep 60", None).await;
+        drop(process);
+        assert_eq!(next_exit(&mut reports).await.exit, ProcessExit::Stopped);
+    }
+}
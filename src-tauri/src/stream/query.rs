@@ -0,0 +1,209 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{self, Params};
+use crate::stream::edit;
+use crate::stream::types::{ScheduleType, Stream, StreamStatus};
+use crate::stream::validation::{ErrorCode, FieldError};
+
+/// Largest page `get_streams` returns
+pub const MAX_PAGE_SIZE: u32 = 500;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StreamSort {
+    #[default]
+    CreatedAt,
+    Name,
+    StartedAt, // Never-started streams sort first
+    Status,
+}
+
+impl StreamSort {
+    /// SQL the streams are ordered by - each has a matching index
+    pub fn expression(&self) -> &'static str {
+        match self {
+            Self::CreatedAt => "s.created_at",
+            Self::Name => "s.name COLLATE NOCASE",
+            Self::StartedAt => "COALESCE(s.started_at, '')",
+            Self::Status => "s.status",
+        }
+    }
+
+    fn value(&self, stream: &Stream) -> String {
+        match self {
+            Self::CreatedAt => stream.created_at.clone(),
+            Self::Name => stream.name.clone(),
+            Self::StartedAt => stream.started_at.clone().unwrap_or_default(),
+            Self::Status => stream.status.as_str().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Which streams `get_streams` returns, in what order, and which page of them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StreamQuery {
+    pub statuses: Vec<StreamStatus>, // Any of these, empty = all
+    pub search: Option<String>,      // Stream or channel name, a key's last characters, or a whole key
+    pub tags: Vec<String>,           // Carrying any of these
+    pub schedule_types: Vec<ScheduleType>,
+    pub archived: bool, // The archive instead of the streams in use
+    pub created_from: Option<String>, // RFC 3339, inclusive
+    pub created_to: Option<String>,   // RFC 3339, exclusive
+    pub started_from: Option<String>,
+    pub started_to: Option<String>,
+    pub sort: StreamSort,
+    pub direction: SortDirection,
+    pub limit: Option<u32>,     // None = every match
+    pub cursor: Option<String>, // `nextCursor` of the previous page
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamPage {
+    pub streams: Vec<Stream>,
+    pub total: u64,                  // Matches across all pages
+    pub next_cursor: Option<String>, // None on the last page
+}
+
+/// Where a page ends: the sort value and id of its last stream. The id breaks ties. The order
+/// the page was in comes along, as the value means nothing in another one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cursor {
+    pub sort: StreamSort,
+    pub direction: SortDirection,
+    pub value: String,
+    pub id: String,
+}
+
+impl Cursor {
+    pub fn after(stream: &Stream, sort: StreamSort, direction: SortDirection) -> Self {
+        Self { sort, direction, value: sort.value(stream), id: stream.id.clone() }
+    }
+
+    pub fn encode(&self) -> String {
+        BASE64.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        serde_json::from_slice(&BASE64.decode(cursor).ok()?).ok()
+    }
+}
+
+impl StreamQuery {
+    /// Check the query, rewriting date bounds as UTC RFC 3339 like stored times and capping the page size
+    pub fn normalize(mut self) -> Result<(Self, Option<Cursor>), Vec<FieldError>> {
+        let mut errors = Vec::new();
+
+        let bounds = [
+            ("createdFrom", &mut self.created_from),
+            ("createdTo", &mut self.created_to),
+            ("startedFrom", &mut self.started_from),
+            ("startedTo", &mut self.started_to),
+        ];
+        for (field, bound) in bounds {
+            let Some(value) = bound.as_deref() else {
+                continue;
+            };
+            match DateTime::parse_from_rfc3339(value) {
+                Ok(time) => *bound = Some(time.with_timezone(&Utc).to_rfc3339()),
+                Err(_) => errors.push(FieldError::new(
+                    field,
+                    ErrorCode::InvalidDateTime,
+                    "field.invalidDateTime",
+                    i18n::params([("datetime", value.to_string())]),
+                )),
+            }
+        }
+
+        let cursor = match self.cursor.as_deref().map(|c| (c, Cursor::decode(c))) {
+            None => None,
+            Some((_, Some(cursor))) if cursor.sort == self.sort && cursor.direction == self.direction => Some(cursor),
+            Some((_, Some(_))) => {
+                errors.push(FieldError::new("cursor", ErrorCode::InvalidValue, "field.cursorMismatch", Params::new()));
+                None
+            }
+            Some((value, None)) => {
+                errors.push(FieldError::new(
+                    "cursor",
+                    ErrorCode::InvalidValue,
+                    "field.invalidValue",
+                    i18n::params([("value", value.to_string())]),
+                ));
+                None
+            }
+        };
+        self.tags = edit::normalize_tags(&self.tags);
+        if self.limit == Some(0) {
+            errors.push(FieldError::new("limit", ErrorCode::OutOfRange, "field.limitZero", Params::new()));
+        }
+        self.limit = self.limit.map(|limit| limit.min(MAX_PAGE_SIZE));
+
+        if errors.is_empty() { Ok((self, cursor)) } else { Err(errors) }
+    }
+
+    /// The search text, if there is any
+    pub fn search_text(&self) -> Option<&str> {
+        self.search.as_deref().map(str::trim).filter(|s| !s.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trips() {
+        let cursor = Cursor {
+            sort: StreamSort::Name,
+            direction: SortDirection::Asc,
+            value: "Lofi, 24/7\0 radio".into(),
+            id: "abc".into(),
+        };
+        assert_eq!(Cursor::decode(&cursor.encode()), Some(cursor));
+        assert_eq!(Cursor::decode("not a cursor!"), None);
+    }
+
+    #[test]
+    fn test_cursor_from_another_order_is_rejected() {
+        let cursor = Cursor { sort: StreamSort::Name, direction: SortDirection::Asc, value: "Lofi".into(), id: "abc".into() };
+        let page = |sort, direction| StreamQuery { sort, direction, cursor: Some(cursor.encode()), ..Default::default() };
+
+        assert_eq!(page(StreamSort::Name, SortDirection::Asc).normalize().unwrap().1, Some(cursor.clone()));
+        for (sort, direction) in [(StreamSort::Name, SortDirection::Desc), (StreamSort::CreatedAt, SortDirection::Asc)] {
+            let errors = page(sort, direction).normalize().unwrap_err();
+            assert_eq!(errors[0].field, "cursor");
+            assert_eq!(errors[0].key, "field.cursorMismatch");
+        }
+    }
+
+    #[test]
+    fn test_normalize_rewrites_bounds_and_caps_the_page() {
+        let query = StreamQuery {
+            created_from: Some("2024-05-07T17:00:00+07:00".into()),
+            limit: Some(10_000),
+            ..Default::default()
+        };
+        let (query, cursor) = query.normalize().unwrap();
+        assert_eq!(query.created_from.as_deref(), Some("2024-05-07T10:00:00+00:00"));
+        assert_eq!(query.limit, Some(MAX_PAGE_SIZE));
+        assert_eq!(cursor, None);
+
+        let errors = StreamQuery { started_to: Some("yesterday".into()), cursor: Some("%%".into()), ..Default::default() }
+            .normalize()
+            .unwrap_err();
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["startedTo", "cursor"]);
+    }
+}
@@ -0,0 +1,78 @@
+/// Shown in place of a stream key: the last 4 characters, enough to tell keys apart
+pub fn mask_key(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() <= 8 {
+        return "••••".to_string();
+    }
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("••••{}", tail)
+}
+
+/// Mask the stream key at the end of every RTMP publish URL in `text` - FFmpeg command
+/// lines and FFmpeg's own log lines name the URL it publishes to.
+pub fn redact_publish_urls(text: &str) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = [rest.find("rtmp://"), rest.find("rtmps://")].into_iter().flatten().min() {
+        let url_len = rest[start..]
+            .find(|c: char| c.is_whitespace() || c == '\'' || c == '"')
+            .unwrap_or(rest.len() - start);
+        let url = &rest[start..start + url_len];
+
+        redacted.push_str(&rest[..start]);
+        redacted.push_str(&redact_url(url));
+        rest = &rest[start + url_len..];
+    }
+
+    redacted.push_str(rest);
+    redacted
+}
+
+/// `rtmp://host/app/key` -> `rtmp://host/app/••••` plus the key's tail.
+/// A URL with no path past the host has no key to hide.
+fn redact_url(url: &str) -> String {
+    let scheme_end = url.find("://").map_or(0, |i| i + 3);
+    let Some(host_end) = url[scheme_end..].find('/').map(|i| scheme_end + i) else {
+        return url.to_string();
+    };
+    let Some(key_start) = url[host_end + 1..].rfind('/').map(|i| host_end + 2 + i) else {
+        return url.to_string();
+    };
+
+    // FFmpeg follows the URL with `: <error>` - keep anything past the key
+    let key_len = url[key_start..]
+        .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+        .unwrap_or(url.len() - key_start);
+    if key_len == 0 {
+        return url.to_string();
+    }
+
+    format!("{}{}{}", &url[..key_start], mask_key(&url[key_start..key_start + key_len]), &url[key_start + key_len..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_keeps_only_the_tail() {
+        assert_eq!(mask_key("abcd-efgh-ijkl-mnop"), "••••mnop");
+        assert_eq!(mask_key("short"), "••••");
+    }
+
+    #[test]
+    fn test_publish_urls_are_redacted() {
+        let line = "rtmp://a.rtmp.youtube.com/live2/abcd-efgh-ijkl-mnop: I/O error";
+        assert_eq!(redact_publish_urls(line), "rtmp://a.rtmp.youtube.com/live2/••••mnop: I/O error");
+
+        let command = "ffmpeg -f flv rtmps://b.rtmp.youtube.com/live2?backup=1/abcd-efgh-ijkl-mnop -loglevel warning";
+        assert_eq!(
+            redact_publish_urls(command),
+            "ffmpeg -f flv rtmps://b.rtmp.youtube.com/live2?backup=1/••••mnop -loglevel warning"
+        );
+
+        // Endpoints without a key stay readable
+        assert_eq!(redact_publish_urls("rtmp://a.rtmp.youtube.com/live2"), "rtmp://a.rtmp.youtube.com/live2");
+    }
+}
@@ -0,0 +1,359 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::stream::types::{SessionEndReason, Stream, StreamSession};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportGrouping {
+    #[default]
+    Stream,
+    Key, // Streams sharing a stream key are one YouTube channel slot
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportQuery {
+    pub from: String, // RFC 3339, inclusive
+    pub to: String,   // RFC 3339, exclusive
+    #[serde(default)]
+    pub group_by: ReportGrouping,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UptimeReport {
+    pub from: String,
+    pub to: String,
+    pub group_by: ReportGrouping,
+    pub rows: Vec<UptimeRow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UptimeRow {
+    pub group: String,        // Stream id, or channel name and masked key
+    pub streams: Vec<String>, // Names of the streams in the group, empty once deleted
+    pub sessions: u32,
+    pub live_seconds: u64,
+    pub scheduled_seconds: u64, // Time the streams were meant to be live
+    pub interruptions: u32,     // Crashes, recovered or not, and shutdowns while live
+    pub mtbf_seconds: Option<u64>,
+    pub uptime_percent: Option<f64>,
+}
+
+const CSV_COLUMNS: [&str; 8] = [
+    "group",
+    "streams",
+    "sessions",
+    "live_seconds",
+    "scheduled_seconds",
+    "interruptions",
+    "mtbf_seconds",
+    "uptime_percent",
+];
+
+/// Parse the bounds of a report range, `None` unless both are RFC 3339 and in order
+pub fn parse_range(query: &ReportQuery) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let (from, to) = (parse_time(&query.from)?, parse_time(&query.to)?);
+    (from < to).then_some((from, to))
+}
+
+/// Aggregate the sessions overlapping `[from, to)`, oldest first, into one row per stream or key.
+/// Nothing after `now` counts, so a range reaching into the future is not penalised for it.
+pub fn build(
+    query: &ReportQuery,
+    (from, to): (DateTime<Utc>, DateTime<Utc>),
+    sessions: &[StreamSession],
+    streams: &[Stream],
+    now: DateTime<Utc>,
+) -> UptimeReport {
+    let until = to.min(now);
+    let mut rows: Vec<UptimeRow> = Vec::new();
+    let mut groups: Vec<String> = Vec::new(); // What each row groups by, index for index
+
+    for (index, session) in sessions.iter().enumerate() {
+        let Some(started) = parse_time(&session.started_at) else {
+            continue;
+        };
+        let ended = session.ended_at.as_deref().and_then(parse_time).unwrap_or(now);
+
+        // A crash or shutdown cuts a run short - it was meant to go on until its deadline,
+        // or until the stream was started again. Deliberate and scheduled stops end the window with the run.
+        let interrupted = matches!(
+            session.end_reason,
+            Some(SessionEndReason::Crash | SessionEndReason::Interrupted)
+        );
+        let next_start = sessions[index + 1..].iter()
+            .find(|next| next.stream_id == session.stream_id)
+            .and_then(|next| parse_time(&next.started_at));
+        let window_end = match session.scheduled_end.as_deref().and_then(parse_time) {
+            Some(deadline) if interrupted => deadline.min(next_start.unwrap_or(deadline)).max(ended),
+            _ => ended,
+        };
+
+        // Sessions of deleted streams are reported under their stream id. A key is one
+        // channel, labelled with its name and masked key - never the key itself.
+        let stream = streams.iter().find(|s| s.id == session.stream_id);
+        let (group, label) = match (query.group_by, stream) {
+            (ReportGrouping::Key, Some(stream)) => {
+                (stream.channel_id.clone(), format!("{} ({})", stream.channel_name, stream.masked_key))
+            }
+            _ => (session.stream_id.clone(), session.stream_id.clone()),
+        };
+
+        let row_index = match groups.iter().position(|g| *g == group) {
+            Some(row_index) => row_index,
+            None => {
+                groups.push(group);
+                rows.push(UptimeRow {
+                    group: label,
+                    streams: Vec::new(),
+                    sessions: 0,
+                    live_seconds: 0,
+                    scheduled_seconds: 0,
+                    interruptions: 0,
+                    mtbf_seconds: None,
+                    uptime_percent: None,
+                });
+                rows.len() - 1
+            }
+        };
+        let row = &mut rows[row_index];
+
+        if let Some(stream) = stream {
+            if !row.streams.contains(&stream.name) {
+                row.streams.push(stream.name.clone());
+            }
+        }
+        row.sessions += 1;
+        row.live_seconds += overlap(started, ended, from, until);
+        row.scheduled_seconds += overlap(started, window_end, from, until);
+        // Restarts count when they happened. Those recorded without a time count when the session started.
+        let timed = session.restart_times.iter()
+            .filter_map(|time| parse_time(time))
+            .filter(|time| *time >= from && *time < to)
+            .count() as u32;
+        let untimed = session.restarts.saturating_sub(session.restart_times.len() as u32);
+        row.interruptions += timed;
+        if started >= from && started < to {
+            row.interruptions += untimed;
+        }
+        if interrupted && ended >= from && ended < to {
+            row.interruptions += 1;
+        }
+    }
+
+    for row in &mut rows {
+        row.mtbf_seconds = (row.interruptions > 0).then(|| row.live_seconds / row.interruptions as u64);
+        row.uptime_percent = (row.scheduled_seconds > 0).then(|| {
+            (row.live_seconds as f64 / row.scheduled_seconds as f64 * 100.0).min(100.0)
+        });
+    }
+    rows.sort_by(|a, b| a.group.cmp(&b.group));
+
+    UptimeReport {
+        from: from.to_rfc3339(),
+        to: to.to_rfc3339(),
+        group_by: query.group_by,
+        rows,
+    }
+}
+
+impl UptimeReport {
+    /// CSV with the same column names whatever the operator's language, so scripts can read it
+    pub fn to_csv(&self) -> String {
+        let mut csv = CSV_COLUMNS.join(",");
+        csv.push('\n');
+        for row in &self.rows {
+            let fields = [
+                csv_field(&row.group),
+                csv_field(&row.streams.join("; ")),
+                row.sessions.to_string(),
+                row.live_seconds.to_string(),
+                row.scheduled_seconds.to_string(),
+                row.interruptions.to_string(),
+                row.mtbf_seconds.map(|s| s.to_string()).unwrap_or_default(),
+                row.uptime_percent.map(|p| format!("{:.2}", p)).unwrap_or_default(),
+            ];
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    pub fn export(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Csv => self.to_csv(),
+            ReportFormat::Json => serde_json::to_string_pretty(self).unwrap_or_else(|_| "{}".to_string()),
+        }
+    }
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value).ok().map(|time| time.with_timezone(&Utc))
+}
+
+/// Seconds `[start, end)` and `[from, until)` have in common
+fn overlap(start: DateTime<Utc>, end: DateTime<Utc>, from: DateTime<Utc>, until: DateTime<Utc>) -> u64 {
+    (end.min(until) - start.max(from)).num_seconds().max(0) as u64
+}
+
+/// Quote a field if it could break the row apart
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32) -> String {
+        format!("2024-05-07T{:02}:00:00+00:00", hour)
+    }
+
+    fn session(stream_id: &str, start: u32, end: u32, scheduled_end: Option<u32>, reason: SessionEndReason) -> StreamSession {
+        StreamSession {
+            id: format!("{}-{}", stream_id, start),
+            stream_id: stream_id.into(),
+            started_at: at(start),
+            ended_at: Some(at(end)),
+            scheduled_end: scheduled_end.map(at),
+            end_reason: Some(reason),
+            failure_kind: None,
+            failure_reason: None,
+            elapsed_seconds: None,
+            live_seconds: None,
+            last_seen_at: None,
+            restarts: 0,
+            restart_times: Vec::new(),
+            encoder_stats: None,
+            log_tail: Vec::new(),
+        }
+    }
+
+    fn query(from: u32, to: u32) -> ReportQuery {
+        ReportQuery { from: at(from), to: at(to), group_by: ReportGrouping::Stream }
+    }
+
+    fn run(query: &ReportQuery, sessions: &[StreamSession]) -> UptimeReport {
+        let now = parse_time(&at(23)).unwrap();
+        build(query, parse_range(query).unwrap(), sessions, &[], now)
+    }
+
+    #[test]
+    fn test_crash_counts_against_the_scheduled_window() {
+        // Scheduled 10:00-14:00, crashed at 12:00, then restarted 13:00-14:00
+        let mut recovered = session("a", 13, 14, Some(14), SessionEndReason::Schedule);
+        recovered.restarts = 1;
+        let sessions = [session("a", 10, 12, Some(14), SessionEndReason::Crash), recovered];
+
+        let report = run(&query(0, 23), &sessions);
+        let row = &report.rows[0];
+        assert_eq!(row.sessions, 2);
+        assert_eq!(row.live_seconds, 3 * 3600);
+        // Down from the crash until the restart
+        assert_eq!(row.scheduled_seconds, 4 * 3600);
+        assert_eq!(row.interruptions, 2);
+        assert_eq!(row.mtbf_seconds, Some(3 * 3600 / 2));
+        assert_eq!(row.uptime_percent, Some(75.0));
+    }
+
+    #[test]
+    fn test_only_restarts_inside_the_range_count() {
+        let mut session = session("a", 8, 12, None, SessionEndReason::Manual);
+        session.restarts = 2;
+        session.restart_times = vec![at(9), at(11)];
+
+        let report = run(&query(10, 12), &[session]);
+        assert_eq!(report.rows[0].interruptions, 1);
+    }
+
+    #[test]
+    fn test_key_rows_are_labelled_with_the_masked_key() {
+        let stream = |id: &str, name: &str| -> Stream {
+            serde_json::from_value(serde_json::json!({
+                "id": id,
+                "name": name,
+                "channelId": "ch",
+                "channelName": "Lofi Girl",
+                "youtubeKey": "enc:v1:k:c2VhbGVk",
+                "maskedKey": "aaaa••••bbbb",
+                "videoPath": "/videos/lofi.mp4",
+                "status": "idle",
+                "schedule": { "type": "manual", "duration": null, "absolute": null },
+                "startedAt": null,
+                "stoppedAt": null,
+                "createdAt": "2024-05-07T10:00:00+00:00",
+            }))
+            .unwrap()
+        };
+        let streams = [stream("a", "Lofi radio"), stream("b", "Lofi radio 2")];
+        let sessions = [
+            session("a", 8, 9, None, SessionEndReason::Manual),
+            session("b", 10, 11, None, SessionEndReason::Manual),
+        ];
+
+        let query = ReportQuery { group_by: ReportGrouping::Key, ..query(0, 23) };
+        let now = parse_time(&at(23)).unwrap();
+        let report = build(&query, parse_range(&query).unwrap(), &sessions, &streams, now);
+        assert_eq!(report.rows.len(), 1);
+        assert_eq!(report.rows[0].group, "Lofi Girl (aaaa••••bbbb)");
+        assert_eq!(report.rows[0].streams, vec!["Lofi radio", "Lofi radio 2"]);
+        assert_eq!(report.rows[0].sessions, 2);
+    }
+
+    #[test]
+    fn test_sessions_are_clipped_to_the_range() {
+        let sessions = [session("a", 8, 12, None, SessionEndReason::Manual)];
+
+        let report = run(&query(10, 11), &sessions);
+        let row = &report.rows[0];
+        assert_eq!(row.live_seconds, 3600);
+        assert_eq!(row.interruptions, 0);
+        assert_eq!(row.mtbf_seconds, None);
+        assert_eq!(row.uptime_percent, Some(100.0));
+    }
+
+    #[test]
+    fn test_csv_quotes_fields() {
+        let report = UptimeReport {
+            from: at(0),
+            to: at(1),
+            group_by: ReportGrouping::Stream,
+            rows: vec![UptimeRow {
+                group: "a".into(),
+                streams: vec!["Lofi, 24/7".into()],
+                sessions: 1,
+                live_seconds: 60,
+                scheduled_seconds: 120,
+                interruptions: 1,
+                mtbf_seconds: Some(60),
+                uptime_percent: Some(50.0),
+            }],
+        };
+        let csv = report.to_csv();
+        assert_eq!(
+            csv.lines().next(),
+            Some("group,streams,sessions,live_seconds,scheduled_seconds,interruptions,mtbf_seconds,uptime_percent")
+        );
+        assert_eq!(csv.lines().nth(1), Some("a,\"Lofi, 24/7\",1,60,120,1,60,50.00"));
+    }
+
+    #[test]
+    fn test_range_must_be_ordered() {
+        assert!(parse_range(&query(12, 10)).is_none());
+        assert!(parse_range(&ReportQuery { from: "yesterday".into(), ..query(0, 1) }).is_none());
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
-use chrono::{NaiveDateTime, TimeZone, Utc};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
 use chrono_tz::Tz;
 use tokio::time::{sleep, Duration};
 
@@ -9,19 +9,19 @@ pub struct Scheduler {
 }
 
 impl Scheduler {
-    /// Create a new scheduler that will call the callback after the specified seconds
-    pub fn new<F>(seconds: u64, callback: F) -> Self 
+    /// Create a new scheduler that will call the callback after the specified delay
+    pub fn new<F>(delay: Duration, callback: F) -> Self 
     where
         F: FnOnce() + Send + 'static,
     {
         let cancelled = Arc::new(AtomicBool::new(false));
         let cancelled_clone = cancelled.clone();
         
-        tracing::info!("Scheduling stop in {} seconds", seconds);
+        tracing::info!("Scheduling callback in {:.3} seconds", delay.as_secs_f64());
         
         tokio::spawn(async move {
             // Use high-precision sleep
-            let target = tokio::time::Instant::now() + Duration::from_secs(seconds);
+            let target = tokio::time::Instant::now() + delay;
             
             // Check cancellation every 100ms for responsive cancellation
             while tokio::time::Instant::now() < target {
@@ -51,15 +51,7 @@ impl Scheduler {
 
     /// Calculate seconds until a specific datetime in a timezone
     pub fn calculate_seconds_until(datetime_str: &str, timezone_str: &str) -> Option<u64> {
-        // Parse the timezone
-        let tz: Tz = timezone_str.parse().ok()?;
-        
-        // Parse datetime (expecting format like "2024-01-15T14:30")
-        let naive = NaiveDateTime::parse_from_str(datetime_str, "%Y-%m-%dT%H:%M").ok()?;
-        
-        // Convert to timezone-aware datetime
-        let target_local = tz.from_local_datetime(&naive).single()?;
-        let target_utc = target_local.with_timezone(&Utc);
+        let target_utc = resolve_deadline(datetime_str, timezone_str).ok()?;
         
         // Get current time
         let now_utc = Utc::now();
@@ -74,6 +66,30 @@ impl Scheduler {
     }
 }
 
+/// Why an absolute stop time could not be resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeadlineError {
+    InvalidTimezone,
+    InvalidDateTime,
+    /// Skipped or repeated by a daylight saving change
+    AmbiguousLocalTime,
+}
+
+/// Resolve a local datetime like "2024-01-15T14:30" in a named timezone to UTC
+pub fn resolve_deadline(datetime_str: &str, timezone_str: &str) -> Result<DateTime<Utc>, DeadlineError> {
+    // Parse the timezone
+    let tz: Tz = timezone_str.parse().map_err(|_| DeadlineError::InvalidTimezone)?;
+
+    // Parse datetime (expecting format like "2024-01-15T14:30")
+    let naive = NaiveDateTime::parse_from_str(datetime_str, "%Y-%m-%dT%H:%M")
+        .map_err(|_| DeadlineError::InvalidDateTime)?;
+
+    // Convert to timezone-aware datetime
+    let target_local = tz.from_local_datetime(&naive).single()
+        .ok_or(DeadlineError::AmbiguousLocalTime)?;
+    Ok(target_local.with_timezone(&Utc))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -84,7 +100,7 @@ mod tests {
         let counter = Arc::new(AtomicU32::new(0));
         let counter_clone = counter.clone();
         
-        let _scheduler = Scheduler::new(1, move || {
+        let _scheduler = Scheduler::new(Duration::from_secs(1), move || {
             counter_clone.fetch_add(1, Ordering::Relaxed);
         });
         
@@ -97,7 +113,7 @@ mod tests {
         let counter = Arc::new(AtomicU32::new(0));
         let counter_clone = counter.clone();
         
-        let scheduler = Scheduler::new(2, move || {
+        let scheduler = Scheduler::new(Duration::from_secs(2), move || {
             counter_clone.fetch_add(1, Ordering::Relaxed);
         });
         
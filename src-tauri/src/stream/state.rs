@@ -0,0 +1,183 @@
+use crate::i18n::Message;
+use crate::stream::types::{EncoderStats, FailureKind, SessionEndReason, StreamStatus};
+
+impl StreamStatus {
+    /// The transition table - every status change is checked against it
+    pub fn can_transition_to(&self, next: &StreamStatus) -> bool {
+        use StreamStatus::*;
+
+        matches!(
+            (self, next),
+            // A start goes live or fails on the spot - a stream never moves to the status it has
+            (Idle | Scheduled | Completed | Error, Live)
+                | (Idle | Scheduled | Completed, Error)
+                | (Idle, Scheduled)
+                | (Scheduled, Idle)
+                | (Live, Stopping | Completed | Error)
+                | (Stopping, Completed | Error)
+        )
+    }
+}
+
+/// A status change and everything recorded with it, written in one transaction
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+    pub to: StreamStatus,
+    /// Stamp `started_at`, clear `stopped_at` and open a session - a new run begins
+    pub started: bool,
+    /// How long the new run is scheduled to last, kept with its session
+    pub scheduled_seconds: Option<u64>,
+    /// Stamp `stopped_at`
+    pub stopped: bool,
+    pub last_elapsed_seconds: Option<u64>,
+    /// Live time of the run across automatic restarts
+    pub live_seconds: Option<u64>,
+    /// `Some(None)` clears the saved position, `None` leaves it alone
+    pub playback_position: Option<Option<f64>>,
+    /// Why the stream ended up here, kept until the next transition and rendered when read
+    pub reason: Option<Message>,
+    /// Close the stream's open session, if it has one
+    pub session_end: Option<SessionEnd>,
+}
+
+/// How a session ended, recorded in the session history
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionEnd {
+    pub reason: SessionEndReason,
+    pub failure_kind: Option<FailureKind>,
+    pub encoder_stats: Option<EncoderStats>,
+    pub log_tail: Vec<String>,
+}
+
+impl SessionEnd {
+    pub fn new(reason: SessionEndReason) -> Self {
+        Self {
+            reason,
+            failure_kind: None,
+            encoder_stats: None,
+            log_tail: Vec::new(),
+        }
+    }
+
+    pub fn crash(kind: FailureKind) -> Self {
+        Self {
+            failure_kind: Some(kind),
+            ..Self::new(SessionEndReason::Crash)
+        }
+    }
+
+    /// Attach what the last FFmpeg process of the session reported
+    pub fn with_output(self, encoder_stats: EncoderStats, log_tail: Vec<String>) -> Self {
+        Self {
+            encoder_stats: Some(encoder_stats),
+            log_tail,
+            ..self
+        }
+    }
+}
+
+impl Transition {
+    fn to(status: StreamStatus) -> Self {
+        Self {
+            to: status,
+            started: false,
+            scheduled_seconds: None,
+            stopped: false,
+            last_elapsed_seconds: None,
+            live_seconds: None,
+            playback_position: None,
+            reason: None,
+            session_end: None,
+        }
+    }
+
+    pub fn live() -> Self {
+        Self {
+            started: true,
+            ..Self::to(StreamStatus::Live)
+        }
+    }
+
+    pub fn stopping() -> Self {
+        Self::to(StreamStatus::Stopping)
+    }
+
+    /// Finished normally - the next start begins at the top of the media
+    pub fn completed(elapsed: Option<u64>, end: SessionEnd) -> Self {
+        Self {
+            stopped: true,
+            last_elapsed_seconds: elapsed,
+            playback_position: Some(None),
+            session_end: Some(end),
+            ..Self::to(StreamStatus::Completed)
+        }
+    }
+
+    /// Record how long the run was live, counting every process since it went live
+    pub fn live_for(self, live_seconds: Option<u64>) -> Self {
+        Self { live_seconds, ..self }
+    }
+
+    /// Failed - keeps the position, if there is one, for a resume
+    pub fn failed(reason: Message, elapsed: Option<u64>, position: Option<f64>, end: SessionEnd) -> Self {
+        Self {
+            stopped: true,
+            last_elapsed_seconds: elapsed,
+            playback_position: position.map(Some),
+            reason: Some(reason),
+            session_end: Some(end),
+            ..Self::to(StreamStatus::Error)
+        }
+    }
+}
+
+/// What happened to a requested transition
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransitionOutcome {
+    Applied { from: StreamStatus },
+    /// Not allowed from the stream's current status - nothing was written
+    Rejected { from: StreamStatus },
+    NotFound,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use StreamStatus::*;
+
+    #[test]
+    fn test_normal_lifecycle_is_allowed() {
+        assert!(Idle.can_transition_to(&Live));
+        assert!(Live.can_transition_to(&Stopping));
+        assert!(Stopping.can_transition_to(&Completed));
+        assert!(Completed.can_transition_to(&Live));
+        assert!(Live.can_transition_to(&Error));
+        assert!(Error.can_transition_to(&Live));
+    }
+
+    #[test]
+    fn test_illegal_transitions_are_rejected() {
+        assert!(!Completed.can_transition_to(&Stopping));
+        assert!(!Idle.can_transition_to(&Stopping));
+        assert!(!Live.can_transition_to(&Live));
+        assert!(!Error.can_transition_to(&Error));
+        assert!(!Completed.can_transition_to(&Completed));
+        assert!(!Idle.can_transition_to(&Completed));
+        assert!(!Stopping.can_transition_to(&Live));
+        assert!(!Live.can_transition_to(&Idle));
+    }
+
+    #[test]
+    fn test_failed_keeps_position_only_when_known() {
+        let end = SessionEnd::crash(FailureKind::EndpointsExhausted);
+        let reason = Message::new("reason.terminated", &Default::default());
+        let failed = Transition::failed(reason.clone(), Some(42), None, end.clone());
+        assert_eq!(failed.to, Error);
+        assert_eq!(failed.playback_position, None);
+        assert_eq!(failed.reason.as_ref(), Some(&reason));
+        assert_eq!(failed.session_end.map(|e| e.reason), Some(SessionEndReason::Crash));
+
+        let failed = Transition::failed(reason, Some(42), Some(12.5), end);
+        assert_eq!(failed.playback_position, Some(Some(12.5)));
+    }
+}
@@ -0,0 +1,260 @@
+use uuid::Uuid;
+
+use crate::stream::edit;
+use crate::stream::types::{Stream, StreamInput, StreamPatch, StreamTemplate, TemplateInput, TemplateSettings};
+use crate::stream::validation::{ErrorCode, FieldError};
+
+impl StreamTemplate {
+    pub fn new(input: TemplateInput) -> Self {
+        let TemplateInput { name, channel_id, mut settings } = input;
+        settings.video_path = settings.video_path.filter(|path| !path.trim().is_empty());
+        settings.tags = edit::normalize_tags(&settings.tags);
+
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name: name.trim().to_string(),
+            channel_id,
+            channel_name: None,
+            settings,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    /// A new stream from the template, with the fields it leaves to each stream filled in from `overrides`
+    pub fn to_input(&self, overrides: &StreamPatch) -> StreamInput {
+        let settings = &self.settings;
+        let mut input = StreamInput {
+            name: String::new(),
+            channel_id: self.channel_id.clone(),
+            channel_name: None,
+            youtube_key: String::new(),
+            video_path: settings.video_path.clone().unwrap_or_default(),
+            trim: settings.trim,
+            ingest_endpoints: settings.ingest_endpoints.clone(),
+            clips: settings.clips.clone(),
+            resume: settings.resume.clone(),
+            schedule: settings.schedule.clone(),
+            tags: settings.tags.clone(),
+            start_immediately: false,
+        };
+        apply_overrides(&mut input, overrides);
+        input
+    }
+}
+
+/// A copy of `source` called `name` on the same channel, with `overrides` applied.
+/// Status, run history and playback position start afresh.
+pub fn clone_input(source: &Stream, name: String, overrides: &StreamPatch) -> StreamInput {
+    let mut input = StreamInput {
+        name,
+        channel_id: Some(source.channel_id.clone()),
+        channel_name: None,
+        youtube_key: String::new(),
+        video_path: source.video_path.clone(),
+        trim: source.trim,
+        ingest_endpoints: source.ingest_endpoints.clone(),
+        clips: source.clips.clone(),
+        resume: source.resume.clone(),
+        schedule: source.schedule.clone(),
+        tags: source.tags.clone(),
+        start_immediately: false,
+    };
+    apply_overrides(&mut input, overrides);
+    input
+}
+
+/// Fill in a new stream like a patch fills in an existing one. A key moves the stream off the
+/// template's or source's channel, onto the key's own.
+fn apply_overrides(input: &mut StreamInput, overrides: &StreamPatch) {
+    if let Some(name) = &overrides.name {
+        input.name = name.clone();
+    }
+    if let Some(key) = &overrides.youtube_key {
+        input.youtube_key = key.clone();
+        input.channel_id = None;
+    }
+    if let Some(channel_id) = &overrides.channel_id {
+        input.channel_id = Some(channel_id.clone());
+    }
+    if let Some(path) = &overrides.video_path {
+        input.video_path = path.clone();
+    }
+    if let Some(trim) = overrides.trim {
+        input.trim = trim;
+    }
+    if let Some(endpoints) = &overrides.ingest_endpoints {
+        input.ingest_endpoints = endpoints.clone();
+    }
+    if let Some(clips) = &overrides.clips {
+        input.clips = clips.clone();
+    }
+    if let Some(resume) = &overrides.resume {
+        input.resume = resume.clone();
+    }
+    if let Some(schedule) = &overrides.schedule {
+        input.schedule = schedule.clone();
+    }
+    if let Some(tags) = &overrides.tags {
+        input.tags = tags.clone();
+    }
+}
+
+/// Errors of a stream built from a template that are the template's to fix.
+/// The file and the key may be left for each stream to fill in, and a stop time that has passed
+/// is only an error for the streams made from the template after it.
+pub fn template_errors(settings: &TemplateSettings, errors: Vec<FieldError>) -> Vec<FieldError> {
+    errors
+        .into_iter()
+        .filter(|e| {
+            let left_out = match e.field.as_str() {
+                "videoPath" => settings.video_path.is_none(),
+                "youtubeKey" => true,
+                _ => false,
+            };
+            !(left_out && e.code == ErrorCode::Required) && e.code != ErrorCode::InPast
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Params;
+    use crate::stream::types::{AbsoluteConfig, ScheduleConfig, ScheduleType};
+
+    fn template() -> StreamTemplate {
+        StreamTemplate::new(TemplateInput {
+            name: " Client A, 24/7 ".into(),
+            channel_id: Some("channel-a".into()),
+            settings: TemplateSettings {
+                video_path: Some("  ".into()),
+                trim: Default::default(),
+                ingest_endpoints: Vec::new(),
+                clips: Default::default(),
+                resume: Default::default(),
+                schedule: ScheduleConfig { schedule_type: ScheduleType::Manual, duration: None, absolute: None, plays: None },
+                tags: vec!["client a".into(), "Client A".into()],
+            },
+        })
+    }
+
+    #[test]
+    fn test_overrides_fill_in_a_template() {
+        let template = template();
+        assert_eq!(template.name, "Client A, 24/7");
+        assert_eq!(template.settings.video_path, None);
+        assert_eq!(template.settings.tags, vec!["client a"]);
+
+        let input = template.to_input(&StreamPatch {
+            name: Some("Lofi".into()),
+            video_path: Some("/videos/lofi.mp4".into()),
+            ..Default::default()
+        });
+        assert_eq!((input.name.as_str(), input.video_path.as_str()), ("Lofi", "/videos/lofi.mp4"));
+        assert_eq!(input.channel_id.as_deref(), Some("channel-a"));
+
+        // A key of its own takes the stream off the template's channel
+        let input = template.to_input(&StreamPatch { youtube_key: Some("cccc-dddd".into()), ..Default::default() });
+        assert_eq!((input.channel_id, input.youtube_key.as_str()), (None, "cccc-dddd"));
+    }
+
+    #[test]
+    fn test_templates_may_leave_out_the_file_and_key() {
+        let required = |field: &str| FieldError::new(field, ErrorCode::Required, "field.required", Params::new());
+        let errors = vec![required("name"), required("videoPath"), required("youtubeKey")];
+
+        let fields = |errors: Vec<FieldError>| errors.into_iter().map(|e| e.field).collect::<Vec<_>>();
+        assert_eq!(fields(template_errors(&template().settings, errors.clone())), vec!["name"]);
+
+        let mut with_file = template().settings;
+        with_file.video_path = Some("/videos/lofi.mp4".into());
+        assert_eq!(fields(template_errors(&with_file, errors)), vec!["name", "videoPath"]);
+    }
+
+    #[test]
+    fn test_templates_keep_a_stop_time_that_has_passed() {
+        let mut settings = template().settings;
+        settings.schedule = ScheduleConfig {
+            schedule_type: ScheduleType::Absolute,
+            duration: None,
+            absolute: Some(AbsoluteConfig { datetime: "2024-05-07T22:00".into(), timezone: "UTC".into() }),
+            plays: None,
+        };
+        let now = chrono::Utc::now();
+        let errors = crate::stream::validation::validate_schedule(&settings.schedule, now);
+        assert_eq!(errors.iter().map(|e| e.code).collect::<Vec<_>>(), [ErrorCode::InPast]);
+        assert!(template_errors(&settings, errors).is_empty());
+
+        // A stop time that doesn't resolve is still the template's to fix
+        settings.schedule.absolute = Some(AbsoluteConfig { datetime: "2024-05-07T22:00".into(), timezone: "Mars/Base".into() });
+        let errors = crate::stream::validation::validate_schedule(&settings.schedule, now);
+        assert_eq!(template_errors(&settings, errors).len(), 1);
+    }
+
+    fn source() -> Stream {
+        serde_json::from_value(serde_json::json!({
+            "id": "source",
+            "name": "Lofi radio",
+            "channelId": "channel-a",
+            "youtubeKey": "enc:v1:k:aaaa",
+            "videoPath": "/videos/lofi.mp4",
+            "trim": { "startSeconds": 5.0, "endSeconds": 60.0 },
+            "ingestEndpoints": ["rtmp://a.rtmp.youtube.com/live2", "rtmp://b.rtmp.youtube.com/live2?backup=1"],
+            "clips": { "introPath": "/videos/intro.mp4", "outroPath": null, "fadeOutSeconds": 3 },
+            "status": "live",
+            "statusReason": "Started by schedule",
+            "schedule": { "type": "plays", "duration": null, "absolute": null, "plays": 2 },
+            "startedAt": "2024-05-07T10:00:00+00:00",
+            "stoppedAt": null,
+            "createdAt": "2024-05-01T10:00:00+00:00",
+            "lastElapsedSeconds": 3600,
+            "playbackPosition": 42.5,
+            "tags": ["lofi"],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_clones_copy_the_settings_and_start_afresh() {
+        let source = source();
+        let input = clone_input(&source, "Lofi radio (2)".into(), &StreamPatch::default());
+
+        assert_eq!(input.name, "Lofi radio (2)");
+        assert_eq!(input.channel_id.as_deref(), Some("channel-a"));
+        assert_eq!(input.youtube_key, "");
+        assert_eq!(input.video_path, source.video_path);
+        assert_eq!(input.trim, source.trim);
+        assert_eq!(input.ingest_endpoints, source.ingest_endpoints);
+        assert_eq!(input.clips.intro_path, source.clips.intro_path);
+        assert_eq!(input.clips.fade_out_seconds, 3);
+        assert_eq!(input.schedule, source.schedule);
+        assert_eq!(input.tags, source.tags);
+        assert!(!input.start_immediately);
+    }
+
+    #[test]
+    fn test_clone_overrides_replace_what_they_name() {
+        let source = source();
+        let input = clone_input(&source, "Lofi radio (2)".into(), &StreamPatch {
+            name: Some("Rain sounds".into()),
+            video_path: Some("/videos/rain.mp4".into()),
+            tags: Some(Vec::new()),
+            ..Default::default()
+        });
+        assert_eq!((input.name.as_str(), input.video_path.as_str()), ("Rain sounds", "/videos/rain.mp4"));
+        assert!(input.tags.is_empty());
+        assert_eq!(input.ingest_endpoints, source.ingest_endpoints);
+
+        // A key of its own takes the clone off the source's channel, a channel puts it on another
+        let input = clone_input(&source, "Lofi radio (2)".into(), &StreamPatch {
+            youtube_key: Some("cccc-dddd".into()),
+            ..Default::default()
+        });
+        assert_eq!((input.channel_id, input.youtube_key.as_str()), (None, "cccc-dddd"));
+        let input = clone_input(&source, "Lofi radio (2)".into(), &StreamPatch {
+            channel_id: Some("channel-b".into()),
+            ..Default::default()
+        });
+        assert_eq!(input.channel_id.as_deref(), Some("channel-b"));
+    }
+}
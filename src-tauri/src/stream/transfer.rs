@@ -0,0 +1,504 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::i18n;
+use crate::stream::manager::ManagerError;
+use crate::stream::report::csv_field;
+use crate::stream::types::{
+    AbsoluteConfig, ClipConfig, DurationConfig, MediaTrim, ResumeConfig, ScheduleConfig, ScheduleType, Stream,
+    StreamInput, StreamPatch,
+};
+use crate::stream::validation::{ErrorCode, FieldError};
+
+/// Version of the JSON export layout
+pub const EXPORT_VERSION: u32 = 1;
+
+/// CSV columns, in export order. Import finds them by name and ignores any others.
+const CSV_COLUMNS: [&str; 18] = [
+    "name",
+    "channel",
+    "youtube_key",
+    "video_path",
+    "trim_start",
+    "trim_end",
+    "ingest_endpoints", // Space separated
+    "schedule_type",
+    "duration_seconds",
+    "stop_at",
+    "timezone",
+    "plays",
+    "intro_path",
+    "outro_path",
+    "fade_out_seconds",
+    "resume",
+    "rewind_seconds",
+    "tags", // Semicolon separated
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferFormat {
+    Csv,  // Flat columns - clip trims are left out
+    Json, // Everything
+}
+
+/// What to do with an imported stream whose name is already taken
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictStrategy {
+    #[default]
+    Skip,
+    Overwrite, // Replace the existing stream's settings
+    Rename,    // Import alongside it as `Name (2)`
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportOptions {
+    #[serde(default)]
+    pub dry_run: bool, // Validate every row and report what would happen, changing nothing
+    #[serde(default)]
+    pub on_conflict: ConflictStrategy,
+}
+
+/// A stream's settings as exported - everything but its status and run history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamRecord {
+    pub name: String,
+    #[serde(default)]
+    pub channel: String, // Channel name - finds the channel on import when the key is left out
+    #[serde(default)]
+    pub youtube_key: Option<String>, // In the clear, None when keys were redacted
+    pub video_path: String,
+    #[serde(default)]
+    pub trim: MediaTrim,
+    #[serde(default)]
+    pub ingest_endpoints: Vec<String>,
+    #[serde(default)]
+    pub clips: ClipConfig,
+    #[serde(default)]
+    pub resume: ResumeConfig,
+    pub schedule: ScheduleConfig,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// A row of an import file that could not be read, named as far as it could be
+#[derive(Debug, Clone, PartialEq)]
+pub struct RejectedRow {
+    pub name: String,
+    pub errors: Vec<FieldError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StreamExport<T> {
+    version: u32,
+    exported_at: String,
+    streams: T,
+}
+
+impl StreamRecord {
+    /// `key` is the stream's key in the clear, or None to redact it
+    pub fn new(stream: &Stream, key: Option<String>) -> Self {
+        Self {
+            name: stream.name.clone(),
+            channel: stream.channel_name.clone(),
+            youtube_key: key,
+            video_path: stream.video_path.clone(),
+            trim: stream.trim,
+            ingest_endpoints: stream.ingest_endpoints.clone(),
+            clips: stream.clips.clone(),
+            resume: stream.resume.clone(),
+            schedule: stream.schedule.clone(),
+            tags: stream.tags.clone(),
+        }
+    }
+
+    /// The stream to add. Without a key it publishes to `channel_id`, the channel named in the record.
+    pub fn to_input(&self, channel_id: Option<String>) -> StreamInput {
+        StreamInput {
+            name: self.name.trim().to_string(),
+            channel_id: channel_id.filter(|_| self.youtube_key.is_none()),
+            channel_name: Some(self.channel.trim().to_string()).filter(|name| !name.is_empty()),
+            youtube_key: self.youtube_key.clone().unwrap_or_default(),
+            video_path: self.video_path.clone(),
+            trim: self.trim,
+            ingest_endpoints: self.ingest_endpoints.clone(),
+            clips: self.clips.clone(),
+            resume: self.resume.clone(),
+            schedule: self.schedule.clone(),
+            tags: self.tags.clone(),
+            start_immediately: false,
+        }
+    }
+
+    /// Every setting of an existing stream replaced with the record's
+    pub fn to_patch(&self, channel_id: Option<String>) -> StreamPatch {
+        StreamPatch {
+            name: Some(self.name.clone()),
+            channel_id: channel_id.filter(|_| self.youtube_key.is_none()),
+            youtube_key: self.youtube_key.clone(),
+            video_path: Some(self.video_path.clone()),
+            trim: Some(self.trim),
+            ingest_endpoints: Some(self.ingest_endpoints.clone()),
+            clips: Some(self.clips.clone()),
+            resume: Some(self.resume.clone()),
+            schedule: Some(self.schedule.clone()),
+            tags: Some(self.tags.clone()),
+        }
+    }
+}
+
+pub fn export(records: &[StreamRecord], format: TransferFormat) -> String {
+    match format {
+        TransferFormat::Csv => to_csv(records),
+        TransferFormat::Json => {
+            let export = StreamExport {
+                version: EXPORT_VERSION,
+                exported_at: chrono::Utc::now().to_rfc3339(),
+                streams: records,
+            };
+            serde_json::to_string_pretty(&export).unwrap_or_else(|_| "{}".to_string())
+        }
+    }
+}
+
+/// Each row of an import file, or why it could not be read. Fails only if the file as a whole is unreadable.
+pub fn parse(text: &str, format: TransferFormat) -> Result<Vec<Result<StreamRecord, RejectedRow>>, String> {
+    match format {
+        TransferFormat::Csv => from_csv(text),
+        TransferFormat::Json => {
+            // Rows are read one at a time so one bad stream doesn't sink the rest
+            let export: StreamExport<Vec<serde_json::Value>> = serde_json::from_str(text).map_err(|e| e.to_string())?;
+            Ok(export.streams.into_iter().map(|value| {
+                let name = value.get("name").and_then(serde_json::Value::as_str).unwrap_or_default().trim().to_string();
+                serde_json::from_value(value).map_err(|e| RejectedRow {
+                    name,
+                    errors: vec![FieldError::new(
+                        "",
+                        ErrorCode::InvalidValue,
+                        "field.invalidRecord",
+                        i18n::params([("error", e.to_string())]),
+                    )],
+                })
+            }).collect())
+        }
+    }
+}
+
+fn to_csv(records: &[StreamRecord]) -> String {
+    let mut csv = CSV_COLUMNS.join(",");
+    csv.push('\n');
+    for record in records {
+        let schedule = &record.schedule;
+        let number = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+        let fields = [
+            record.name.clone(),
+            record.channel.clone(),
+            record.youtube_key.clone().unwrap_or_default(),
+            record.video_path.clone(),
+            number(record.trim.start_seconds),
+            number(record.trim.end_seconds),
+            record.ingest_endpoints.join(" "),
+            schedule.schedule_type.as_str().to_string(),
+            schedule.duration.as_ref().map(|d| d.to_seconds().to_string()).unwrap_or_default(),
+            schedule.absolute.as_ref().map(|a| a.datetime.clone()).unwrap_or_default(),
+            schedule.absolute.as_ref().map(|a| a.timezone.clone()).unwrap_or_default(),
+            schedule.plays.map(|p| p.to_string()).unwrap_or_default(),
+            record.clips.intro_path.clone().unwrap_or_default(),
+            record.clips.outro_path.clone().unwrap_or_default(),
+            record.clips.fade_out_seconds.to_string(),
+            record.resume.enabled.to_string(),
+            record.resume.rewind_seconds.to_string(),
+            record.tags.join(";"),
+        ];
+        csv.push_str(&fields.map(|field| csv_field(&field)).join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+fn from_csv(text: &str) -> Result<Vec<Result<StreamRecord, RejectedRow>>, String> {
+    let mut rows = parse_csv(text).into_iter();
+    let header = rows.next().ok_or("The file is empty")?;
+    let columns: HashMap<&str, usize> = header.iter().enumerate().map(|(i, name)| (name.trim(), i)).collect();
+    for required in ["name", "video_path"] {
+        if !columns.contains_key(required) {
+            return Err(format!("Missing column: {}", required));
+        }
+    }
+
+    Ok(rows.map(|row| {
+        let mut errors = Vec::new();
+        let text = |column: &str| {
+            columns.get(column).and_then(|&i| row.get(i)).map(|v| v.trim()).unwrap_or_default().to_string()
+        };
+        let optional = |column: &str| Some(text(column)).filter(|v| !v.is_empty());
+        let mut number = |column: &'static str| -> Option<f64> {
+            let value = optional(column)?;
+            let parsed = value.parse::<f64>().ok().filter(|n| n.is_finite());
+            if parsed.is_none() {
+                errors.push(invalid_value(column, &value));
+            }
+            parsed
+        };
+        let trim = MediaTrim { start_seconds: number("trim_start"), end_seconds: number("trim_end") };
+
+        // Counts and whole seconds - a fraction or a sign is a mistake, not something to round away
+        let mut whole = |column: &'static str| -> Option<u32> {
+            let value = optional(column)?;
+            let parsed = value.parse::<u32>().ok();
+            if parsed.is_none() {
+                errors.push(invalid_value(column, &value));
+            }
+            parsed
+        };
+        let duration_seconds = whole("duration_seconds");
+        let plays = whole("plays");
+        let fade_out_seconds = whole("fade_out_seconds");
+        let rewind_seconds = whole("rewind_seconds");
+
+        let schedule_type = match optional("schedule_type").as_deref() {
+            None | Some("manual") => ScheduleType::Manual,
+            Some("duration") => ScheduleType::Duration,
+            Some("absolute") => ScheduleType::Absolute,
+            Some("plays") => ScheduleType::Plays,
+            Some(other) => {
+                errors.push(invalid_value("schedule_type", other));
+                ScheduleType::Manual
+            }
+        };
+        let resume_enabled = match optional("resume").as_deref() {
+            None => ResumeConfig::default().enabled,
+            Some("true") => true,
+            Some("false") => false,
+            Some(other) => {
+                errors.push(invalid_value("resume", other));
+                true
+            }
+        };
+
+        let schedule = ScheduleConfig {
+            schedule_type,
+            duration: duration_seconds.map(|seconds| {
+                DurationConfig { hours: seconds / 3600, minutes: seconds % 3600 / 60, seconds: seconds % 60 }
+            }),
+            absolute: optional("stop_at").map(|datetime| AbsoluteConfig {
+                datetime,
+                timezone: optional("timezone").unwrap_or_else(|| "UTC".to_string()),
+            }),
+            plays,
+        };
+
+        let record = StreamRecord {
+            name: text("name"),
+            channel: text("channel"),
+            youtube_key: optional("youtube_key"),
+            video_path: text("video_path"),
+            trim,
+            ingest_endpoints: text("ingest_endpoints").split_whitespace().map(str::to_string).collect(),
+            clips: ClipConfig {
+                intro_path: optional("intro_path"),
+                outro_path: optional("outro_path"),
+                fade_out_seconds: fade_out_seconds.unwrap_or_default(),
+                ..Default::default()
+            },
+            resume: ResumeConfig {
+                enabled: resume_enabled,
+                rewind_seconds: rewind_seconds.unwrap_or(ResumeConfig::default().rewind_seconds),
+            },
+            schedule,
+            tags: text("tags").split(';').map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect(),
+        };
+        if errors.is_empty() { Ok(record) } else { Err(RejectedRow { name: record.name, errors }) }
+    }).collect())
+}
+
+fn invalid_value(column: &str, value: &str) -> FieldError {
+    FieldError::new(
+        column,
+        ErrorCode::InvalidValue,
+        "field.invalidValue",
+        i18n::params([("value", value.to_string())]),
+    )
+}
+
+/// Split CSV into rows of fields. Quoted fields may hold commas, quotes (doubled) and line breaks.
+/// Blank lines are skipped.
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if field.is_empty() => quoted = true,
+            (',', false) => row.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut field));
+                if row.iter().any(|f| !f.is_empty()) {
+                    rows.push(std::mem::take(&mut row));
+                }
+                row.clear();
+            }
+            (c, _) => field.push(c),
+        }
+    }
+    row.push(field);
+    if row.iter().any(|f| !f.is_empty()) {
+        rows.push(row);
+    }
+    rows
+}
+
+/// What an export wrote
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportReport {
+    pub exported: usize,
+    pub redacted: Vec<String>, // Streams written without their key because it could not be decrypted
+}
+
+/// What an import did, or would do, with one row
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportAction {
+    Create,
+    Overwrite,
+    Rename,
+    Skip,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRow {
+    pub row: usize, // 1-based, not counting a CSV header
+    pub name: String, // As imported - renamed rows carry their new name
+    pub action: ImportAction,
+    pub stream_id: Option<String>, // The created or overwritten stream, None on a dry run
+    pub error: Option<ManagerError>, // Why the row was not imported
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub created: usize,
+    pub overwritten: usize,
+    pub renamed: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub rows: Vec<ImportRow>,
+}
+
+impl ImportReport {
+    pub fn new(dry_run: bool, rows: Vec<ImportRow>) -> Self {
+        let count = |action: ImportAction| rows.iter().filter(|r| r.error.is_none() && r.action == action).count();
+        Self {
+            dry_run,
+            created: count(ImportAction::Create),
+            overwritten: count(ImportAction::Overwrite),
+            renamed: count(ImportAction::Rename),
+            skipped: count(ImportAction::Skip),
+            failed: rows.iter().filter(|r| r.error.is_some()).count(),
+            rows,
+        }
+    }
+}
+
+/// `name (2)`, `name (3)`, ... - the first one not in `taken`
+pub fn free_name<V>(name: &str, taken: &HashMap<String, V>) -> String {
+    (2..)
+        .map(|n| format!("{} ({})", name, n))
+        .find(|candidate| !taken.contains_key(candidate))
+        .unwrap_or_else(|| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> StreamRecord {
+        StreamRecord {
+            name: "Lofi, 24/7".into(),
+            channel: "Lofi radio".into(),
+            youtube_key: Some("aaaa-bbbb".into()),
+            video_path: "/videos/lofi.mp4".into(),
+            trim: MediaTrim { start_seconds: Some(5.0), end_seconds: None },
+            ingest_endpoints: vec!["rtmp://a.rtmp.youtube.com/live2".into(), "rtmp://b.example.com/live".into()],
+            clips: ClipConfig { outro_path: Some("/videos/outro.mp4".into()), fade_out_seconds: 3, ..Default::default() },
+            resume: ResumeConfig { enabled: false, rewind_seconds: 10 },
+            schedule: ScheduleConfig {
+                schedule_type: ScheduleType::Duration,
+                duration: Some(DurationConfig { hours: 1, minutes: 30, seconds: 0 }),
+                absolute: None,
+                plays: None,
+            },
+            tags: vec!["Client A".into(), "lofi chill".into()],
+        }
+    }
+
+    #[test]
+    fn test_csv_and_json_round_trip() {
+        for format in [TransferFormat::Csv, TransferFormat::Json] {
+            let records = parse(&export(&[record()], format), format).unwrap();
+            assert_eq!(records.len(), 1);
+            assert_eq!(records[0].as_ref().unwrap(), &record());
+        }
+    }
+
+    #[test]
+    fn test_bad_csv_values_are_reported_per_row() {
+        let csv = "name,video_path,trim_start,schedule_type\nA,/a.mp4,abc,daily\n\n\"B \"\"2\"\"\",/b.mp4,,\n";
+        let records = parse(csv, TransferFormat::Csv).unwrap();
+        assert_eq!(records.len(), 2);
+
+        let rejected = records[0].as_ref().unwrap_err();
+        assert_eq!(rejected.name, "A");
+        let fields: Vec<&str> = rejected.errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["trim_start", "schedule_type"]);
+        assert_eq!(records[1].as_ref().unwrap().name, "B \"2\"");
+
+        assert!(parse("video_path\n/a.mp4\n", TransferFormat::Csv).is_err());
+    }
+
+    #[test]
+    fn test_counts_and_seconds_must_be_whole() {
+        let csv = "name,video_path,duration_seconds,plays,fade_out_seconds,rewind_seconds\n\
+                   A,/a.mp4,90.5,-1,1e3,4294967296\n\
+                   B,/b.mp4,5400,3,2,10\n";
+        let records = parse(csv, TransferFormat::Csv).unwrap();
+
+        let fields: Vec<&str> = records[0].as_ref().unwrap_err().errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["duration_seconds", "plays", "fade_out_seconds", "rewind_seconds"]);
+
+        let record = records[1].as_ref().unwrap();
+        assert_eq!(record.schedule.duration, Some(DurationConfig { hours: 1, minutes: 30, seconds: 0 }));
+        assert_eq!(record.schedule.plays, Some(3));
+        assert_eq!(record.clips.fade_out_seconds, 2);
+        assert_eq!(record.resume.rewind_seconds, 10);
+    }
+
+    #[test]
+    fn test_unreadable_json_rows_keep_their_name() {
+        let json = r#"{"version":1,"exportedAt":"2024-05-07T10:00:00Z","streams":[{"name":" Lofi ","videoPath":3}]}"#;
+        let records = parse(json, TransferFormat::Json).unwrap();
+        assert_eq!(records[0].as_ref().unwrap_err().name, "Lofi");
+    }
+
+    #[test]
+    fn test_free_name_skips_taken_names() {
+        let taken: HashMap<String, ()> = [("Lofi".to_string(), ()), ("Lofi (2)".to_string(), ())].into();
+        assert_eq!(free_name("Lofi", &taken), "Lofi (3)");
+    }
+}
@@ -1,8 +1,11 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+use crate::i18n::{self, Params};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum StreamStatus {
+    #[default]
     Idle,       // Draft - not started
     Live,       // Currently streaming
     Scheduled,  // Scheduled to start later
@@ -11,21 +14,57 @@ pub enum StreamStatus {
     Stopping,   // In process of stopping
 }
 
-impl Default for StreamStatus {
-    fn default() -> Self {
-        Self::Idle
+impl StreamStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Idle => "idle",
+            Self::Live => "live",
+            Self::Scheduled => "scheduled",
+            Self::Completed => "completed",
+            Self::Error => "error",
+            Self::Stopping => "stopping",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "live" => Self::Live,
+            "scheduled" => Self::Scheduled,
+            "completed" => Self::Completed,
+            "error" => Self::Error,
+            "stopping" => Self::Stopping,
+            _ => Self::Idle,
+        }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl std::fmt::Display for StreamStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ScheduleType {
     Manual,
     Duration,
     Absolute,
+    Plays, // Stop after N full plays of the media
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl ScheduleType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Manual => "manual",
+            Self::Duration => "duration",
+            Self::Absolute => "absolute",
+            Self::Plays => "plays",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DurationConfig {
     pub hours: u32,
     pub minutes: u32,
@@ -38,18 +77,136 @@ impl DurationConfig {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AbsoluteConfig {
     pub datetime: String, // ISO format
     pub timezone: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ScheduleConfig {
     #[serde(rename = "type")]
     pub schedule_type: ScheduleType,
     pub duration: Option<DurationConfig>,
     pub absolute: Option<AbsoluteConfig>,
+    #[serde(default)]
+    pub plays: Option<u32>, // 1 = play once
+}
+
+/// In/out points selecting the part of a media file that gets played
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaTrim {
+    pub start_seconds: Option<f64>,
+    pub end_seconds: Option<f64>,
+}
+
+impl MediaTrim {
+    pub fn is_empty(&self) -> bool {
+        self.start_seconds.is_none() && self.end_seconds.is_none()
+    }
+
+    pub fn start(&self) -> f64 {
+        self.start_seconds.unwrap_or(0.0)
+    }
+
+    /// Whichever points are set
+    pub fn points(&self) -> impl Iterator<Item = f64> {
+        self.start_seconds.into_iter().chain(self.end_seconds)
+    }
+
+    /// Length of the selected segment of a file lasting `duration` seconds
+    pub fn length(&self, duration: f64) -> f64 {
+        (self.end_seconds.unwrap_or(duration).min(duration) - self.start()).max(0.0)
+    }
+
+    /// Check the points against the probed duration of the file
+    pub fn validate(&self, duration: f64) -> Result<(), TrimError> {
+        if self.points().any(|p| !p.is_finite()) {
+            return Err(TrimError::NotFinite);
+        }
+        if self.points().any(|p| p < 0.0) {
+            return Err(TrimError::Negative);
+        }
+        if self.start() >= duration {
+            return Err(TrimError::StartPastEnd { start: self.start(), duration });
+        }
+        if let Some(end) = self.end_seconds {
+            if end > duration {
+                return Err(TrimError::EndPastEnd { end, duration });
+            }
+            if end <= self.start() {
+                return Err(TrimError::EndBeforeStart { start: self.start(), end });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why a pair of trim points does not select anything playable
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrimError {
+    NotFinite,
+    Negative,
+    EndBeforeStart { start: f64, end: f64 },
+    StartPastEnd { start: f64, duration: f64 },
+    EndPastEnd { end: f64, duration: f64 },
+}
+
+impl TrimError {
+    /// Message catalogue key
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::NotFinite => "trim.notFinite",
+            Self::Negative => "trim.negative",
+            Self::EndBeforeStart { .. } => "trim.endBeforeStart",
+            Self::StartPastEnd { .. } => "trim.startPastEnd",
+            Self::EndPastEnd { .. } => "trim.endPastEnd",
+        }
+    }
+
+    pub fn params(&self) -> Params {
+        let seconds = |value: f64| format!("{:.1}", value);
+        match *self {
+            Self::NotFinite | Self::Negative => Params::new(),
+            Self::EndBeforeStart { start, end } => i18n::params([("start", seconds(start)), ("end", seconds(end))]),
+            Self::StartPastEnd { start, duration } => {
+                i18n::params([("start", seconds(start)), ("duration", seconds(duration))])
+            }
+            Self::EndPastEnd { end, duration } => i18n::params([("end", seconds(end)), ("duration", seconds(duration))]),
+        }
+    }
+}
+
+/// Clips played around the main content
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipConfig {
+    pub intro_path: Option<String>, // Played once before the main loop
+    #[serde(default)]
+    pub intro_trim: MediaTrim,
+    pub outro_path: Option<String>, // Ends exactly at the scheduled stop
+    #[serde(default)]
+    pub outro_trim: MediaTrim,
+    #[serde(default)]
+    pub fade_out_seconds: u32, // Fade audio and video before the stop, 0 = off
+}
+
+/// Resume the media where a crashed or interrupted run left off
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResumeConfig {
+    pub enabled: bool,
+    pub rewind_seconds: u32, // Replay a little so viewers don't miss anything
+}
+
+impl Default for ResumeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            rewind_seconds: 10,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,9 +214,24 @@ pub struct ScheduleConfig {
 pub struct Stream {
     pub id: String,
     pub name: String,
-    pub youtube_key: String,
+    #[serde(default)]
+    pub channel_id: String, // Channel the stream publishes to
+    #[serde(default)]
+    pub channel_name: String,
+    #[serde(skip_serializing)]
+    pub youtube_key: String, // The channel's key, sealed by the vault - never sent to the webview
+    #[serde(default)]
+    pub masked_key: String, // What the webview shows instead, e.g. `••••mnop`
     pub video_path: String,
+    #[serde(default)]
+    pub trim: MediaTrim, // Part of the video that gets played and looped
+    #[serde(default)]
+    pub ingest_endpoints: Vec<String>, // Ordered primary/backup RTMP endpoints
+    #[serde(default)]
+    pub clips: ClipConfig,
     pub status: StreamStatus,
+    #[serde(default)]
+    pub status_reason: Option<String>, // Why the stream is in its current status, e.g. the error
     pub schedule: ScheduleConfig,
     pub started_at: Option<String>,
     pub stopped_at: Option<String>,
@@ -67,17 +239,253 @@ pub struct Stream {
     #[serde(default)]
     pub elapsed_seconds: Option<u64>,
     #[serde(default)]
-    pub last_elapsed_seconds: Option<u64>, // Store elapsed when stopped/errored
+    pub last_elapsed_seconds: Option<u64>, // Store elapsed when stopped/errored, checkpointed while live
+    #[serde(default)]
+    pub live_seconds: Option<u64>, // Live time of the current or last run, across automatic restarts
+    #[serde(default)]
+    pub last_seen_at: Option<String>, // Last heartbeat of a live run
+    #[serde(default)]
+    pub active_endpoint: Option<String>, // Ingest endpoint currently publishing
+    #[serde(default)]
+    pub resume: ResumeConfig,
+    #[serde(default)]
+    pub playback_position: Option<f64>, // Media seconds played when last checkpointed
+    #[serde(default)]
+    pub tags: Vec<String>, // Groups the stream belongs to, e.g. a client or campaign
+    #[serde(default)]
+    pub archived_at: Option<String>, // Set while the stream is in the archive
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StreamInput {
     pub name: String,
-    pub youtube_key: String,
+    #[serde(default)]
+    pub channel_id: Option<String>,
+    #[serde(default)]
+    pub channel_name: Option<String>, // Name of a channel created for the key, default the stream's
+    #[serde(default)]
+    pub youtube_key: String, // Without a channel: reuses the key's channel or creates one
     pub video_path: String,
+    #[serde(default)]
+    pub trim: MediaTrim,
+    #[serde(default)]
+    pub ingest_endpoints: Vec<String>, // Empty = YouTube primary then backup
+    #[serde(default)]
+    pub clips: ClipConfig,
+    #[serde(default)]
+    pub resume: ResumeConfig,
     pub schedule: ScheduleConfig,
-    pub created_at: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
     #[serde(default)]
     pub start_immediately: bool, // New field: start after save
 }
+
+/// Changes to a stream's settings - fields left out keep their value
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamPatch {
+    pub name: Option<String>,
+    pub channel_id: Option<String>,
+    pub youtube_key: Option<String>, // Moves the stream to the key's channel, created if new
+    pub video_path: Option<String>,
+    pub trim: Option<MediaTrim>,
+    pub ingest_endpoints: Option<Vec<String>>,
+    pub clips: Option<ClipConfig>,
+    pub resume: Option<ResumeConfig>,
+    pub schedule: Option<ScheduleConfig>,
+    pub tags: Option<Vec<String>>, // Replaces every tag
+}
+
+/// Settings streams made from a template share. Their name is always their own,
+/// and the file only comes from the template if every stream plays the same one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateSettings {
+    #[serde(default)]
+    pub video_path: Option<String>, // None = each stream picks its own
+    #[serde(default)]
+    pub trim: MediaTrim,
+    #[serde(default)]
+    pub ingest_endpoints: Vec<String>,
+    #[serde(default)]
+    pub clips: ClipConfig,
+    #[serde(default)]
+    pub resume: ResumeConfig,
+    pub schedule: ScheduleConfig,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// A named set of settings new streams start from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamTemplate {
+    pub id: String,
+    pub name: String,
+    pub channel_id: Option<String>, // None = each stream picks its own channel or key
+    #[serde(default)]
+    pub channel_name: Option<String>,
+    #[serde(flatten)]
+    pub settings: TemplateSettings,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateInput {
+    pub name: String,
+    #[serde(default)]
+    pub channel_id: Option<String>,
+    #[serde(flatten)]
+    pub settings: TemplateSettings,
+}
+
+/// A tag and how many streams carry it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tag {
+    pub name: String,
+    pub stream_count: u64,
+}
+
+/// A YouTube channel's stream key and where to publish it, shared by the streams that use it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Channel {
+    pub id: String,
+    pub name: String,
+    pub destination: Option<String>, // Ingest endpoint for streams without their own, None = YouTube
+    #[serde(skip_serializing)]
+    pub youtube_key: String, // Sealed by the vault
+    #[serde(default)]
+    pub masked_key: String,
+    #[serde(default)]
+    pub notes: String,
+    pub created_at: String,
+    #[serde(default)]
+    pub stream_ids: Vec<String>, // Streams publishing to the channel
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelInput {
+    pub name: String,
+    pub youtube_key: String,
+    #[serde(default)]
+    pub destination: Option<String>,
+    #[serde(default)]
+    pub notes: String,
+}
+
+/// Changes to a channel - fields left out keep their value, an empty destination clears it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelPatch {
+    pub name: Option<String>,
+    pub youtube_key: Option<String>,
+    pub destination: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Why a session - one go-live of a stream - ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionEndReason {
+    Manual,       // Stopped by the user
+    Schedule,     // Stopped by the schedule's deadline
+    Crash,        // FFmpeg died and could not be brought back
+    EndOfInput,   // Played the media the configured number of times
+    Interrupted,  // The app shut down while the stream was live
+}
+
+impl SessionEndReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Manual => "manual",
+            Self::Schedule => "schedule",
+            Self::Crash => "crash",
+            Self::EndOfInput => "endOfInput",
+            Self::Interrupted => "interrupted",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "manual" => Some(Self::Manual),
+            "schedule" => Some(Self::Schedule),
+            "crash" => Some(Self::Crash),
+            "endOfInput" => Some(Self::EndOfInput),
+            "interrupted" => Some(Self::Interrupted),
+            _ => None,
+        }
+    }
+}
+
+/// What went wrong when a session ended in a crash
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FailureKind {
+    RestartFailed,       // FFmpeg died and relaunching it failed
+    EndpointsExhausted,  // Every ingest endpoint failed repeatedly
+    StopFailed,          // FFmpeg outlived a stop request
+}
+
+impl FailureKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::RestartFailed => "restartFailed",
+            Self::EndpointsExhausted => "endpointsExhausted",
+            Self::StopFailed => "stopFailed",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "restartFailed" => Some(Self::RestartFailed),
+            "endpointsExhausted" => Some(Self::EndpointsExhausted),
+            "stopFailed" => Some(Self::StopFailed),
+            _ => None,
+        }
+    }
+}
+
+/// Encoder counters from FFmpeg's `-progress` output
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncoderStats {
+    pub frames: u64,
+    pub fps: Option<f64>,
+    pub bitrate_kbps: Option<f64>,
+    pub total_size_bytes: u64,
+    pub dropped_frames: u64,
+    pub duplicated_frames: u64,
+    pub speed: Option<f64>, // 1.0 = realtime
+}
+
+/// One go-live of a stream, from the start to whatever ended it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamSession {
+    pub id: String,
+    pub stream_id: String,
+    pub started_at: String,
+    pub ended_at: Option<String>, // None while the session is live
+    #[serde(default)]
+    pub scheduled_end: Option<String>, // When the schedule was due to stop it, if it had a deadline
+    pub end_reason: Option<SessionEndReason>,
+    pub failure_kind: Option<FailureKind>,
+    pub failure_reason: Option<String>,
+    pub elapsed_seconds: Option<u64>, // Uptime of the session's last FFmpeg process
+    #[serde(default)]
+    pub live_seconds: Option<u64>, // Live time across the session's automatic restarts
+    #[serde(default)]
+    pub last_seen_at: Option<String>, // Last heartbeat while live
+    pub restarts: u32, // FFmpeg relaunches after crashes within the session
+    #[serde(default)]
+    pub restart_times: Vec<String>, // When each relaunch happened, missing for sessions recorded before
+    pub encoder_stats: Option<EncoderStats>, // Last counters of the final FFmpeg process
+    #[serde(default)]
+    pub log_tail: Vec<String>, // Last FFmpeg log lines before the end
+}
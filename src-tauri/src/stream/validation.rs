@@ -0,0 +1,394 @@
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::i18n::{self, Locale, Message, Params};
+use crate::stream::ingest;
+use crate::stream::scheduler::{self, DeadlineError};
+use crate::stream::types::{Channel, MediaTrim, ScheduleConfig, ScheduleType, Stream, TrimError};
+
+/// Longest tag, in characters
+pub const MAX_TAG_LENGTH: usize = 40;
+
+/// Stable, machine-readable reason a field was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ErrorCode {
+    Required,
+    FileNotFound,
+    UnreadableMedia,
+    InvalidTrim,
+    InvalidUrl,
+    InvalidTimezone,
+    InvalidDateTime,
+    InPast,
+    OutOfRange,
+    UnknownChannel,
+    InUse,
+    InvalidValue,
+}
+
+/// One rejected field. `field` is the camelCase path the frontend sent, e.g. `schedule.duration`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub code: ErrorCode,
+    /// Message catalogue key and the values it is rendered with
+    pub key: &'static str,
+    pub params: Params,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, code: ErrorCode, key: &'static str, params: Params) -> Self {
+        Self {
+            field: field.into(),
+            code,
+            key,
+            params,
+        }
+    }
+
+    pub fn trim(field: impl Into<String>, error: &TrimError) -> Self {
+        Self::new(field, ErrorCode::InvalidTrim, error.key(), error.params())
+    }
+
+    pub fn message(&self, locale: Locale) -> String {
+        i18n::translate(locale, self.key, &self.params)
+    }
+
+    /// `field: message`, as listed in a validation error's own message
+    pub fn to_message(&self) -> Message {
+        Message::new("field.described", &i18n::params([("field", self.field.clone())]))
+            .with("message", vec![Message::new(self.key, &self.params)])
+    }
+
+    pub fn describe(&self, locale: Locale) -> String {
+        self.to_message().render(locale)
+    }
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.describe(Locale::En))
+    }
+}
+
+/// Sent as `{ field, code, params, message }`, the message in the operator's language
+impl Serialize for FieldError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("FieldError", 4)?;
+        state.serialize_field("field", &self.field)?;
+        state.serialize_field("code", &self.code)?;
+        state.serialize_field("params", &self.params)?;
+        state.serialize_field("message", &self.message(i18n::locale()))?;
+        state.end()
+    }
+}
+
+/// Check everything about a stream that can be checked without probing its media
+pub fn validate_stream(stream: &Stream, now: DateTime<Utc>) -> Vec<FieldError> {
+    let mut errors = validate_settings(stream);
+    errors.extend(validate_schedule(&stream.schedule, now));
+    errors
+}
+
+/// Everything but the schedule - a stream's past deadline stays valid once it has run
+pub fn validate_settings(stream: &Stream) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+
+    if stream.name.trim().is_empty() {
+        errors.push(FieldError::new("name", ErrorCode::Required, "field.nameRequired", Params::new()));
+    }
+    if stream.youtube_key.trim().is_empty() {
+        errors.push(FieldError::new("youtubeKey", ErrorCode::Required, "field.keyRequired", Params::new()));
+    }
+
+    if stream.video_path.trim().is_empty() {
+        errors.push(FieldError::new("videoPath", ErrorCode::Required, "field.videoRequired", Params::new()));
+    } else {
+        check_file("videoPath", &stream.video_path, &mut errors);
+    }
+    check_trim("trim", &stream.trim, &mut errors);
+
+    if let Some(intro) = &stream.clips.intro_path {
+        check_file("clips.introPath", intro, &mut errors);
+    }
+    check_trim("clips.introTrim", &stream.clips.intro_trim, &mut errors);
+    if let Some(outro) = &stream.clips.outro_path {
+        check_file("clips.outroPath", outro, &mut errors);
+    }
+    check_trim("clips.outroTrim", &stream.clips.outro_trim, &mut errors);
+
+    for (index, endpoint) in stream.ingest_endpoints.iter().enumerate() {
+        check_url(&format!("ingestEndpoints[{}]", index), endpoint, &mut errors);
+    }
+    for (index, tag) in stream.tags.iter().enumerate() {
+        check_tag(&format!("tags[{}]", index), tag, &mut errors);
+    }
+
+    errors
+}
+
+/// Check a channel before its key is sealed
+pub fn validate_channel(channel: &Channel) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+
+    if channel.name.trim().is_empty() {
+        errors.push(FieldError::new("name", ErrorCode::Required, "field.channelNameRequired", Params::new()));
+    }
+    if channel.youtube_key.trim().is_empty() {
+        errors.push(FieldError::new("youtubeKey", ErrorCode::Required, "field.keyRequired", Params::new()));
+    }
+    if let Some(destination) = &channel.destination {
+        check_url("destination", destination, &mut errors);
+    }
+
+    errors
+}
+
+/// Reject schedules that would silently fall back to manual or stop at once
+pub fn validate_schedule(schedule: &ScheduleConfig, now: DateTime<Utc>) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+
+    match schedule.schedule_type {
+        ScheduleType::Manual => {}
+        ScheduleType::Duration => match &schedule.duration {
+            None => errors.push(FieldError::new(
+                "schedule.duration",
+                ErrorCode::Required,
+                "field.durationRequired",
+                Params::new(),
+            )),
+            Some(duration) if duration.to_seconds() == 0 => errors.push(FieldError::new(
+                "schedule.duration",
+                ErrorCode::OutOfRange,
+                "field.durationZero",
+                Params::new(),
+            )),
+            Some(_) => {}
+        },
+        ScheduleType::Absolute => match &schedule.absolute {
+            None => errors.push(FieldError::new(
+                "schedule.absolute",
+                ErrorCode::Required,
+                "field.stopTimeRequired",
+                Params::new(),
+            )),
+            Some(absolute) => {
+                let params = i18n::params([
+                    ("datetime", absolute.datetime.clone()),
+                    ("timezone", absolute.timezone.clone()),
+                ]);
+                match scheduler::resolve_deadline(&absolute.datetime, &absolute.timezone) {
+                    Ok(deadline) if deadline <= now => errors.push(FieldError::new(
+                        "schedule.absolute.datetime",
+                        ErrorCode::InPast,
+                        "field.stopTimeInPast",
+                        params,
+                    )),
+                    Ok(_) => {}
+                    Err(DeadlineError::InvalidTimezone) => errors.push(FieldError::new(
+                        "schedule.absolute.timezone",
+                        ErrorCode::InvalidTimezone,
+                        "field.invalidTimezone",
+                        params,
+                    )),
+                    Err(DeadlineError::InvalidDateTime) => errors.push(FieldError::new(
+                        "schedule.absolute.datetime",
+                        ErrorCode::InvalidDateTime,
+                        "field.invalidDateTime",
+                        params,
+                    )),
+                    Err(DeadlineError::AmbiguousLocalTime) => errors.push(FieldError::new(
+                        "schedule.absolute.datetime",
+                        ErrorCode::InvalidDateTime,
+                        "field.ambiguousTime",
+                        params,
+                    )),
+                }
+            }
+        },
+        ScheduleType::Plays => {
+            if schedule.plays == Some(0) {
+                errors.push(FieldError::new("schedule.plays", ErrorCode::OutOfRange, "field.playsZero", Params::new()));
+            }
+        }
+    }
+
+    errors
+}
+
+fn check_file(field: &str, path: &str, errors: &mut Vec<FieldError>) {
+    if !Path::new(path).is_file() {
+        errors.push(FieldError::new(
+            field,
+            ErrorCode::FileNotFound,
+            "field.fileNotFound",
+            i18n::params([("path", path.to_string())]),
+        ));
+    }
+}
+
+/// Trim points that are wrong whatever the media's length
+fn check_trim(field: &str, trim: &MediaTrim, errors: &mut Vec<FieldError>) {
+    if trim.points().any(|p| !p.is_finite()) {
+        errors.push(FieldError::trim(field, &TrimError::NotFinite));
+    } else if trim.points().any(|p| p < 0.0) {
+        errors.push(FieldError::trim(field, &TrimError::Negative));
+    } else if let Some(end) = trim.end_seconds.filter(|end| *end <= trim.start()) {
+        errors.push(FieldError::trim(field, &TrimError::EndBeforeStart { start: trim.start(), end }));
+    }
+}
+
+/// A tag given on its own, e.g. to a bulk command
+pub fn validate_tag(tag: &str) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    check_tag("tag", tag, &mut errors);
+    errors
+}
+
+fn check_tag(field: &str, tag: &str, errors: &mut Vec<FieldError>) {
+    if tag.trim().is_empty() {
+        errors.push(FieldError::new(field, ErrorCode::Required, "field.tagRequired", Params::new()));
+    } else if tag.trim().chars().count() > MAX_TAG_LENGTH {
+        errors.push(FieldError::new(
+            field,
+            ErrorCode::OutOfRange,
+            "field.tagTooLong",
+            i18n::params([("tag", tag.trim().to_string()), ("max", MAX_TAG_LENGTH.to_string())]),
+        ));
+    }
+}
+
+fn check_url(field: &str, url: &str, errors: &mut Vec<FieldError>) {
+    let scheme_ok = url.starts_with("rtmp://") || url.starts_with("rtmps://");
+    if !scheme_ok || ingest::host_port(url).is_none() {
+        errors.push(FieldError::new(
+            field,
+            ErrorCode::InvalidUrl,
+            "field.invalidUrl",
+            i18n::params([("url", url.to_string())]),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::types::{AbsoluteConfig, DurationConfig};
+
+    fn stream() -> Stream {
+        serde_json::from_value(serde_json::json!({
+            "id": "abc",
+            "name": "Lofi radio",
+            "youtubeKey": "aaaa-bbbb",
+            "videoPath": file!(),
+            "status": "idle",
+            "schedule": { "type": "manual", "duration": null, "absolute": null },
+            "startedAt": null,
+            "stoppedAt": null,
+            "createdAt": "2024-05-07T10:00:00+00:00",
+        }))
+        .unwrap()
+    }
+
+    fn now() -> DateTime<Utc> {
+        "2024-05-07T10:00:00Z".parse().unwrap()
+    }
+
+    fn codes(errors: &[FieldError]) -> Vec<(&str, ErrorCode)> {
+        errors.iter().map(|e| (e.field.as_str(), e.code)).collect()
+    }
+
+    #[test]
+    fn test_valid_stream_passes() {
+        assert_eq!(validate_stream(&stream(), now()), vec![]);
+    }
+
+    #[test]
+    fn test_settings_errors_name_their_fields() {
+        let mut stream = stream();
+        stream.youtube_key = "  ".into();
+        stream.video_path = "/no/such/video.mp4".into();
+        stream.trim = MediaTrim { start_seconds: Some(30.0), end_seconds: Some(10.0) };
+        stream.ingest_endpoints = vec!["rtmp://a.rtmp.youtube.com/live2".into(), "http://example.com".into()];
+        stream.tags = vec!["client a".into(), "x".repeat(MAX_TAG_LENGTH + 1)];
+
+        assert_eq!(
+            codes(&validate_settings(&stream)),
+            vec![
+                ("youtubeKey", ErrorCode::Required),
+                ("videoPath", ErrorCode::FileNotFound),
+                ("trim", ErrorCode::InvalidTrim),
+                ("ingestEndpoints[1]", ErrorCode::InvalidUrl),
+                ("tags[1]", ErrorCode::OutOfRange),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trim_points_must_be_finite() {
+        let mut stream = stream();
+        for point in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            stream.trim = MediaTrim { start_seconds: None, end_seconds: Some(point) };
+            let errors = validate_settings(&stream);
+            assert_eq!(codes(&errors), vec![("trim", ErrorCode::InvalidTrim)]);
+            assert_eq!(errors[0].key, "trim.notFinite");
+            assert_eq!(stream.trim.validate(60.0), Err(TrimError::NotFinite));
+        }
+    }
+
+    #[test]
+    fn test_channel_errors_name_their_fields() {
+        let mut channel = Channel {
+            id: "abc".into(),
+            name: "Music".into(),
+            destination: Some("rtmp://a.rtmp.youtube.com/live2".into()),
+            youtube_key: "aaaa-bbbb".into(),
+            masked_key: "••••bbbb".into(),
+            notes: String::new(),
+            created_at: "2024-05-07T10:00:00+00:00".into(),
+            stream_ids: Vec::new(),
+        };
+        assert_eq!(validate_channel(&channel), vec![]);
+
+        channel.name = " ".into();
+        channel.youtube_key = String::new();
+        channel.destination = Some("http://example.com".into());
+        assert_eq!(
+            codes(&validate_channel(&channel)),
+            vec![
+                ("name", ErrorCode::Required),
+                ("youtubeKey", ErrorCode::Required),
+                ("destination", ErrorCode::InvalidUrl),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_schedules_that_would_fall_back_are_rejected() {
+        let mut schedule = stream().schedule;
+        schedule.schedule_type = ScheduleType::Duration;
+        assert_eq!(codes(&validate_schedule(&schedule, now())), vec![("schedule.duration", ErrorCode::Required)]);
+
+        schedule.duration = Some(DurationConfig { hours: 0, minutes: 0, seconds: 0 });
+        assert_eq!(codes(&validate_schedule(&schedule, now())), vec![("schedule.duration", ErrorCode::OutOfRange)]);
+
+        schedule.schedule_type = ScheduleType::Absolute;
+        schedule.absolute = Some(AbsoluteConfig { datetime: "2024-05-07T16:00".into(), timezone: "Mars/Olympus".into() });
+        assert_eq!(
+            codes(&validate_schedule(&schedule, now())),
+            vec![("schedule.absolute.timezone", ErrorCode::InvalidTimezone)]
+        );
+
+        // 16:00 in Ho Chi Minh City is 09:00 UTC, an hour before now
+        schedule.absolute = Some(AbsoluteConfig { datetime: "2024-05-07T16:00".into(), timezone: "Asia/Ho_Chi_Minh".into() });
+        assert_eq!(
+            codes(&validate_schedule(&schedule, now())),
+            vec![("schedule.absolute.datetime", ErrorCode::InPast)]
+        );
+
+        schedule.absolute = Some(AbsoluteConfig { datetime: "2024-05-07T18:00".into(), timezone: "Asia/Ho_Chi_Minh".into() });
+        assert_eq!(validate_schedule(&schedule, now()), vec![]);
+    }
+}
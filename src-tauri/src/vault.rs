@@ -0,0 +1,320 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use aes_gcm_siv::aead::{Aead, KeyInit, OsRng};
+use aes_gcm_siv::{Aes256GcmSiv, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use thiserror::Error;
+
+/// Prefix of a sealed value: `enc:v1:<key id>:<base64 ciphertext>`
+const SEALED_PREFIX: &str = "enc:v1:";
+
+#[derive(Error, Debug)]
+pub enum VaultError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid master key file: {0}")]
+    InvalidKeyFile(PathBuf),
+    #[error("Stream key was sealed with an unknown master key")]
+    UnknownKey,
+    #[error("Stream key could not be decrypted")]
+    Undecryptable,
+}
+
+/// A master key and the id sealed values name it by
+#[derive(Clone)]
+struct MasterKey {
+    id: String,
+    cipher: Aes256GcmSiv,
+    line: String, // `<id>:<base64 key>`, as stored
+}
+
+impl MasterKey {
+    fn read(path: &Path) -> Result<Option<Self>, VaultError> {
+        Ok(Self::read_all(path)?.into_iter().next())
+    }
+
+    /// The keys in a file holding one per line, none if there is no file
+    fn read_all(path: &Path) -> Result<Vec<Self>, VaultError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let invalid = || VaultError::InvalidKeyFile(path.to_path_buf());
+        contents.lines().map(str::trim).filter(|line| !line.is_empty()).map(|line| {
+            let (id, key) = line.split_once(':').ok_or_else(invalid)?;
+            let key = BASE64.decode(key).map_err(|_| invalid())?;
+            let cipher = Aes256GcmSiv::new_from_slice(&key).map_err(|_| invalid())?;
+            Ok(Self { id: id.to_string(), cipher, line: line.to_string() })
+        }).collect()
+    }
+}
+
+/// Seals stream keys for storage with the app's master key.
+///
+/// The master key lives in a file next to the database, readable by the owner only. Sealing is
+/// deterministic (AES-GCM-SIV with a fixed nonce): the same stream key seals to the same value,
+/// so keys can be compared without decrypting them, and nothing but that equality leaks.
+///
+/// Keys a rotation replaced are kept in a `.retired` file beside it, so database snapshots
+/// taken before the rotation can still be restored.
+#[derive(Clone)]
+pub struct Vault {
+    path: PathBuf,
+    current: MasterKey,
+    previous: Option<MasterKey>, // Still opens values sealed before a rotation
+    retired: Vec<MasterKey>,     // Open values in snapshots sealed before earlier rotations
+    pending: bool,               // `current` is only in the `.new` file until `commit`
+}
+
+impl Vault {
+    /// Load the master key at `path`, creating one if there is none. A `.new` key left by an
+    /// interrupted rotation or first run becomes current - reseal the database, then `commit`.
+    pub fn load(path: &Path) -> Result<Self, VaultError> {
+        let pending_path = Self::pending_path(path);
+        let current = MasterKey::read(path)?;
+        let retired = MasterKey::read_all(&Self::retired_path(path))?;
+
+        match MasterKey::read(&pending_path)? {
+            Some(pending) => Ok(Self {
+                path: path.to_path_buf(),
+                current: pending,
+                previous: current,
+                retired,
+                pending: true,
+            }),
+            None => match current {
+                Some(current) => Ok(Self { path: path.to_path_buf(), current, previous: None, retired, pending: false }),
+                None => {
+                    create_key_file(&pending_path)?;
+                    Self::load(path)
+                }
+            },
+        }
+    }
+
+    /// Start a rotation: a fresh master key, written aside until `commit`
+    pub fn rotate(&self) -> Result<Self, VaultError> {
+        let pending_path = Self::pending_path(&self.path);
+        create_key_file(&pending_path)?;
+        let pending = MasterKey::read(&pending_path)?.ok_or(VaultError::InvalidKeyFile(pending_path))?;
+
+        Ok(Self {
+            path: self.path.clone(),
+            current: pending,
+            previous: Some(self.current.clone()),
+            retired: self.retired.clone(),
+            pending: true,
+        })
+    }
+
+    /// Make a pending key the master key, once every stored value is sealed with it. The key it
+    /// replaces is retired rather than dropped, as snapshots may still be sealed with it.
+    pub fn commit(&mut self) -> Result<(), VaultError> {
+        if self.pending {
+            if let Some(previous) = self.previous.take() {
+                // Retired first - a crash before the rename commits again and finds it there
+                if !self.retired.iter().any(|key| key.id == previous.id) {
+                    let mut options = std::fs::OpenOptions::new();
+                    options.append(true).create(true);
+                    let mut file = open_private(&Self::retired_path(&self.path), &mut options)?;
+                    writeln!(file, "{}", previous.line)?;
+                    file.sync_all()?;
+                    self.retired.push(previous);
+                }
+            }
+            std::fs::rename(Self::pending_path(&self.path), &self.path)?;
+            self.pending = false;
+        }
+        Ok(())
+    }
+
+    /// Drop a pending key, leaving the master key as it was
+    pub fn discard(self) -> Result<(), VaultError> {
+        if self.pending {
+            std::fs::remove_file(Self::pending_path(&self.path))?;
+        }
+        Ok(())
+    }
+
+    pub fn seal(&self, plain: &str) -> String {
+        let ciphertext = self.current.cipher
+            .encrypt(&Nonce::default(), plain.as_bytes())
+            .expect("sealing a stream key cannot fail");
+        format!("{}{}:{}", SEALED_PREFIX, self.current.id, BASE64.encode(ciphertext))
+    }
+
+    pub fn open(&self, sealed: &str) -> Result<String, VaultError> {
+        let (id, data) = sealed.strip_prefix(SEALED_PREFIX)
+            .and_then(|rest| rest.split_once(':'))
+            .ok_or(VaultError::Undecryptable)?;
+        let key = [Some(&self.current), self.previous.as_ref()]
+            .into_iter()
+            .flatten()
+            .chain(&self.retired)
+            .find(|key| key.id == id)
+            .ok_or(VaultError::UnknownKey)?;
+
+        let ciphertext = BASE64.decode(data).map_err(|_| VaultError::Undecryptable)?;
+        let plain = key.cipher
+            .decrypt(&Nonce::default(), ciphertext.as_ref())
+            .map_err(|_| VaultError::Undecryptable)?;
+        String::from_utf8(plain).map_err(|_| VaultError::Undecryptable)
+    }
+
+    /// Whether a stored value is already sealed with the current master key
+    pub fn is_current(&self, value: &str) -> bool {
+        value.strip_prefix(SEALED_PREFIX)
+            .and_then(|rest| rest.split_once(':'))
+            .is_some_and(|(id, _)| id == self.current.id)
+    }
+
+    /// A stored key in the clear, whether it was sealed or stored before keys were sealed
+    pub fn reveal(&self, value: &str) -> Result<String, VaultError> {
+        if value.starts_with(SEALED_PREFIX) {
+            self.open(value)
+        } else {
+            Ok(value.to_string())
+        }
+    }
+
+    fn pending_path(path: &Path) -> PathBuf {
+        Self::sibling(path, ".new")
+    }
+
+    fn retired_path(path: &Path) -> PathBuf {
+        Self::sibling(path, ".retired")
+    }
+
+    fn sibling(path: &Path, suffix: &str) -> PathBuf {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(suffix);
+        path.with_file_name(name)
+    }
+}
+
+/// Write a fresh random master key, as `<id>:<base64 key>`, to a file only the current user can read
+fn create_key_file(path: &Path) -> Result<(), VaultError> {
+    let id = &uuid::Uuid::new_v4().simple().to_string()[..8];
+    let contents = format!("{}:{}\n", id, BASE64.encode(Aes256GcmSiv::generate_key(&mut OsRng)));
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    let mut file = open_private(path, &mut options)?;
+    file.write_all(contents.as_bytes())?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Open a key file with `options`, restricted to the current user before anything is written to it
+fn open_private(path: &Path, options: &mut std::fs::OpenOptions) -> Result<std::fs::File, VaultError> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let file = options.open(path)?;
+    #[cfg(windows)]
+    restrict_to_owner(path)?;
+    Ok(file)
+}
+
+/// Replace the inherited permissions of `path` with full control for the current user alone
+#[cfg(windows)]
+fn restrict_to_owner(path: &Path) -> Result<(), VaultError> {
+    let user = std::env::var("USERNAME").map_err(|_| VaultError::InvalidKeyFile(path.to_path_buf()))?;
+    let status = std::process::Command::new("icacls")
+        .arg(path)
+        .args(["/inheritance:r", "/grant:r"])
+        .arg(format!("{}:F", user))
+        .stdout(std::process::Stdio::null())
+        .status()?;
+    if !status.success() {
+        return Err(std::io::Error::other(format!("icacls exited with {}", status)).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_path() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ylm-vault-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("streams_test.key")
+    }
+
+    #[test]
+    fn test_sealing_is_deterministic_and_opens() {
+        let path = key_path();
+        let mut vault = Vault::load(&path).unwrap();
+        vault.commit().unwrap();
+
+        let sealed = vault.seal("aaaa-bbbb");
+        assert_ne!(sealed, "aaaa-bbbb");
+        assert_eq!(sealed, vault.seal("aaaa-bbbb"));
+        assert!(vault.is_current(&sealed));
+        assert_eq!(vault.open(&sealed).unwrap(), "aaaa-bbbb");
+
+        // The same key file opens it after a restart
+        assert_eq!(Vault::load(&path).unwrap().open(&sealed).unwrap(), "aaaa-bbbb");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn test_interrupted_rotation_resumes_on_load() {
+        let path = key_path();
+        let mut vault = Vault::load(&path).unwrap();
+        vault.commit().unwrap();
+        let sealed = vault.seal("aaaa-bbbb");
+
+        // Crash after writing the new key, before the database was resealed
+        let rotated = vault.rotate().unwrap();
+        drop(rotated);
+
+        let mut resumed = Vault::load(&path).unwrap();
+        assert!(!resumed.is_current(&sealed));
+        let resealed = resumed.seal(&resumed.reveal(&sealed).unwrap());
+        resumed.commit().unwrap();
+
+        let reloaded = Vault::load(&path).unwrap();
+        assert_eq!(reloaded.open(&resealed).unwrap(), "aaaa-bbbb");
+        assert!(reloaded.is_current(&resealed) && !reloaded.is_current(&sealed));
+
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn test_retired_keys_still_open_old_values() {
+        let path = key_path();
+        let mut vault = Vault::load(&path).unwrap();
+        vault.commit().unwrap();
+        let first = vault.seal("aaaa-bbbb");
+
+        // Two rotations later, a value from a snapshot taken before either still opens
+        for _ in 0..2 {
+            vault = vault.rotate().unwrap();
+            vault.commit().unwrap();
+        }
+        let reloaded = Vault::load(&path).unwrap();
+        assert!(!reloaded.is_current(&first));
+        assert_eq!(reloaded.open(&first).unwrap(), "aaaa-bbbb");
+        assert_eq!(MasterKey::read_all(&Vault::retired_path(&path)).unwrap().len(), 2);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(Vault::retired_path(&path)).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+}
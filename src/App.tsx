@@ -1,12 +1,19 @@
-import { useState, useEffect } from "react";
+import { useState, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { StreamList } from "./components/StreamList";
+import { listen } from "@tauri-apps/api/event";
+import { StreamList, PAGE_SIZE } from "./components/StreamList";
 import { AddStreamDialog } from "./components/AddStreamDialog";
 import { Header } from "./components/Header";
-import type { Stream } from "./types";
+import type { Stream, StreamEvent, StreamInput, StreamPage, StreamQuery } from "./types";
 
 function App() {
   const [streams, setStreams] = useState<Stream[]>([]);
+  const [page, setPage] = useState(1);
+  const [total, setTotal] = useState(0);
+  const [liveCount, setLiveCount] = useState(0);
+  // Cursor each visited page starts at, and where the one after it starts - undefined for the first
+  const cursors = useRef<(string | undefined)[]>([undefined]);
+  const pageRef = useRef(1);
   const [isAddDialogOpen, setIsAddDialogOpen] = useState(false);
   const [instanceId, setInstanceId] = useState<string>("");
 
@@ -32,16 +39,30 @@ function App() {
     }
   };
 
-  const loadStreams = async () => {
+  // Fetch one page of streams, the one on screen unless told otherwise
+  const loadStreams = async (target = pageRef.current) => {
     try {
-      const result = await invoke<Stream[]>("get_streams");
-      setStreams(result);
+      const query: StreamQuery = { limit: PAGE_SIZE, cursor: cursors.current[target - 1] };
+      const [result, live] = await Promise.all([
+        invoke<StreamPage>("get_streams", { query }),
+        invoke<StreamPage>("get_streams", { query: { statuses: ["live"], limit: 1 } }),
+      ]);
+      // A page emptied by deletes falls back to the one before it
+      if (result.streams.length === 0 && target > 1) {
+        return loadStreams(target - 1);
+      }
+      cursors.current[target] = result.nextCursor ?? undefined;
+      pageRef.current = target;
+      setPage(target);
+      setStreams(result.streams);
+      setTotal(result.total);
+      setLiveCount(live.total);
     } catch (error) {
       console.error("Failed to load streams:", error);
     }
   };
 
-  const handleAddStream = async (stream: Omit<Stream, "id" | "status" | "startedAt" | "stoppedAt" | "elapsedSeconds" | "lastElapsedSeconds"> & { startImmediately: boolean }) => {
+  const handleAddStream = async (stream: StreamInput) => {
     try {
       await invoke("add_stream", { stream });
       await loadStreams();
@@ -78,10 +99,36 @@ function App() {
     }
   };
 
-  // Poll stream status every 2 seconds
+  // Follow backend events instead of polling
   useEffect(() => {
-    const interval = setInterval(loadStreams, 2000);
-    return () => clearInterval(interval);
+    const unlisten = listen<StreamEvent>("stream-event", ({ payload }) => {
+      switch (payload.type) {
+        case "statusChanged":
+          loadStreams();
+          break;
+        case "stats":
+          setStreams((current) =>
+            current.map((s) =>
+              s.id === payload.streamId
+                ? {
+                    ...s,
+                    elapsedSeconds: payload.elapsedSeconds,
+                    liveSeconds: payload.liveSeconds,
+                    playbackPosition: payload.playbackPosition ?? undefined,
+                    activeEndpoint: payload.activeEndpoint ?? undefined,
+                  }
+                : s
+            )
+          );
+          break;
+        case "error":
+          console.error(`Stream ${payload.streamId} error:`, payload.reason);
+          break;
+      }
+    });
+    return () => {
+      unlisten.then((stop) => stop());
+    };
   }, []);
 
   return (
@@ -89,13 +136,17 @@ function App() {
       <Header 
         instanceId={instanceId} 
         onAddClick={() => setIsAddDialogOpen(true)} 
-        streamCount={streams.length}
-        liveCount={streams.filter(s => s.status === "live").length}
+        streamCount={total}
+        liveCount={liveCount}
       />
       
       <main className="max-w-7xl mx-auto px-4 sm:px-6 lg:px-8 py-6">
         <StreamList
           streams={streams}
+          page={page}
+          total={total}
+          hasNextPage={cursors.current[page] !== undefined}
+          onPageChange={loadStreams}
           onStart={handleStartStream}
           onStop={handleStopStream}
           onDelete={handleDeleteStream}
@@ -4,13 +4,13 @@ import { Input } from "./ui/input";
 import { Label } from "./ui/label";
 import { Select } from "./ui/select";
 import { Dialog, DialogContent, DialogHeader, DialogTitle, DialogDescription } from "./ui/dialog";
-import type { Stream, ScheduleType, ScheduleConfig } from "../types";
+import type { StreamInput, ScheduleType, ScheduleConfig } from "../types";
 import { FolderOpen, Key, Video, Clock, Globe, Zap, Timer } from "lucide-react";
 
 interface AddStreamDialogProps {
   open: boolean;
   onOpenChange: (open: boolean) => void;
-  onSubmit: (stream: Omit<Stream, "id" | "status" | "startedAt" | "stoppedAt" | "elapsedSeconds" | "lastElapsedSeconds"> & { startImmediately: boolean }) => void;
+  onSubmit: (stream: StreamInput) => void;
 }
 
 const TIMEZONES = [
@@ -73,7 +73,6 @@ export function AddStreamDialog({ open, onOpenChange, onSubmit }: AddStreamDialo
       youtubeKey,
       videoPath,
       schedule,
-      createdAt: new Date().toISOString(),
       startImmediately,
     });
 
@@ -5,14 +5,18 @@ import { Badge } from "./ui/badge";
 import type { Stream } from "../types";
 
 interface StreamListProps {
-  streams: Stream[];
+  streams: Stream[]; // The page on screen
+  page: number; // 1-based
+  total: number; // Streams across all pages
+  hasNextPage: boolean;
+  onPageChange: (page: number) => void;
   onStart: (id: string) => void;
   onStop: (id: string) => void;
   onDelete: (id: string) => void;
   onEdit?: (stream: Stream) => void;
 }
 
-const PAGE_SIZE = 20;
+export const PAGE_SIZE = 20;
 
 function formatTime(dateStr?: string): string {
   if (!dateStr) return "-";
@@ -63,17 +67,19 @@ function getStatusBadge(status: Stream["status"]) {
 
 export function StreamList({ 
   streams, 
+  page,
+  total,
+  hasNextPage,
+  onPageChange,
   onStart, 
   onStop, 
   onDelete, 
   onEdit
 }: StreamListProps) {
   const [selectedIds, setSelectedIds] = useState<Set<string>>(new Set());
-  const [currentPage, setCurrentPage] = useState(1);
 
-  const totalPages = Math.ceil(streams.length / PAGE_SIZE);
-  const startIndex = (currentPage - 1) * PAGE_SIZE;
-  const paginatedStreams = streams.slice(startIndex, startIndex + PAGE_SIZE);
+  const totalPages = Math.ceil(total / PAGE_SIZE);
+  const startIndex = (page - 1) * PAGE_SIZE;
 
   const toggleSelect = (id: string) => {
     const newSelected = new Set(selectedIds);
@@ -86,10 +92,10 @@ export function StreamList({
   };
 
   const toggleSelectAll = () => {
-    if (selectedIds.size === paginatedStreams.length) {
+    if (selectedIds.size === streams.length) {
       setSelectedIds(new Set());
     } else {
-      setSelectedIds(new Set(paginatedStreams.map(s => s.id)));
+      setSelectedIds(new Set(streams.map(s => s.id)));
     }
   };
 
@@ -123,8 +129,8 @@ export function StreamList({
     setSelectedIds(new Set());
   };
 
-  const isAllSelected = paginatedStreams.length > 0 && selectedIds.size === paginatedStreams.length;
-  const isSomeSelected = selectedIds.size > 0 && selectedIds.size < paginatedStreams.length;
+  const isAllSelected = streams.length > 0 && selectedIds.size === streams.length;
+  const isSomeSelected = selectedIds.size > 0 && selectedIds.size < streams.length;
 
   if (streams.length === 0) {
     return (
@@ -158,7 +164,7 @@ export function StreamList({
           </Button>
           <Button variant="outline" size="sm" onClick={handleBatchDelete}>
             <Trash2 className="mr-1 h-3 w-3" />
-            Xóa
+            Lưu trữ
           </Button>
         </div>
       )}
@@ -187,7 +193,7 @@ export function StreamList({
             </tr>
           </thead>
           <tbody className="divide-y divide-slate-100">
-            {paginatedStreams.map((stream, index) => (
+            {streams.map((stream, index) => (
               <tr 
                 key={stream.id} 
                 className={`hover:bg-slate-50 transition-colors ${
@@ -219,6 +225,21 @@ export function StreamList({
                   <div className="text-[10px] text-slate-400 truncate max-w-[280px]" title={stream.videoPath}>
                     {stream.videoPath.split("/").pop()}
                   </div>
+                  <div className="text-[10px] text-slate-400 font-mono">{stream.channelName} · {stream.maskedKey}</div>
+                  {stream.tags.length > 0 && (
+                    <div className="flex flex-wrap gap-1 mt-0.5">
+                      {stream.tags.map((tag) => (
+                        <span key={tag} className="text-[10px] px-1.5 rounded bg-slate-100 text-slate-500">
+                          {tag}
+                        </span>
+                      ))}
+                    </div>
+                  )}
+                  {stream.status === "live" && stream.activeEndpoint && (
+                    <div className="text-[10px] text-slate-400 truncate max-w-[280px]" title={stream.activeEndpoint}>
+                      {stream.activeEndpoint.split("://").pop()?.split(/[/?]/)[0]}
+                    </div>
+                  )}
                 </td>
 
                 {/* Start Time */}
@@ -257,7 +278,7 @@ export function StreamList({
                 </td>
 
                 {/* Status */}
-                <td className="px-3 py-2">
+                <td className="px-3 py-2" title={stream.statusReason ?? undefined}>
                   {getStatusBadge(stream.status)}
                 </td>
 
@@ -304,7 +325,7 @@ export function StreamList({
                       size="icon-sm"
                       onClick={() => onDelete(stream.id)}
                       disabled={stream.status === "live" || stream.status === "stopping"}
-                      title="Xóa"
+                      title="Chuyển vào lưu trữ"
                       className="text-slate-400 hover:text-red-500"
                     >
                       <Trash2 className="h-4 w-4" />
@@ -321,25 +342,25 @@ export function StreamList({
       {totalPages > 1 && (
         <div className="flex items-center justify-between px-2">
           <div className="text-slate-500">
-            Hiển thị {startIndex + 1}-{Math.min(startIndex + PAGE_SIZE, streams.length)} / {streams.length} luồng
+            Hiển thị {startIndex + 1}-{startIndex + streams.length} / {total} luồng
           </div>
           <div className="flex items-center gap-1">
             <Button
               variant="outline"
               size="icon-sm"
-              onClick={() => setCurrentPage(p => Math.max(1, p - 1))}
-              disabled={currentPage === 1}
+              onClick={() => onPageChange(page - 1)}
+              disabled={page === 1}
             >
               <ChevronLeft className="h-3 w-3" />
             </Button>
             <span className="text-slate-600 px-2">
-              {currentPage} / {totalPages}
+              {page} / {totalPages}
             </span>
             <Button
               variant="outline"
               size="icon-sm"
-              onClick={() => setCurrentPage(p => Math.min(totalPages, p + 1))}
-              disabled={currentPage === totalPages}
+              onClick={() => onPageChange(page + 1)}
+              disabled={!hasNextPage}
             >
               <ChevronRight className="h-3 w-3" />
             </Button>
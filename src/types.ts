@@ -1,6 +1,6 @@
 export type StreamStatus = "idle" | "live" | "scheduled" | "completed" | "error" | "stopping";
 
-export type ScheduleType = "manual" | "duration" | "absolute";
+export type ScheduleType = "manual" | "duration" | "absolute" | "plays";
 
 export interface ScheduleConfig {
   type: ScheduleType;
@@ -13,27 +13,316 @@ export interface ScheduleConfig {
     datetime: string;
     timezone: string;
   };
+  plays?: number;
+}
+
+export interface MediaTrim {
+  startSeconds?: number;
+  endSeconds?: number;
+}
+
+export interface ClipConfig {
+  introPath?: string;
+  introTrim?: MediaTrim;
+  outroPath?: string;
+  outroTrim?: MediaTrim;
+  fadeOutSeconds: number;
+}
+
+export interface ResumeConfig {
+  enabled: boolean;
+  rewindSeconds: number;
 }
 
 export interface Stream {
   id: string;
   name: string;
-  youtubeKey: string;
+  channelId: string;
+  channelName: string;
+  maskedKey: string; // The channel's key, e.g. "••••mnop" - the full key only comes from reveal_stream_key
   videoPath: string;
+  trim?: MediaTrim;
+  ingestEndpoints: string[];
+  clips: ClipConfig;
   status: StreamStatus;
+  statusReason?: string | null;
   schedule: ScheduleConfig;
   startedAt?: string;
   stoppedAt?: string;
   createdAt: string;
   elapsedSeconds?: number;
   lastElapsedSeconds?: number;
+  liveSeconds?: number | null; // Live time across automatic restarts
+  lastSeenAt?: string | null; // Last heartbeat of a live run
+  activeEndpoint?: string;
+  resume: ResumeConfig;
+  playbackPosition?: number;
+  tags: string[]; // Groups the stream belongs to, e.g. a client or campaign
+  archivedAt?: string | null; // Set while the stream is in the archive
 }
 
 export interface StreamInput {
   name: string;
-  youtubeKey: string;
+  channelId?: string;
+  channelName?: string; // Name of a channel created for the key, default the stream's
+  youtubeKey?: string; // Without a channel: reuses the key's channel or creates one
   videoPath: string;
+  trim?: MediaTrim;
+  ingestEndpoints?: string[];
+  clips?: ClipConfig;
+  resume?: ResumeConfig;
   schedule: ScheduleConfig;
-  createdAt: string;
+  tags?: string[];
   startImmediately: boolean;
 }
+
+// Fields left out keep their value
+export interface StreamPatch {
+  name?: string;
+  channelId?: string;
+  youtubeKey?: string; // Moves the stream to the key's channel
+  videoPath?: string;
+  trim?: MediaTrim;
+  ingestEndpoints?: string[];
+  clips?: ClipConfig;
+  resume?: ResumeConfig;
+  schedule?: ScheduleConfig;
+  tags?: string[]; // Replaces every tag
+}
+
+export type StreamSort = "createdAt" | "name" | "startedAt" | "status";
+
+// What get_streams returns; every field is optional and a query without a limit returns every match
+export interface StreamQuery {
+  statuses?: StreamStatus[]; // Any of these
+  search?: string; // Stream or channel name, a key's last characters, or a whole key
+  tags?: string[]; // Carrying any of these
+  scheduleTypes?: ScheduleType[];
+  archived?: boolean; // The archive instead of the streams in use
+  createdFrom?: string; // RFC 3339, inclusive
+  createdTo?: string; // RFC 3339, exclusive
+  startedFrom?: string;
+  startedTo?: string;
+  sort?: StreamSort; // Default "createdAt"
+  direction?: "asc" | "desc"; // Default "desc"
+  limit?: number; // At most 500
+  cursor?: string; // nextCursor of the previous page, queried with the same sort and direction
+}
+
+export interface StreamPage {
+  streams: Stream[];
+  total: number; // Matches across all pages
+  nextCursor: string | null; // null on the last page
+}
+
+// Settings streams made from a template share; each stream brings its own name,
+// and its own file or key when the template leaves them out
+export interface TemplateSettings {
+  videoPath?: string | null;
+  trim?: MediaTrim;
+  ingestEndpoints?: string[];
+  clips?: ClipConfig;
+  resume?: ResumeConfig;
+  schedule: ScheduleConfig;
+  tags?: string[];
+}
+
+export interface StreamTemplate extends TemplateSettings {
+  id: string;
+  name: string;
+  channelId: string | null;
+  channelName: string | null;
+  createdAt: string;
+}
+
+export interface TemplateInput extends TemplateSettings {
+  name: string;
+  channelId?: string;
+}
+
+export interface Tag {
+  name: string;
+  streamCount: number;
+}
+
+// What a bulk command did with one stream; "skipped" means it was already as asked
+export type BulkOutcome = "done" | "skipped" | "failed";
+
+export interface BulkResult {
+  streamId: string;
+  name: string;
+  outcome: BulkOutcome;
+  error: CommandError | null;
+}
+
+// What bulk_start, bulk_stop, bulk_reschedule and bulk_delete return for the tag's streams
+export interface BulkReport {
+  tag: string;
+  done: number;
+  skipped: number;
+  failed: number;
+  results: BulkResult[];
+}
+
+// A stream key shared by the streams publishing to the channel
+export interface Channel {
+  id: string;
+  name: string;
+  destination?: string | null; // Ingest endpoint for streams without their own
+  maskedKey: string;
+  notes: string;
+  createdAt: string;
+  streamIds: string[];
+}
+
+export interface ChannelInput {
+  name: string;
+  youtubeKey: string;
+  destination?: string;
+  notes?: string;
+}
+
+// Fields left out keep their value, an empty destination clears it
+export interface ChannelPatch {
+  name?: string;
+  youtubeKey?: string;
+  destination?: string;
+  notes?: string;
+}
+
+export type SessionEndReason = "manual" | "schedule" | "crash" | "endOfInput" | "interrupted";
+
+export type FailureKind = "restartFailed" | "endpointsExhausted" | "stopFailed";
+
+export interface EncoderStats {
+  frames: number;
+  fps?: number | null;
+  bitrateKbps?: number | null;
+  totalSizeBytes: number;
+  droppedFrames: number;
+  duplicatedFrames: number;
+  speed?: number | null;
+}
+
+export interface StreamSession {
+  id: string;
+  streamId: string;
+  startedAt: string;
+  endedAt?: string | null; // Unset while the session is live
+  scheduledEnd?: string | null; // When the schedule was due to stop it
+  endReason?: SessionEndReason | null;
+  failureKind?: FailureKind | null;
+  failureReason?: string | null;
+  elapsedSeconds?: number | null;
+  liveSeconds?: number | null;
+  lastSeenAt?: string | null;
+  restarts: number;
+  restartTimes: string[];
+  encoderStats?: EncoderStats | null;
+  logTail: string[];
+}
+
+export type ReportGrouping = "stream" | "key";
+
+export type ReportFormat = "csv" | "json";
+
+export interface ReportQuery {
+  from: string; // RFC 3339, inclusive
+  to: string; // RFC 3339, exclusive
+  groupBy?: ReportGrouping;
+}
+
+export interface UptimeRow {
+  group: string; // Stream id or stream key
+  streams: string[];
+  sessions: number;
+  liveSeconds: number;
+  scheduledSeconds: number;
+  interruptions: number;
+  mtbfSeconds?: number | null;
+  uptimePercent?: number | null;
+}
+
+export interface UptimeReport {
+  from: string;
+  to: string;
+  groupBy: ReportGrouping;
+  rows: UptimeRow[];
+}
+
+// JSON keeps everything, CSV has flat columns without clip trims
+export type TransferFormat = "csv" | "json";
+
+export interface ExportReport {
+  exported: number;
+  redacted: string[]; // Streams written without their key because it could not be decrypted
+}
+
+// What to do with an imported stream whose name is already taken
+export type ConflictStrategy = "skip" | "overwrite" | "rename";
+
+export interface ImportOptions {
+  dryRun?: boolean; // Validate and report, changing nothing
+  onConflict?: ConflictStrategy;
+}
+
+export type ImportAction = "create" | "overwrite" | "rename" | "skip";
+
+export interface ImportRow {
+  row: number; // 1-based, not counting a CSV header
+  name: string;
+  action: ImportAction;
+  streamId: string | null; // null on a dry run
+  error: CommandError | null; // Why the row was not imported
+}
+
+export interface ImportReport {
+  dryRun: boolean;
+  created: number;
+  overwritten: number;
+  renamed: number;
+  skipped: number;
+  failed: number;
+  rows: ImportRow[];
+}
+
+export type ScheduledAction = "stop";
+
+export type StreamEvent =
+  | { type: "statusChanged"; streamId: string; status: StreamStatus }
+  | { type: "error"; streamId: string; reason: string }
+  | {
+      type: "stats";
+      streamId: string;
+      elapsedSeconds: number;
+      liveSeconds: number;
+      playbackPosition?: number | null;
+      activeEndpoint?: string | null;
+    }
+  | { type: "schedulerArmed"; streamId: string; action: ScheduledAction; firesInSeconds: number }
+  | { type: "schedulerFired"; streamId: string; action: ScheduledAction };
+
+export interface FieldError {
+  field: string; // camelCase path, e.g. "schedule.duration"
+  code: string;
+  params: Record<string, string>;
+  message: string; // In the operator's locale
+}
+
+// What every command rejects with
+export interface CommandError {
+  kind: string; // Stable code, e.g. "duplicateKey"
+  params: Record<string, string>; // Values the message is built from
+  message: string; // In the operator's locale
+  errors: FieldError[]; // Rejected fields when kind is "validation"
+}
+
+// A snapshot of the stream database, taken every few hours and before a restore
+export interface BackupInfo {
+  fileName: string; // What restore_backup takes
+  createdAt: string;
+  sizeBytes: number;
+}
+
+// Language of backend-generated text: errors, failure reasons, reports
+export type Locale = "en" | "vi";